@@ -0,0 +1,548 @@
+use std::fmt::Write;
+
+use crate::backend::parser::{FunctionArg, Node, Pattern, Spanned, Variant};
+use crate::backend::ttype::Type;
+use crate::lexer::TokenType;
+
+/// Renders `program` as an indented tree, one node per line, for `--emit=ast`.
+pub fn dump(program: &[Spanned<Node>]) -> String {
+    let mut out = String::new();
+    for node in program {
+        write_node(&mut out, node, 0);
+    }
+    out
+}
+
+fn line(out: &mut String, depth: usize, text: &str) {
+    let _ = writeln!(out, "{}{text}", "  ".repeat(depth));
+}
+
+fn args(args: &[FunctionArg], variadic: bool) -> String {
+    let mut list: Vec<String> = args
+        .iter()
+        .map(|a| {
+            let r = if a.is_ref { "%" } else { "" };
+            format!("{}: {r}{}", a.name, a.type_hint.debug())
+        })
+        .collect();
+    if variadic {
+        list.push("...".into());
+    }
+    list.join(", ")
+}
+
+fn operator(opr: &TokenType) -> String {
+    match opr {
+        TokenType::Operator(o) | TokenType::Separator(o) | TokenType::Keyword(o) => o.clone(),
+        other => format!("{other:?}"),
+    }
+}
+
+/// `T, U: Trait + Other`, the type parameters with their bounds.
+fn type_params(generics: &[String], bounds: &[(String, Type)]) -> String {
+    let params: Vec<String> = generics
+        .iter()
+        .map(|g| {
+            let traits: Vec<String> = bounds
+                .iter()
+                .filter(|(p, _)| p == g)
+                .map(|(_, t)| t.debug())
+                .collect();
+            if traits.is_empty() {
+                g.clone()
+            } else {
+                format!("{g}: {}", traits.join(" + "))
+            }
+        })
+        .collect();
+    params.join(", ")
+}
+
+/// Doc comments are printed the way they were written, under their item.
+fn write_doc(out: &mut String, depth: usize, doc: &Option<String>) {
+    for text in doc.iter().flat_map(|d| d.lines()) {
+        line(out, depth, format!("/// {text}").trim_end());
+    }
+}
+
+/// One line per variant, `Name(int, str) = 4`.
+fn write_variants(out: &mut String, depth: usize, variants: &[Variant]) {
+    if variants.is_empty() {
+        return;
+    }
+    line(out, depth, "variants:");
+    for v in variants {
+        let mut text = v.name.clone();
+        if !v.payload.is_empty() {
+            let types: Vec<String> = v.payload.iter().map(|t| t.debug()).collect();
+            text += &format!("({})", types.join(", "));
+        }
+        if let Some(value) = v.value {
+            text += &format!(" = {value}");
+        }
+        line(
+            out,
+            depth + 1,
+            &format!("{text} @{}..{}", v.span.start, v.span.end),
+        );
+        write_doc(out, depth + 2, &v.doc);
+    }
+}
+
+/// A pattern as it is written, `Shape::Rect(w, _)` or `1..=9`.
+fn pattern(p: &Pattern) -> String {
+    let literal = |n: &Node| match n {
+        Node::LiteralInt(v, _) => v.clone(),
+        Node::LiteralCh(c) => format!("{c:?}"),
+        Node::LiteralStr(s) => format!("{s:?}"),
+        Node::LiteralBool(b) => b.to_string(),
+        other => format!("{other:?}"),
+    };
+    match p {
+        Pattern::Wildcard => "_".into(),
+        Pattern::Binding(name) => name.clone(),
+        Pattern::Literal(n) => literal(n),
+        Pattern::Range { lo, hi, inclusive } => {
+            let dots = if *inclusive { "..=" } else { ".." };
+            format!("{}{dots}{}", literal(lo), literal(hi))
+        }
+        Pattern::Variant { path, bindings } if bindings.is_empty() => path.join("::"),
+        Pattern::Variant { path, bindings } => {
+            format!("{}({})", path.join("::"), bindings.join(", "))
+        }
+    }
+}
+
+/// A labelled child, e.g. the `cond` of an if statement.
+fn child(out: &mut String, depth: usize, label: &str, node: &Spanned<Node>) {
+    line(out, depth, &format!("{label}:"));
+    write_node(out, node, depth + 1);
+}
+
+fn children(out: &mut String, depth: usize, label: &str, nodes: &[Spanned<Node>]) {
+    if nodes.is_empty() {
+        return;
+    }
+    line(out, depth, &format!("{label}:"));
+    for node in nodes {
+        write_node(out, node, depth + 1);
+    }
+}
+
+/// Function bodies and blocks are `Program`s, print their statements directly.
+fn block(out: &mut String, depth: usize, label: &str, body: &Spanned<Node>) {
+    match &body.node {
+        Node::Program(nodes) => {
+            line(out, depth, &format!("{label}:"));
+            for node in nodes {
+                write_node(out, node, depth + 1);
+            }
+        }
+        _ => child(out, depth, label, body),
+    }
+}
+
+fn write_node(out: &mut String, spanned: &Spanned<Node>, depth: usize) {
+    let span = &spanned.span;
+    let at = format!("@{}..{}", span.start, span.end);
+    match &spanned.node {
+        Node::NULLPTR => line(out, depth, &format!("Null {at}")),
+        Node::Poison => line(out, depth, &format!("Poison {at}")),
+        Node::BREAK => line(out, depth, &format!("Break {at}")),
+        Node::CONTINUE => line(out, depth, &format!("Continue {at}")),
+        Node::LiteralBool(b) => line(out, depth, &format!("Bool {b} {at}")),
+        Node::Intrinsic(name) => line(out, depth, &format!("Intrinsic {name} {at}")),
+        Node::SizeOf(ty) => line(out, depth, &format!("SizeOf {} {at}", ty.debug())),
+        Node::LiteralCh(c) => line(out, depth, &format!("Char {c:?} {at}")),
+        Node::LiteralInt(n, ty) => match ty {
+            Some(ty) => line(out, depth, &format!("Int {n} as {} {at}", ty.debug())),
+            None => line(out, depth, &format!("Int {n} {at}")),
+        },
+        Node::LiteralFloat(n, ty) => match ty {
+            Some(ty) => line(out, depth, &format!("Float {n} as {} {at}", ty.debug())),
+            None => line(out, depth, &format!("Float {n} {at}")),
+        },
+        Node::LiteralStr(s) => line(out, depth, &format!("Str {s:?} {at}")),
+        Node::Token(name, _) => line(out, depth, &format!("Ident {name} {at}")),
+        Node::BinaryExpr { lhs, opr, rhs } => {
+            line(out, depth, &format!("Binary {} {at}", operator(opr)));
+            write_node(out, lhs, depth + 1);
+            write_node(out, rhs, depth + 1);
+        }
+        Node::StructInit { fields } => {
+            line(out, depth, &format!("StructInit {at}"));
+            for field in fields {
+                write_node(out, field, depth + 1);
+            }
+        }
+        Node::Pair { field, value } => {
+            line(out, depth, &format!("Field {field} {at}"));
+            write_node(out, value, depth + 1);
+        }
+        Node::StructStmt {
+            name,
+            fields,
+            meths,
+            statics,
+            doc,
+        } => {
+            line(out, depth, &format!("Struct {name} {at}"));
+            write_doc(out, depth + 1, doc);
+            children(out, depth + 1, "fields", fields);
+            write_methods(out, depth + 1, meths);
+            if let Some(statics) = statics {
+                children(out, depth + 1, "statics", statics);
+            }
+        }
+        Node::GenericStructStmt {
+            name,
+            generics,
+            bounds,
+            fields,
+            meths,
+            statics,
+            doc,
+        } => {
+            line(
+                out,
+                depth,
+                &format!("Struct {name}<{}> {at}", type_params(generics, bounds)),
+            );
+            write_doc(out, depth + 1, doc);
+            children(out, depth + 1, "fields", fields);
+            write_methods(out, depth + 1, meths);
+            if let Some(statics) = statics {
+                children(out, depth + 1, "statics", statics);
+            }
+        }
+        Node::EnumStmt {
+            name,
+            variants,
+            meths,
+            doc,
+        } => {
+            line(out, depth, &format!("Enum {name} {at}"));
+            write_doc(out, depth + 1, doc);
+            write_variants(out, depth + 1, variants);
+            children(out, depth + 1, "methods", meths);
+        }
+        Node::GenericEnumStmt {
+            name,
+            generics,
+            bounds,
+            variants,
+            meths,
+            doc,
+        } => {
+            line(
+                out,
+                depth,
+                &format!("Enum {name}<{}> {at}", type_params(generics, bounds)),
+            );
+            write_doc(out, depth + 1, doc);
+            write_variants(out, depth + 1, variants);
+            children(out, depth + 1, "methods", meths);
+        }
+        Node::Feilds {
+            name,
+            type_hint,
+            doc,
+        } => {
+            line(out, depth, &format!("{name}: {} {at}", type_hint.debug()));
+            write_doc(out, depth + 1, doc);
+        }
+        Node::BundleAccess { base, field } => {
+            line(out, depth, &format!("BundleAccess {at}"));
+            write_node(out, base, depth + 1);
+            write_node(out, field, depth + 1);
+        }
+        Node::BundleStmt { path, alias } => {
+            line(out, depth, &format!("Bundle {path:?} as {alias} {at}"))
+        }
+        Node::NameSpace { alias, body } => {
+            line(out, depth, &format!("Bundle {alias} {at}"));
+            block(out, depth + 1, "body", body);
+        }
+        Node::UnpackStmt { alias, symbols } => line(
+            out,
+            depth,
+            &format!("Unpack {alias} {{ {} }} {at}", symbols.join(", ")),
+        ),
+        Node::Cast { expr, ty } => {
+            line(out, depth, &format!("Cast as {} {at}", ty.debug()));
+            write_node(out, expr, depth + 1);
+        }
+        Node::UnaryExpr { opr, expr } => {
+            line(out, depth, &format!("Unary {} {at}", operator(opr)));
+            write_node(out, expr, depth + 1);
+        }
+        Node::DeRefExpr { expr } => {
+            line(out, depth, &format!("Deref {at}"));
+            write_node(out, expr, depth + 1);
+        }
+        Node::RefExpr { expr } => {
+            line(out, depth, &format!("Ref {at}"));
+            write_node(out, expr, depth + 1);
+        }
+        Node::ExTernStmt {
+            name,
+            args: params,
+            return_type,
+            vardaic,
+            doc,
+        } => {
+            line(
+                out,
+                depth,
+                &format!(
+                    "Extern fn {name}({}): {} {at}",
+                    args(params, *vardaic),
+                    return_type.debug()
+                ),
+            );
+            write_doc(out, depth + 1, doc);
+        }
+        Node::FcCall { params, callee } => {
+            line(out, depth, &format!("Call {at}"));
+            child(out, depth + 1, "callee", callee);
+            children(out, depth + 1, "args", params);
+        }
+        Node::GenericFnCall {
+            callee,
+            generics,
+            args: params,
+        } => {
+            let generics: Vec<String> = generics.iter().map(|g| g.debug()).collect();
+            line(out, depth, &format!("Call <{}> {at}", generics.join(", ")));
+            child(out, depth + 1, "callee", callee);
+            children(out, depth + 1, "args", params);
+        }
+        Node::FnStmt {
+            body,
+            args: params,
+            name,
+            ret_type,
+            vardaic,
+            doc,
+            ..
+        } => {
+            line(
+                out,
+                depth,
+                &format!(
+                    "Fn {name}({}): {} {at}",
+                    args(params, *vardaic),
+                    ret_type.debug()
+                ),
+            );
+            write_doc(out, depth + 1, doc);
+            block(out, depth + 1, "body", body);
+        }
+        Node::GenericFnStmt {
+            generics,
+            bounds,
+            body,
+            args: params,
+            name,
+            ret_type,
+            vardaic,
+            doc,
+            ..
+        } => {
+            line(
+                out,
+                depth,
+                &format!(
+                    "Fn {name}<{}>({}): {} {at}",
+                    type_params(generics, bounds),
+                    args(params, *vardaic),
+                    ret_type.debug()
+                ),
+            );
+            write_doc(out, depth + 1, doc);
+            block(out, depth + 1, "body", body);
+        }
+        Node::FnSig {
+            name,
+            args: params,
+            ret_type,
+            doc,
+        } => {
+            line(
+                out,
+                depth,
+                &format!(
+                    "Fn {name}({}): {} {at}",
+                    args(params, false),
+                    ret_type.debug()
+                ),
+            );
+            write_doc(out, depth + 1, doc);
+        }
+        Node::TraitStmt { name, meths, doc } => {
+            line(out, depth, &format!("Trait {name} {at}"));
+            write_doc(out, depth + 1, doc);
+            children(out, depth + 1, "methods", meths);
+        }
+        Node::ImplStmt {
+            trait_name,
+            target,
+            meths,
+        } => {
+            line(
+                out,
+                depth,
+                &format!("Impl {} for {} {at}", trait_name.debug(), target.debug()),
+            );
+            children(out, depth + 1, "methods", meths);
+        }
+        Node::PluginStatement {
+            name,
+            ret_type,
+            body,
+            targ_type,
+            args: params,
+            ..
+        } => {
+            line(
+                out,
+                depth,
+                &format!(
+                    "Plugin {name} for {}({}): {} {at}",
+                    targ_type.debug(),
+                    args(params, false),
+                    ret_type.debug()
+                ),
+            );
+            block(out, depth + 1, "body", body);
+        }
+        Node::GPluginStatement {
+            generics,
+            name,
+            ret_type,
+            body,
+            targ_type,
+            args: params,
+            ..
+        } => {
+            line(
+                out,
+                depth,
+                &format!(
+                    "Plugin {name}<{}> for {}({}): {} {at}",
+                    generics.join(", "),
+                    targ_type.debug(),
+                    args(params, false),
+                    ret_type.debug()
+                ),
+            );
+            block(out, depth + 1, "body", body);
+        }
+        Node::ForStmt {
+            init,
+            cond,
+            inc,
+            body,
+        } => {
+            line(out, depth, &format!("For {at}"));
+            child(out, depth + 1, "init", init);
+            child(out, depth + 1, "cond", cond);
+            child(out, depth + 1, "inc", inc);
+            block(out, depth + 1, "body", body);
+        }
+        Node::WhileStmt { cond, body } => {
+            line(out, depth, &format!("While {at}"));
+            child(out, depth + 1, "cond", cond);
+            block(out, depth + 1, "body", body);
+        }
+        Node::IfStmt {
+            cond,
+            body,
+            elseifs,
+            elsestmt,
+        } => {
+            line(out, depth, &format!("If {at}"));
+            child(out, depth + 1, "cond", cond);
+            block(out, depth + 1, "then", body);
+            if let Some(elseifs) = elseifs {
+                children(out, depth + 1, "else if", elseifs);
+            }
+            if let Some(elsestmt) = elsestmt {
+                block(out, depth + 1, "else", elsestmt);
+            }
+        }
+        Node::LetStmt {
+            is_mut,
+            name,
+            type_hint,
+            value,
+        } => {
+            let m = if *is_mut { "mut " } else { "" };
+            line(
+                out,
+                depth,
+                &format!("Let {m}{name}: {} {at}", type_hint.debug()),
+            );
+            write_node(out, value, depth + 1);
+        }
+        Node::ListAccess { name, index } => {
+            line(out, depth, &format!("Index {at}"));
+            write_node(out, name, depth + 1);
+            write_node(out, index, depth + 1);
+        }
+        Node::ListInit { content } => {
+            line(out, depth, &format!("List {at}"));
+            for item in content {
+                write_node(out, item, depth + 1);
+            }
+        }
+        Node::MatchExpr { scrutinee, arms } => {
+            line(out, depth, &format!("Match {at}"));
+            write_node(out, scrutinee, depth + 1);
+            for arm in arms {
+                let p = &arm.pattern;
+                line(
+                    out,
+                    depth + 1,
+                    &format!("Arm {} @{}..{}", pattern(&p.node), p.span.start, p.span.end),
+                );
+                if let Some(guard) = &arm.guard {
+                    child(out, depth + 2, "guard", guard);
+                }
+                write_node(out, &arm.body, depth + 2);
+            }
+        }
+        Node::MemberAccess { base, field } => {
+            line(out, depth, &format!("Member .{field} {at}"));
+            write_node(out, base, depth + 1);
+        }
+        Node::Program(nodes) => {
+            line(out, depth, &format!("Block {at}"));
+            for node in nodes {
+                write_node(out, node, depth + 1);
+            }
+        }
+        Node::ReVal { name, value } => {
+            line(out, depth, &format!("Assign {at}"));
+            write_node(out, name, depth + 1);
+            write_node(out, value, depth + 1);
+        }
+        Node::Ret(value) => {
+            line(out, depth, &format!("Ret {at}"));
+            write_node(out, value, depth + 1);
+        }
+    }
+}
+
+fn write_methods(out: &mut String, depth: usize, meths: &[Result<Spanned<Node>, String>]) {
+    if meths.is_empty() {
+        return;
+    }
+    line(out, depth, "methods:");
+    for meth in meths {
+        match meth {
+            Ok(meth) => write_node(out, meth, depth + 1),
+            Err(e) => line(out, depth + 1, &format!("<error: {e}>")),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use super::{
-    codegen::is_builtin, function::Function, ttype::Type, type_table::TTable, var_table::VTable,
+    codegen::is_builtin, enums::Enum, function::Function, parser::Node, parser::Spanned,
+    traits::Trait, ttype::Type, type_table::TTable, var_table::VTable,
 };
 #[derive(Debug, Clone)]
 pub struct Bundle {
@@ -9,6 +10,10 @@ pub struct Bundle {
     pub types: TTable,
     pub bundles: Vec<Bundle>,
     pub path: String,
+    /// Generic structs and functions, instantiated by whoever uses them.
+    pub templates: Vec<Spanned<Node>>,
+    pub traits: Vec<Trait>,
+    pub enums: Vec<Enum>,
 }
 
 impl Bundle {
@@ -27,6 +32,9 @@ impl Bundle {
             types,
             bundles,
             path: p,
+            templates: Vec::new(),
+            traits: Vec::new(),
+            enums: Vec::new(),
         }
     }
     pub fn refuse_dup(&mut self, subject: String) -> Option<Bundle> {
@@ -1,20 +1,22 @@
 use crate::backend::var;
 use crate::lexer::{self, Span, TokenType};
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Write;
 use std::path::{self, Path, PathBuf};
-use std::process::exit;
+use std::rc::Rc;
 use std::str::FromStr;
 
-use colored::Colorize;
-
-use crate::parser::{FunctionArg, Node, Parser, Spanned};
+use crate::parser::{FunctionArg, MatchArm, Node, Parser, Pattern, Spanned, Variant};
+use crate::source_map::{FileId, SourceMap};
 
 use super::bundle::Bundle;
 use super::compile_error::{CompileError, ErrLevel};
 use super::context::Context;
+use super::enums::Enum;
 use super::function::Function;
+use super::generics;
+use super::patterns::{self, Coverage, Kind, Part};
+use super::traits::{self, Trait};
 use super::ttype::Type;
 use super::type_table::TTable;
 use super::var::Var;
@@ -27,7 +29,7 @@ struct FResult {
     preamble: String,
 }
 #[derive(Debug, Clone)]
-pub(crate) struct FieldLayout {
+pub struct FieldLayout {
     ty: Type,
 }
 #[derive(Debug, Clone)]
@@ -35,6 +37,8 @@ pub struct StructLayout {
     pub name: Type,
     pub feilds: HashMap<String, FieldLayout>,
     pub methods: Vec<Function>,
+    /// Ids of the traits implemented, see [`Trait::id`](super::traits::Trait::id).
+    pub traits: Vec<String>,
     pub file: String,
 }
 #[derive(Debug, Clone)]
@@ -53,6 +57,42 @@ struct ExprResult {
     var: Option<String>,
 }
 
+/// A `match` pattern checked against the type of the value it is matched with.
+#[derive(Debug, Clone)]
+struct CheckedPattern {
+    part: Part,
+    /// C condition for the value to match, None if every value does.
+    cond: Option<String>,
+    /// Label of the `switch` case the pattern is, None if it cannot be one.
+    case: Option<String>,
+    /// Names the pattern binds, their types and the C expressions of their values.
+    binds: Vec<(String, Type, String)>,
+}
+
+/// The C code of a `match` arm: the statements of its block, or its expression.
+#[derive(Debug, Clone)]
+enum ArmBody {
+    Block(String),
+    Expr { preamble: String, value: String },
+}
+
+/// A C file produced by the generator, kept in memory until someone writes it out.
+#[derive(Debug, Clone)]
+pub struct GeneratedFile {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+impl GeneratedFile {
+    /// Writes the file to its path, creating missing directories.
+    pub fn write(&self) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, &self.contents)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Section {
     TEXT,
@@ -69,15 +109,23 @@ pub enum BodyType {
 pub struct Generator {
     bss: String,
     pub(crate) buildpath: Box<PathBuf>,
+    /// Alias this file was bundled under, empty for the main program. Read by `JBUNDLE`.
+    bundle_name: String,
     bundled: Vec<String>,
     bundles: Vec<Bundle>,
     cur_body_type: BodyType,
     cur_section: Section,
     current_context: Context,
     current_file: String,
+    /// Qualified name of the function being generated, e.g. `Point::new`. Read by `JFUNC`.
+    current_func: String,
     current_scope_return_type: Type,
     data: String,
+    /// Enums declared in this file, and the instances of generic ones.
+    enums: Vec<Enum>,
     errorbox: Vec<CompileError>,
+    artifacts: Vec<GeneratedFile>,
+    failed_bundles: Vec<String>, /* aliases of bundles that did not load, already reported */
     func: String,
     func_table: Vec<Function>,
     global_context: Context,
@@ -85,12 +133,23 @@ pub struct Generator {
     header: String,
     immediate_counter: u8,
     inputpath: String,
+    /// Names of the generic instances generated so far, finished or not.
+    instances: Vec<String>,
+    /// Finished instances of generic functions.
+    fn_instances: Vec<Function>,
+    /// Generic instances as they are written in the source, `opt<int>`, by their C name.
+    instance_sources: HashMap<String, String>,
+    /// Accept integer conditions in if/while/for with a warning instead of an error.
+    pub int_conditions: bool,
     is_included: bool,
     pub(crate) outfilename: String,
     pub(crate) outfilepath: String,
     pub source: Vec<Spanned<Node>>,
-    source_code: String,
+    sources: Rc<RefCell<SourceMap>>,
+    /// Generic structs and functions declared in this file.
+    templates: Vec<Spanned<Node>>,
     pub text: String,
+    traits: Vec<Trait>,
     track_rsp: bool,
     types: TTable,
     var_table: VTable, // Validating return statements
@@ -100,7 +159,7 @@ impl Generator {
     pub fn new(
         source: Vec<Spanned<Node>>,
         path: &str,
-        input: String,
+        sources: Rc<RefCell<SourceMap>>,
         is_included: bool,
         inputpath: String,
         current_file: String,
@@ -125,24 +184,35 @@ impl Generator {
             cur_section: Section::TEXT,
             track_rsp: false,
             errorbox: vec![],
+            artifacts: vec![],
+            failed_bundles: vec![],
             outfilename: Path::new(path)
                 .file_name()
                 .unwrap()
                 .to_str()
                 .unwrap()
                 .to_string(),
-            source_code: input.clone(),
+            sources,
             has_error: false,
             types: base,
             global_context: global_context.clone(),
             current_context: global_context,
             bundles: Vec::new(),
+            instances: Vec::new(),
+            fn_instances: Vec::new(),
+            instance_sources: HashMap::new(),
+            templates: Vec::new(),
+            traits: Vec::new(),
+            enums: Vec::new(),
             is_included,
+            int_conditions: false,
             inputpath,
             current_file,
             bundled: vec![],
             buildpath: Box::new(Path::new(&builddir).to_path_buf()),
             current_scope_return_type: Type::NoType,
+            current_func: String::new(),
+            bundle_name: String::new(),
         }
     }
     pub fn change_scope(&mut self, name: &str) {
@@ -166,14 +236,13 @@ impl Generator {
         if self.is_included {
             self.emit("#pragma once\n");
         }
-        /* Path to Runtime and generic helper header */
-        self.emit(&format!(
-            "\n#include \"/home/dry/Documents/Eggo/jaguar/std/claw.h\"",
-        ));
+        /* Runtime and generic helper header, found through the runtime include path */
+        self.emit("\n#include \"claw.h\"");
         self.cur_section = Section::TEXT;
     }
     pub fn generate(&mut self, source: Vec<Spanned<Node>>) {
-        for node in source.clone() {
+        for mut node in source.clone() {
+            self.instantiate_types(&mut node);
             match node.clone().node {
                 Node::LetStmt {
                     name: _,
@@ -200,14 +269,14 @@ impl Generator {
                     self.emit(";");
                 }
                 Node::BundleStmt { path, alias } => {
-                    /* append var:path to the parent path of the main file */
-                    let p_import_path = Path::new(&self.inputpath)
+                    /* append var:path to the parent path of the current file */
+                    let import_path = Path::new(&self.inputpath)
                         .parent()
                         .unwrap()
+                        .join(&path)
                         .to_str()
                         .unwrap()
                         .to_string();
-                    let import_path = format!("{}/{}", p_import_path, path);
                     if !Path::exists(Path::new(import_path.as_str())) {
                         self.consume(CompileError::new(
                             format!("Could not resolve {}", path),
@@ -215,24 +284,18 @@ impl Generator {
                             node.span,
                             ErrLevel::ERROR,
                         ));
-                        self.flush();
-                        exit(1);
+                        self.failed_bundles.push(alias.clone());
+                        continue;
                     }
                     let source = std::fs::read_to_string(import_path.clone()).unwrap();
+                    let file = self
+                        .sources
+                        .borrow_mut()
+                        .add(import_path.clone(), source.clone());
                     #[allow(unused_assignments)]
                     let mut output = import_path.clone();
-                    let mut tokenizer = lexer::Tokenizer::new(&source);
-                    let mut tokens = Vec::new();
-                    loop {
-                        let tok = tokenizer.next_token();
-                        if let TokenType::Comment(_) = tok.kind {
-                            continue;
-                        }
-                        tokens.push(tok.clone());
-                        if tok.kind == lexer::TokenType::EOF {
-                            break;
-                        }
-                    }
+                    let mut tokenizer = lexer::Tokenizer::new(&source, file);
+                    let tokens = tokenizer.tokenize();
                     let mut parser = Parser::new(tokens, source.clone());
                     let final_output = format!("{}.h", import_path);
                     let p = std::path::Path::new(&import_path)
@@ -254,23 +317,39 @@ impl Generator {
                     if found {
                         continue;
                     }
-                    match parser.parse_program() {
+                    let program = parser.parse_program();
+                    /* spans carry the bundle's file id, so they can sit with ours */
+                    self.errorbox.extend_from_slice(tokenizer.diagnostics());
+                    self.errorbox.extend_from_slice(parser.diagnostics());
+                    if parser.has_errors() || !tokenizer.diagnostics().is_empty() {
+                        self.has_error = true;
+                        self.failed_bundles.push(alias.clone());
+                        continue;
+                    }
+                    match program {
                         program => {
                             let mut cgen = Generator::new(
                                 program,
                                 &output,
-                                source,
+                                self.sources.clone(),
                                 true,
-                                path.clone(),
+                                import_path.clone(),
                                 std::fs::canonicalize(path::Path::new(&import_path))
                                     .ok()
                                     .map(|p| p.to_string_lossy().to_string())
                                     .unwrap(),
                                 self.buildpath.to_str().unwrap().to_string(),
                             );
+                            cgen.int_conditions = self.int_conditions;
+                            cgen.bundle_name = alias.clone();
                             cgen.init();
                             cgen.generate(cgen.source.clone());
                             cgen.rest();
+                            if cgen.has_errors() {
+                                self.has_error = true;
+                            }
+                            self.errorbox.append(&mut cgen.errorbox);
+                            self.artifacts.append(&mut cgen.artifacts);
                             self.bundled.append(&mut cgen.bundled.clone());
                             let mut new_bundle = Bundle::new(
                                 alias.clone(),
@@ -280,6 +359,9 @@ impl Generator {
                                 cgen.bundles,
                                 final_output.clone(),
                             );
+                            new_bundle.templates = cgen.templates;
+                            new_bundle.traits = cgen.traits;
+                            new_bundle.enums = cgen.enums;
                             new_bundle.types.wrap(alias.clone());
                             new_bundle.wrap(&alias.clone());
                             self.bundles.push(new_bundle);
@@ -303,11 +385,13 @@ impl Generator {
                     fields,
                     meths,
                     statics,
+                    doc,
                 } => {
                     let layout: StructLayout;
                     let mut b_fields: HashMap<String, FieldLayout> = HashMap::new();
                     let save = self.cur_section.clone();
                     self.cur_section = Section::HEADER;
+                    self.emit(&c_doc(&doc, ""));
                     self.emit(format!("\ntypedef struct {name} {{\n").as_str());
 
                     let mut encountered_fields: Vec<(String, Span)> = vec![];
@@ -315,20 +399,21 @@ impl Generator {
                         if let Node::Feilds {
                             name: fname,
                             type_hint,
+                            doc,
                         } = field.node.clone()
                         {
                             if self.get_layout(type_hint.clone()).is_some() {
+                                self.emit(&c_doc(&doc, "\t"));
                                 self.emit(format!("\n\t{} {fname};", type_hint.to_str()).as_str());
                                 b_fields.insert(fname.clone(), FieldLayout { ty: type_hint });
                                 encountered_fields.push((fname, field.span.clone()));
-                            } else {
+                            } else if type_hint != Type::Poison {
                                 self.consume(CompileError::new(
                                     format!("Not a type, {}", type_hint.debug()),
                                     None,
                                     field.span.clone(),
                                     ErrLevel::ERROR,
                                 ));
-                                self.flush();
                             }
                         }
                     }
@@ -336,128 +421,13 @@ impl Generator {
                         name: Type::Custom(name.clone()),
                         feilds: b_fields.clone(),
                         methods: Vec::new(),
+                        traits: Vec::new(),
                         file: self.current_file.clone(),
                     };
                     self.types.add_type(Type::Custom(name.clone()), layout);
                     self.emit(format!("}} {name};").as_str());
-                    for m in meths.clone() {
-                        if let Spanned {
-                            node:
-                                Node::FnStmt {
-                                    body,
-                                    args,
-                                    name: fname,
-                                    ret_type,
-                                    returns,
-                                    return_val: _,
-                                    vardaic: _,
-                                    mangled_name: _,
-                                },
-                            span: _,
-                        } = m.clone().unwrap()
-                        {
-                            let targ_type = Type::Custom(name.clone());
-                            let layout = self.get_layout(targ_type.clone());
-                            if matches!(layout, None) {
-                                println!(
-                                    "ToDo Err system: Not a type {}",
-                                    targ_type.debug().clone()
-                                );
-                                exit(1);
-                            }
-                            match layout
-                                .as_ref()
-                                .unwrap()
-                                .clone()
-                                .methods
-                                .clone()
-                                .iter()
-                                .find(|m| m.get_name() == fname.clone())
-                            {
-                                Some(_plug) => {
-                                    println!(
-                                        "ToDo Err system: Plugin {} already exsist for type {}",
-                                        fname.clone(),
-                                        targ_type.debug().clone()
-                                    );
-                                    exit(1);
-                                }
-                                None => (),
-                            }
-                            let mut context = Context::new(
-                                name.clone(),
-                                Some(Box::new(self.current_context.clone())),
-                            );
-                            self.emit(&format!(
-                                "\nextern inline {} {}_{}(",
-                                ret_type.clone().c_impl(),
-                                targ_type.c_impl(),
-                                fname.clone()
-                            ));
-                            for (i, a) in args.clone().iter_mut().enumerate() {
-                                let mut modif = "";
-                                if a.name == "self" {
-                                    if a.type_hint == Type::NoType {
-                                        modif = "*";
-                                        a.type_hint = targ_type.clone();
-                                    }
-                                }
-                                self.emit(&format!(
-                                    "{}{modif} {}",
-                                    a.type_hint.clone().to_str(),
-                                    a.name.clone()
-                                ));
-                                if i != args.clone().len() - 1 {
-                                    self.emit(",");
-                                }
-                                context.add(Var::new(
-                                    a.name.clone(),
-                                    a.type_hint.clone(),
-                                    a.is_ref,
-                                    None,
-                                    m.clone().unwrap().span,
-                                ));
-                            }
-                            self.emit(");");
-                            let mut f = Function::new(
-                                fname.clone(),
-                                context,
-                                ret_type.clone(),
-                                returns,
-                                body.clone().node,
-                            );
-                            f.args = args;
-                            self.register_plugin(Type::Custom(name.clone()), f);
-                        }
-                    }
-                    for meth in meths.clone() {
-                        if let Spanned {
-                            node:
-                                Node::FnStmt {
-                                    body,
-                                    args,
-                                    name: fname,
-                                    ret_type,
-                                    returns: _,
-                                    return_val,
-                                    vardaic: _,
-                                    mangled_name: _,
-                                },
-                            span,
-                        } = meth.clone().unwrap().clone()
-                        {
-                            let p = Node::PluginStatement {
-                                name: fname.clone(),
-                                ret_val: return_val,
-                                ret_type: Box::new(ret_type.clone()),
-                                body: body.clone(),
-                                targ_type: Type::Custom(name.clone()),
-                                args: args.clone(),
-                            };
-
-                            self.generate(vec![Spanned { node: p, span }]);
-                        }
-                    }
+                    let meths = meths.into_iter().flatten().collect();
+                    self.gen_plugins(Type::Custom(name.clone()), meths);
                     if statics.is_some() {
                         let functions = statics.unwrap();
                         let body = self.convert_vecnode_nodeprogram(functions);
@@ -465,7 +435,7 @@ impl Generator {
                         let o = self.gen_expr(
                             Box::new(Spanned {
                                 node: type_bundle,
-                                span: Span { start: 0, end: 0 },
+                                span: node.span.clone(),
                             }),
                             Type::Any,
                             RefStyle::COPY,
@@ -505,8 +475,9 @@ impl Generator {
                     args: params,
                     return_type,
                     vardaic,
+                    doc,
                 } => {
-                    let mut stream = String::new();
+                    let mut stream = c_doc(&doc, "");
                     let mut context = Context::new(name.clone(), None);
                     stream += format!("\nextern {} {name} (", return_type.to_str()).as_str();
                     for (i, arg) in params.clone().iter().enumerate() {
@@ -563,6 +534,7 @@ impl Generator {
                     return_val: _,
                     vardaic: _,
                     mangled_name: _,
+                    doc: _,
                 } => {
                     let out = self.gen_expr(Box::new(node.clone()), Type::Any, RefStyle::COPY);
                     self.emit(out.stream.as_str());
@@ -578,6 +550,76 @@ impl Generator {
                     let o = self.gen_expr(Box::new(node), Type::Any, RefStyle::COPY);
                     self.emit(&o.stream);
                 }
+                Node::GenericStructStmt { ref bounds, .. }
+                | Node::GenericEnumStmt { ref bounds, .. }
+                | Node::GenericFnStmt { ref bounds, .. } => {
+                    for (_, bound) in bounds {
+                        if self.find_trait(bound).is_none() {
+                            self.consume(CompileError::new(
+                                format!("No trait named '{}'", bound.debug()),
+                                None,
+                                node.span.clone(),
+                                ErrLevel::ERROR,
+                            ));
+                        }
+                    }
+                    /* generated per use, see instantiate_struct and instantiate_fn */
+                    self.templates.push(node);
+                }
+                Node::EnumStmt {
+                    name,
+                    variants,
+                    meths,
+                    doc,
+                } => {
+                    let save = self.cur_section.clone();
+                    self.gen_enum(name, variants, meths, doc);
+                    self.cur_section = save;
+                }
+                Node::TraitStmt { name, meths, .. } => {
+                    if self.traits.iter().any(|t| t.name == name) {
+                        self.consume(CompileError::new(
+                            format!("Trait '{name}' is declared twice"),
+                            None,
+                            node.span.clone(),
+                            ErrLevel::ERROR,
+                        ));
+                        continue;
+                    }
+                    let mut seen: Vec<String> = vec![];
+                    for meth in &meths {
+                        let (Node::FnSig { name: fname, .. } | Node::FnStmt { name: fname, .. }) =
+                            &meth.node
+                        else {
+                            continue;
+                        };
+                        if seen.contains(fname) {
+                            self.consume(CompileError::new(
+                                format!("Method '{fname}' is declared twice in trait '{name}'"),
+                                None,
+                                meth.span.clone(),
+                                ErrLevel::ERROR,
+                            ));
+                        }
+                        seen.push(fname.clone());
+                    }
+                    let tr = Trait {
+                        name,
+                        file: self.current_file.clone(),
+                        meths,
+                    };
+                    self.gen_dyn(&tr);
+                    self.traits.push(tr);
+                }
+                Node::ImplStmt {
+                    trait_name,
+                    target,
+                    meths,
+                } => {
+                    let save = self.cur_section.clone();
+                    self.gen_impl(trait_name, target, meths, node.span.clone());
+                    self.cur_section = save;
+                }
                 Node::UnpackStmt { alias, symbols } => {
                     /* pull symbols from 'symbols' into the global context */
                     let bndl = self.bundles.iter().find(|b| b.name == alias).cloned();
@@ -588,7 +630,7 @@ impl Generator {
                             node.clone().span,
                             ErrLevel::ERROR,
                         ));
-                        self.flush();
+                        continue;
                     }
                     let mut b = bndl.clone().unwrap();
                     for sym in symbols.clone() {
@@ -599,7 +641,6 @@ impl Generator {
                                 self.consume(CompileError::new(
                                     format!("Conflicting symbol {sym}. Function with this name already exists in the scope"), None, node.clone().span, ErrLevel::ERROR
                                 ));
-                                self.flush();
                             }
                             self.func_table.push(f.unwrap().clone());
                             continue;
@@ -611,7 +652,6 @@ impl Generator {
                                 self.consume(CompileError::new(
                                     format!("Conflicting symbol {sym}. Bundle with this alias already exists in the scope"), None, node.clone().span, ErrLevel::ERROR
                                 ));
-                                self.flush();
                             }
                             self.bundles.push(bn.unwrap().clone());
                             continue;
@@ -623,19 +663,35 @@ impl Generator {
                                 self.consume(CompileError::new(
                                     format!("Conflicting symbol {sym}. Variable with this name already exists in the scope"), None, node.clone().span, ErrLevel::ERROR
                                 ));
-                                self.flush();
                             }
                             self.current_context.add(v.unwrap().clone());
                             continue;
                         }
+                        if let Some(tr) = b.traits.iter().find(|t| t.name == sym) {
+                            if self.traits.iter().any(|t| t.name == sym) {
+                                self.consume(CompileError::new(
+                                    format!("Conflicting symbol {sym}. Trait with this name already exists in the scope"), None, node.clone().span, ErrLevel::ERROR
+                                ));
+                            }
+                            let bound = Type::BundledType {
+                                bundle: alias.clone(),
+                                ty: Box::new(Type::Custom(sym.clone())),
+                            };
+                            let mut tr = self.find_trait(&bound).unwrap_or(tr.clone());
+                            tr.name = sym.clone();
+                            self.traits.push(tr);
+                            continue;
+                        }
                         let mut t = b.types.get_layout(Type::Custom(sym.clone()));
                         let tt = self.types.get_layout(Type::Custom(sym.clone()));
+                        if let Some(en) = b.enums.iter().find(|e| e.name == sym) {
+                            self.enums.push(en.qualified(&alias));
+                        }
                         if t.is_some() {
                             if tt.is_some() {
                                 self.consume(CompileError::new(
                                     format!("Conflicting symbol {sym}. Type with this name already exists in the scope"), None, node.clone().span, ErrLevel::ERROR
                                 ));
-                                self.flush();
                             }
                             t.as_mut().unwrap().name = Type::Custom(sym.clone());
                             self.types
@@ -648,7 +704,6 @@ impl Generator {
                             node.clone().span,
                             ErrLevel::ERROR,
                         ));
-                        self.flush();
                     }
                 }
                 _ => {
@@ -661,20 +716,169 @@ impl Generator {
             }
         }
     }
+    /// Declares the methods `meths` of `targ_type`, then generates them. Used for the
+    /// methods of a struct and for those of an `impl` block.
+    fn gen_plugins(&mut self, targ_type: Type, meths: Vec<Spanned<Node>>) {
+        self.cur_section = Section::HEADER;
+        for m in meths.clone() {
+            if let Spanned {
+                node:
+                    Node::FnStmt {
+                        body,
+                        args,
+                        name: fname,
+                        ret_type,
+                        returns,
+                        return_val: _,
+                        vardaic: _,
+                        mangled_name: _,
+                        doc: _,
+                    },
+                span: _,
+            } = m.clone()
+            {
+                let layout = self.get_layout(targ_type.clone());
+                if matches!(layout, None) {
+                    self.consume(CompileError::new(
+                        format!("Not a type {}", targ_type.debug()),
+                        None,
+                        m.span.clone(),
+                        ErrLevel::ERROR,
+                    ));
+                    continue;
+                }
+                match layout
+                    .as_ref()
+                    .unwrap()
+                    .clone()
+                    .methods
+                    .clone()
+                    .iter()
+                    .find(|m| m.get_name() == fname.clone())
+                {
+                    Some(_plug) => {
+                        self.consume(CompileError::new(
+                            format!(
+                                "Plugin {} already exsist for type {}",
+                                fname.clone(),
+                                targ_type.debug()
+                            ),
+                            None,
+                            m.span.clone(),
+                            ErrLevel::ERROR,
+                        ));
+                        continue;
+                    }
+                    None => (),
+                }
+                let mut context = Context::new(
+                    targ_type.c_impl(),
+                    Some(Box::new(self.current_context.clone())),
+                );
+                self.emit(&format!(
+                    "\nextern inline {} {}_{}(",
+                    ret_type.clone().c_impl(),
+                    targ_type.c_impl(),
+                    fname.clone()
+                ));
+                for (i, a) in args.clone().iter_mut().enumerate() {
+                    let mut modif = "";
+                    let mut c_type = a.type_hint.to_str();
+                    if a.name == "self" {
+                        if a.type_hint == Type::NoType {
+                            modif = "*";
+                            a.type_hint = targ_type.clone();
+                            /* the method may change the struct it is called on */
+                            c_type = targ_type.c_impl();
+                        }
+                    }
+                    self.emit(&format!("{c_type}{modif} {}", a.name.clone()));
+                    if i != args.clone().len() - 1 {
+                        self.emit(",");
+                    }
+                    context.add(Var::new(
+                        a.name.clone(),
+                        a.type_hint.clone(),
+                        a.is_ref,
+                        None,
+                        m.span.clone(),
+                    ));
+                }
+                self.emit(");");
+                let mut f = Function::new(
+                    fname.clone(),
+                    context,
+                    ret_type.clone(),
+                    returns,
+                    body.clone().node,
+                );
+                f.args = args;
+                self.register_plugin(targ_type.clone(), f);
+            }
+        }
+        for meth in meths {
+            if let Spanned {
+                node:
+                    Node::FnStmt {
+                        body,
+                        args,
+                        name: fname,
+                        ret_type,
+                        returns: _,
+                        return_val,
+                        vardaic: _,
+                        mangled_name: _,
+                        doc: _,
+                    },
+                span,
+            } = meth
+            {
+                let p = Node::PluginStatement {
+                    name: fname.clone(),
+                    ret_val: return_val,
+                    ret_type: Box::new(ret_type.clone()),
+                    body: body.clone(),
+                    targ_type: targ_type.clone(),
+                    args: args.clone(),
+                };
+
+                self.generate(vec![Spanned { node: p, span }]);
+            }
+        }
+    }
     pub fn rest(&mut self) {
-        self.flush();
+        if self.errorbox.iter().any(|e| e.is_error()) {
+            self.has_error = true;
+        }
         if !self.is_included {
             self.outfilename = format!("{}.c", self.outfilename);
         } else {
             self.outfilename = format!("{}", self.outfilename);
         }
         self.outfilename = format!("{}/{}", self.buildpath.to_str().unwrap(), self.outfilename);
-        let mut outfile = File::create(self.outfilename.clone()).unwrap();
-        outfile.write(self.bss.as_bytes()).unwrap();
-        outfile.write(self.data.as_bytes()).unwrap();
-        outfile.write(self.header.as_bytes()).unwrap();
-        outfile.write(self.text.as_bytes()).unwrap();
-        outfile.write(self.func.as_bytes()).unwrap();
+        let contents = [&self.bss, &self.data, &self.header, &self.text, &self.func]
+            .map(|s| s.as_str())
+            .concat();
+        /* our own file goes before the headers of the bundles it pulled in */
+        self.artifacts.insert(
+            0,
+            GeneratedFile {
+                path: PathBuf::from(&self.outfilename),
+                contents,
+            },
+        );
+    }
+    pub fn has_errors(&self) -> bool {
+        self.has_error
+    }
+    /// Errors and warnings of this file and every bundle it loaded.
+    pub fn diagnostics(&self) -> &[CompileError] {
+        &self.errorbox
+    }
+    /// Generated C: this file's output first, then one header per bundle.
+    /// Empty until [`Generator::rest`] has run.
+    pub fn artifacts(&self) -> &[GeneratedFile] {
+        &self.artifacts
     }
     fn name_mangler(&mut self, input: String) -> String {
         let _ = input;
@@ -688,17 +892,32 @@ impl Generator {
             .to_string();
         format!("{_prefix}_{_path}_{_namespace}_{input}")
     }
+    /// Generates `expression`. Where a `dyn Trait` is expected, a pointer to a value of a
+    /// type implementing the trait is turned into one.
     fn gen_expr(
         &mut self,
         expression: Box<Spanned<Node>>,
         target_type: Type,
         is_ref: RefStyle,
+    ) -> ExprResult {
+        let span = expression.span.clone();
+        let out = self.gen_value(expression, target_type.clone(), is_ref);
+        match dyn_trait(&target_type) {
+            Some(tr) => self.make_dyn(out, tr, &span),
+            None => out,
+        }
+    }
+    fn gen_value(
+        &mut self,
+        expression: Box<Spanned<Node>>,
+        target_type: Type,
+        is_ref: RefStyle,
     ) -> ExprResult {
         let mut stream = String::new();
         let v_is_ref = false;
         let expr = expression.as_ref();
         match expr.node.clone() {
-            Node::LiteralInt(num) => {
+            Node::LiteralInt(num, suffix) => {
                 if (is_int(target_type.clone())) || (target_type == Type::Any) {
                     match is_ref {
                         RefStyle::DEREF => {
@@ -708,7 +927,6 @@ impl Generator {
                                 expr.clone().span,
                                 ErrLevel::ERROR,
                             ));
-                            self.flush();
                         }
                         RefStyle::REF => {
                             self.consume(CompileError::new(
@@ -717,19 +935,158 @@ impl Generator {
                                 expr.clone().span,
                                 ErrLevel::ERROR,
                             ));
-                            self.flush();
                         }
                         _ => {}
                     }
                 }
-                self.check_overflow(num.parse::<i128>().unwrap(), target_type, expr.clone().span);
-                stream.push_str(format!("{}", num).as_str());
+                let mut expected = target_type;
+                while let Type::MUT(inner) = expected {
+                    expected = *inner;
+                }
+                /* a suffix fixes the literal's type, otherwise it takes whatever is expected */
+                let literal_type = match suffix {
+                    Some(ty) => {
+                        /* int is i32 */
+                        let want = match expected {
+                            Type::INT => Type::I32,
+                            ref other => other.clone(),
+                        };
+                        if is_int(expected.clone()) && want != ty {
+                            self.consume(CompileError::new(
+                                format!(
+                                    "mismatched type. literal is {} but {} is expected",
+                                    ty.debug(),
+                                    expected.debug()
+                                ),
+                                Some(format!("Change the suffix to {}", expected.debug())),
+                                expr.clone().span,
+                                ErrLevel::ERROR,
+                            ));
+                        }
+                        ty
+                    }
+                    None => Type::INT,
+                };
+                /* a literal with nothing to go to is an int, and has to fit one */
+                let checked = if literal_type == Type::INT && is_int(expected.clone()) {
+                    expected
+                } else {
+                    literal_type.clone()
+                };
+                let value = num.parse::<i128>().unwrap();
+                self.check_overflow(value, checked.clone(), expr.clone().span);
+                stream.push_str(&num);
+                /* C reads big decimal constants as signed, keep 64-bit values 64-bit */
+                if checked == Type::U64 {
+                    stream.push_str("ULL");
+                } else if value > i32::MAX as i128 {
+                    stream.push_str("LL");
+                }
+                return ExprResult {
+                    preamble: String::new(),
+                    stream,
+                    is_ref: false,
+                    refed_var: None,
+                    type_hint: Box::new(literal_type),
+                    var: None,
+                };
+            }
+            Node::MatchExpr { scrutinee, arms } => {
+                return self.gen_match(scrutinee, arms, target_type);
+            }
+            Node::Intrinsic(name) => {
+                /* resolved from the span, so they point into bundles and methods correctly */
+                let sources = self.sources.borrow();
+                let file = sources.file(&expr.span);
+                let (line, column) = file.line_column(expr.span.start);
+                let node = match name.as_str() {
+                    "JLINE" => Node::LiteralInt(line.to_string(), None),
+                    "JCOLUMN" => Node::LiteralInt(column.to_string(), None),
+                    "JFILE" => Node::LiteralStr(file.name.clone()),
+                    "JFUNC" => Node::LiteralStr(self.current_func.clone()),
+                    _ => Node::LiteralStr(self.bundle_name.clone()),
+                };
+                drop(sources);
+                let literal = Spanned {
+                    node,
+                    span: expr.span.clone(),
+                };
+                return self.gen_expr(Box::new(literal), target_type, is_ref);
+            }
+            Node::LiteralBool(value) => {
+                if matches!(is_ref, RefStyle::REF) {
+                    self.consume(CompileError::new(
+                        format!("Cannot reference an immediate value"),
+                        Some("Consider Removing the & operator".to_owned()),
+                        expr.clone().span,
+                        ErrLevel::ERROR,
+                    ));
+                } else if matches!(is_ref, RefStyle::DEREF) {
+                    self.consume(CompileError::new(
+                        format!("Cannot dereference an immediate value"),
+                        Some("Consider Removing the * operator".to_owned()),
+                        expr.clone().span,
+                        ErrLevel::ERROR,
+                    ));
+                }
+                return ExprResult {
+                    preamble: String::new(),
+                    stream: if value { "1" } else { "0" }.to_owned(),
+                    is_ref: false,
+                    refed_var: None,
+                    type_hint: Box::new(Type::BOOL),
+                    var: None,
+                };
+            }
+            Node::LiteralFloat(num, suffix) => {
+                if matches!(is_ref, RefStyle::REF) {
+                    self.consume(CompileError::new(
+                        format!("Cannot reference an immediate value"),
+                        Some("Consider Removing the & operator".to_owned()),
+                        expr.clone().span,
+                        ErrLevel::ERROR,
+                    ));
+                } else if matches!(is_ref, RefStyle::DEREF) {
+                    self.consume(CompileError::new(
+                        format!("Cannot dereference an immediate value"),
+                        Some("Consider Removing the * operator".to_owned()),
+                        expr.clone().span,
+                        ErrLevel::ERROR,
+                    ));
+                }
+                let mut expected = target_type;
+                while let Type::MUT(inner) = expected {
+                    expected = *inner;
+                }
+                /* a suffix fixes the width, otherwise it is the expected one or f64.
+                 * a mismatched suffix is reported by whoever checks the expected type */
+                let literal_type = match suffix {
+                    Some(ty) => ty,
+                    None if is_float(expected.clone()) => expected,
+                    None => Type::F64,
+                };
+                stream.push_str(&num);
+                if literal_type == Type::F32 {
+                    if num.parse::<f64>().unwrap().abs() > f32::MAX as f64 {
+                        self.consume(CompileError::new(
+                            format!("f32 overflow. value {num} becomes infinity"),
+                            Some(format!(
+                                "Use from range {:e}..{:e} for type f32",
+                                f32::MIN,
+                                f32::MAX
+                            )),
+                            expr.clone().span,
+                            ErrLevel::WARNING,
+                        ));
+                    }
+                    stream.push('f');
+                }
                 return ExprResult {
                     preamble: String::new(),
                     stream,
                     is_ref: false,
                     refed_var: None,
-                    type_hint: Box::new(Type::INT),
+                    type_hint: Box::new(literal_type),
                     var: None,
                 };
             }
@@ -751,13 +1108,14 @@ impl Generator {
                 }
                 let save = self.cur_section.clone();
                 self.cur_section = save;
+                let literal = c_string_literal(&value);
                 return ExprResult {
                     preamble: String::new(),
-                    stream: format!("\"{value}\""),
+                    stream: literal.clone(),
                     is_ref: false,
                     refed_var: None,
                     type_hint: Box::new(Type::MUT(Box::new(Type::STR))),
-                    var: Some(format!("(const char*)\"{value}\"")),
+                    var: Some(format!("(const char*){literal}")),
                 };
             }
             Node::LiteralCh(value) => {
@@ -780,11 +1138,14 @@ impl Generator {
                 self.cur_section = save;
                 return ExprResult {
                     preamble: String::new(),
-                    stream: format!("\'{value}\'"),
+                    stream: c_char_literal(value),
                     is_ref: false,
                     refed_var: None,
                     type_hint: Box::new(Type::CHAR),
-                    var: Some(format!("(const char*)\"{value}\"")),
+                    var: Some(format!(
+                        "(const char*){}",
+                        c_string_literal(&value.to_string())
+                    )),
                 };
             }
 
@@ -794,27 +1155,42 @@ impl Generator {
                 if !is_generic {
                     fix = "";
                 }
+                let mut list_target = target_type.clone();
+                while let Type::MUT(inner) = list_target {
+                    list_target = *inner;
+                }
+                /* a list literal takes its element type and size from where it goes */
+                let Type::List(mut list_type, list_size) = list_target.clone() else {
+                    if list_target != Type::Poison {
+                        self.consume(CompileError::new(
+                            "Cannot tell the type of this list".to_string(),
+                            Some("give it one, e.g. 'let xs: list<int, 2> = [1, 2];'".to_string()),
+                            expr.span.clone(),
+                            ErrLevel::ERROR,
+                        ));
+                    }
+                    return Self::poisoned();
+                };
+                let Ok(capacity) = list_size.parse::<usize>() else {
+                    return Self::poisoned();
+                };
                 let save = self.cur_section.clone();
                 self.cur_section = Section::HEADER;
-                let mut list_type = Box::new(Type::NoType);
-                let mut list_size = String::new();
-                if let Type::List(t, n) = target_type.clone() {
-                    list_type = t.clone();
-                    list_size = n.clone();
-                    self.emit(format!("\njaguar_list({}, {});\n", t.to_str(), n).as_str());
-                    self.cur_section = save;
-                }
+                self.emit(
+                    format!("\njaguar_list({}, {});\n", list_type.c_impl(), list_size).as_str(),
+                );
+                self.cur_section = save;
                 stream += "{.data = {";
+                let element_type = *list_type.clone();
                 for (i, expr) in content.iter().enumerate() {
-                    let out = self.gen_expr(expr.clone(), Type::Any, is_ref.clone());
+                    let out = self.gen_expr(expr.clone(), element_type.clone(), is_ref.clone());
                     list_type = out.type_hint.clone();
                     stream += out.stream.clone().as_str();
                     if i != content.len() - 1 {
                         stream += ",";
                     }
-                    if i >= (list_size.parse::<u32>().unwrap()) as usize {
+                    if i >= capacity {
                         self.consume(CompileError::new(format!("Excess elements passed to array initializer. expected only {} but got {}+", list_size.clone(), i), None,expr.clone().span , ErrLevel::ERROR));
-                        self.flush();
                     }
                 }
                 stream += format!("}}, .len = {list_size}}}{fix}").as_str();
@@ -832,10 +1208,24 @@ impl Generator {
                 match name.node.clone() {
                     Node::MemberAccess { base, field } => {
                         let b = self.gen_expr(name.clone(), target_type, RefStyle::COPY);
-                        let v = self
-                            .lookup_variable(&b.var.clone().unwrap())
-                            .unwrap()
-                            .clone();
+                        let Some(v) = b
+                            .var
+                            .clone()
+                            .and_then(|var| self.lookup_variable(&var).cloned())
+                        else {
+                            /* a poisoned base was reported already */
+                            if *b.type_hint != Type::Poison {
+                                self.consume(CompileError::new(
+                                    format!(
+                                        "Cannot index '{field}', it is not the field of a variable"
+                                    ),
+                                    None,
+                                    name.span.clone(),
+                                    ErrLevel::ERROR,
+                                ));
+                            }
+                            return Self::poisoned();
+                        };
                         let layout = self.get_layout(v.clone().type_hint).clone();
                         if matches!(layout, None) {
                             self.consume(CompileError::new(
@@ -844,7 +1234,7 @@ impl Generator {
                                 base.span,
                                 ErrLevel::ERROR,
                             ));
-                            self.flush();
+                            return Self::poisoned();
                         }
                         let mut l = layout.clone().unwrap();
                         let f = l.feilds.iter_mut().find(|p| p.0.to_string() == field);
@@ -858,9 +1248,12 @@ impl Generator {
                                 expr.clone().span,
                                 ErrLevel::ERROR,
                             ));
-                            self.flush();
+                            return Self::poisoned();
+                        }
+                        let mut fl = f.unwrap().1.ty.clone();
+                        while let Type::MUT(inner) = fl {
+                            fl = *inner;
                         }
-                        let fl = f.unwrap().1.ty.clone();
                         let mut ty = Type::NoType;
                         if !self.is_iterable(fl.clone()) {}
                         let i = self.gen_expr(index, Type::Any, RefStyle::COPY);
@@ -885,7 +1278,10 @@ impl Generator {
                     }
                     Node::Token(_v, _t) => {
                         let t = self.gen_expr(name.clone(), target_type, RefStyle::COPY);
-                        let fl = *t.type_hint.clone();
+                        let mut fl = *t.type_hint.clone();
+                        while let Type::MUT(inner) = fl {
+                            fl = *inner;
+                        }
                         let mut ty = Type::NoType;
                         if !self.is_iterable(fl.clone()) {}
                         let i = self.gen_expr(index, Type::Any, RefStyle::COPY);
@@ -930,13 +1326,15 @@ impl Generator {
                 }
                 let layout = self.get_layout(t);
                 if layout.is_none() {
-                    self.consume(CompileError::new(
-                        format!("Not a type, {}", block_name.debug()),
-                        None,
-                        expr.clone().span,
-                        ErrLevel::ERROR,
-                    ));
-                    self.flush();
+                    if block_name != Type::Poison {
+                        self.consume(CompileError::new(
+                            format!("Not a type, {}", block_name.debug()),
+                            None,
+                            expr.clone().span,
+                            ErrLevel::ERROR,
+                        ));
+                    }
+                    return Self::poisoned();
                 }
                 stream += &format!("({}) {{", target_type.to_str());
                 for (_i, b_field) in block_fields.clone().iter().enumerate() {
@@ -964,8 +1362,7 @@ impl Generator {
                                     value.clone().span,
                                     ErrLevel::ERROR,
                                 ));
-                                self.flush();
-                                exit(100);
+                                continue;
                             }
                             stream += &format!(".{} = ", field);
                             stream += out.stream.as_str();
@@ -979,8 +1376,7 @@ impl Generator {
                                 b_field.clone().span,
                                 ErrLevel::ERROR,
                             ));
-                            self.flush();
-                            exit(100);
+                            continue;
                         }
                     }
                 }
@@ -995,7 +1391,16 @@ impl Generator {
                 };
             }
             Node::RefExpr { expr } => {
-                let out = self.gen_expr(expr.clone(), target_type, RefStyle::REF);
+                /* the operand is what the expected pointer points to */
+                let mut pointee = target_type;
+                while let Type::MUT(inner) = pointee {
+                    pointee = *inner;
+                }
+                let pointee = match pointee {
+                    Type::PTR(inner) => *inner,
+                    _ => Type::Any,
+                };
+                let out = self.gen_expr(expr.clone(), pointee, RefStyle::REF);
                 if let Node::Token(name, _is_deref) = &expr.node.clone() {
                     if let Some(var) = self.lookup_variable(name).cloned() {
                         self.set_ref(var);
@@ -1051,7 +1456,6 @@ impl Generator {
                                         expr.clone().span,
                                         ErrLevel::ERROR,
                                     ));
-                                    self.flush();
                                 }
                             }
                             return ExprResult {
@@ -1094,20 +1498,15 @@ impl Generator {
                         expr.clone().span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
+                    return Self::poisoned();
                 }
-                return ExprResult {
-                    stream,
-                    is_ref: false,
-                    refed_var: None,
-                    type_hint: Box::new(Type::NoType),
-                    var: None,
-                    preamble: String::new(),
-                };
             }
             Node::Cast { expr: ex, ty } => {
                 let out = self.gen_expr(ex.clone(), target_type, RefStyle::COPY);
-                if self.is_castable(*out.type_hint.clone(), ty.clone()) {
+                /* an enum casts to its discriminant */
+                if self.enum_of(&out.type_hint).is_some() && is_int(ty.clone()) {
+                    stream += &format!("({})({}).tag", ty.to_str(), out.stream);
+                } else if self.is_castable(*out.type_hint.clone(), ty.clone()) {
                     stream += &format!("({})({})", ty.to_str(), out.stream);
                 } else {
                     self.consume(CompileError::new(
@@ -1120,10 +1519,9 @@ impl Generator {
                         expression.span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
                 }
                 return ExprResult {
-                    preamble: String::new(),
+                    preamble: out.preamble,
                     stream,
                     is_ref: v_is_ref,
                     refed_var: None,
@@ -1131,6 +1529,26 @@ impl Generator {
                     var: None,
                 };
             }
+            Node::SizeOf(ty) => {
+                let known = matches!(ty, Type::List(..) | Type::PTR(_) | Type::Poison)
+                    || self.get_layout(ty.clone()).is_some();
+                if !known {
+                    self.consume(CompileError::new(
+                        format!("Not a Type, '{}'", ty.debug()),
+                        None,
+                        expression.span,
+                        ErrLevel::ERROR,
+                    ));
+                }
+                return ExprResult {
+                    preamble: String::new(),
+                    stream: format!("((jaguar_u64)sizeof({}))", ty.to_str()),
+                    is_ref: false,
+                    refed_var: None,
+                    type_hint: Box::new(Type::U64),
+                    var: None,
+                };
+            }
             Node::Ret(v) => {
                 let out = self.gen_expr(
                     v.clone(),
@@ -1151,7 +1569,6 @@ impl Generator {
                         v.span.clone(),
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
                 }
                 stream += &format!("return {}", out.stream);
                 return ExprResult {
@@ -1176,36 +1593,78 @@ impl Generator {
             }
             Node::BinaryExpr { lhs, opr, rhs } => {
                 let mut out = self.gen_expr(lhs.clone(), target_type.clone(), RefStyle::COPY);
-                if !is_int(*out.type_hint.clone())
-                    && opr.clone() != TokenType::Operator("==".to_owned())
-                {
-                    self.consume(CompileError::new(
-                        format!(
-                            "Type {} does not support binary arithmetic'",
-                            out.type_hint.debug()
-                        ),
-                        None,
-                        lhs.clone().span,
-                        ErrLevel::ERROR,
-                    ));
-                    self.flush();
-                }
+                let lhs_ok = self.check_operand(&opr, *out.type_hint.clone(), lhs.clone().span);
                 stream += format!("( {}", out.stream.clone()).as_str();
-                out = self.gen_expr(rhs.clone(), target_type.clone(), RefStyle::COPY);
-                if !is_int(*out.type_hint.clone())
-                    && opr.clone() != TokenType::Operator("==".to_owned())
+                let lhs_type = *out.type_hint.clone();
+                let enum_cmp = match &opr {
+                    TokenType::Operator(op) if op == "==" || op == "!=" => {
+                        self.enum_of(&lhs_type).map(|en| (en, op.clone()))
+                    }
+                    _ => None,
+                };
+                if let Some((en, op)) = enum_cmp {
+                    return self.gen_enum_compare(en, out, &op, rhs, expr.span.clone());
+                }
+                /* the other side of a float has to be a float of the same width */
+                let rhs_target = if is_float(lhs_type.clone()) {
+                    lhs_type.clone()
+                } else {
+                    target_type.clone()
+                };
+                out = self.gen_expr(rhs.clone(), rhs_target, RefStyle::COPY);
+                /* one report per expression is enough when both sides are wrong */
+                if lhs_ok {
+                    self.check_operand(&opr, *out.type_hint.clone(), rhs.clone().span);
+                }
+                let rhs_type = *out.type_hint.clone();
+                if is_bool(lhs_type.clone()) != is_bool(rhs_type.clone())
+                    && lhs_type != Type::Poison
+                    && rhs_type != Type::Poison
+                    && matches!(&opr, TokenType::Operator(v) if v == "==" || v == "!=")
                 {
                     self.consume(CompileError::new(
                         format!(
-                            "Type {} does not support binary arithmetic'",
-                            out.type_hint.debug()
+                            "mismatched types. cannot compare '{}' and '{}'",
+                            lhs_type.debug(),
+                            rhs_type.debug()
                         ),
                         None,
-                        rhs.clone().span,
+                        expr.clone().span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
                 }
+                let float_type = [&lhs_type, &rhs_type]
+                    .into_iter()
+                    .find(|t| is_float((*t).clone()))
+                    .cloned();
+                if let Some(float_type) = float_type.clone() {
+                    if !self.type_match(lhs_type.clone(), rhs_type.clone()) {
+                        self.consume(CompileError::new(
+                            format!(
+                                "mismatched types. cannot mix '{}' and '{}' in binary arithmetic",
+                                lhs_type.debug(),
+                                rhs_type.debug()
+                            ),
+                            Some(format!(
+                                "Cast the other side with 'as {}'",
+                                float_type.debug()
+                            )),
+                            expr.clone().span,
+                            ErrLevel::ERROR,
+                        ));
+                    }
+                }
+                /* comparisons and logic give a bool, arithmetic on floats stays a float */
+                let is_logical = matches!(
+                    &opr,
+                    TokenType::Operator(v)
+                        if ["==", "!=", "<", ">", "<=", ">=", "&&", "||"].contains(&v.as_str())
+                );
+                let result_type = match float_type {
+                    _ if is_logical => Type::BOOL,
+                    Some(ty) => ty,
+                    None => Type::INT,
+                };
                 match opr {
                     crate::lexer::TokenType::Operator(val) if val == "+".to_owned() => {
                         stream += "+";
@@ -1229,8 +1688,7 @@ impl Generator {
                             expr.clone().span,
                             ErrLevel::ERROR,
                         ));
-                        self.flush();
-                        exit(1)
+                        return Self::poisoned();
                     }
                 }
                 stream += format!(" {})", out.stream.clone()).as_str();
@@ -1239,22 +1697,107 @@ impl Generator {
                     stream: stream.clone(),
                     is_ref: false,
                     refed_var: None,
-                    type_hint: Box::new(Type::INT),
+                    type_hint: Box::new(result_type),
                     var: Some(stream),
                 };
             }
+            Node::UnaryExpr { opr, expr: operand } => {
+                let op = match &opr {
+                    TokenType::Operator(op) => op.clone(),
+                    other => format!("{other:?}"),
+                };
+                /* a negative literal is checked against the expected type as a whole */
+                if op == "-" {
+                    let negated = match operand.node.clone() {
+                        Node::LiteralInt(num, suffix) => {
+                            Some(Node::LiteralInt(format!("-{num}"), suffix))
+                        }
+                        Node::LiteralFloat(num, suffix) => {
+                            Some(Node::LiteralFloat(format!("-{num}"), suffix))
+                        }
+                        _ => None,
+                    };
+                    if let Some(node) = negated {
+                        let literal = Spanned {
+                            node,
+                            span: expr.span.clone(),
+                        };
+                        return self.gen_expr(Box::new(literal), target_type, is_ref);
+                    }
+                }
+                let out = self.gen_expr(operand.clone(), target_type, RefStyle::COPY);
+                let mut ty = *out.type_hint.clone();
+                while let Type::MUT(inner) = ty {
+                    ty = *inner;
+                }
+                let supported = match op.as_str() {
+                    "!" => is_bool(ty.clone()),
+                    "~" => is_int(ty.clone()),
+                    _ => is_numeric(ty.clone()),
+                };
+                if !supported && ty != Type::Poison {
+                    self.consume(CompileError::new(
+                        format!("Type {} does not support unary '{op}'", ty.debug()),
+                        if op == "!" && is_int(ty.clone()) {
+                            Some("Compare it explicitly, e.g. 'x == 0'".to_owned())
+                        } else {
+                            None
+                        },
+                        expr.clone().span,
+                        ErrLevel::ERROR,
+                    ));
+                }
+                stream += &format!("({op}{})", out.stream);
+                return ExprResult {
+                    preamble: out.preamble,
+                    stream,
+                    is_ref: false,
+                    refed_var: None,
+                    type_hint: Box::new(ty),
+                    var: None,
+                };
+            }
             Node::BundleAccess { base, field } => {
+                /* `Enum::Variant`, the enum may share its name with nothing else in scope */
+                let enum_ty = match &base.node {
+                    Node::Token(name, _) => self.find_enum(None, name, &target_type, &base.span),
+                    _ => None,
+                };
+                if let Some(ty) = enum_ty {
+                    return self.gen_variant(ty, *field, &expression.span);
+                }
                 if let Node::Token(var, _) = base.node.clone() {
-                    let bndl = self.bundles.iter().find(|b| b.name == var);
+                    let bndl = self.bundles.iter().find(|b| b.name == var).cloned();
                     if matches!(bndl, None) {
+                        if self.failed_bundles.contains(&var) {
+                            return Self::poisoned();
+                        }
                         self.consume(CompileError::new(
                             format!("Could not resolve {var}"),
                             None,
                             base.clone().span,
                             ErrLevel::ERROR,
                         ));
-                        self.flush();
-                        exit(100);
+                        return Self::poisoned();
+                    }
+                    if let Node::GenericFnCall { .. } = field.node {
+                        return self.gen_generic_call(Some(var), *field, target_type);
+                    }
+                    /* `alias::Enum::Variant` */
+                    let enum_ty = match &field.node {
+                        Node::BundleAccess {
+                            base: b2,
+                            field: f2,
+                        } => match &b2.node {
+                            Node::Token(name, _) => self
+                                .find_enum(Some(&var), name, &target_type, &b2.span)
+                                .map(|ty| (ty, *f2.clone())),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    if let Some((ty, variant)) = enum_ty {
+                        return self.gen_variant(ty, variant, &expression.span);
                     }
                     if let Node::FcCall {
                         params: _,
@@ -1306,6 +1849,9 @@ impl Generator {
                     }
                 }
             }
+            Node::GenericFnCall { .. } => {
+                return self.gen_generic_call(None, expr.clone(), target_type);
+            }
             Node::FcCall {
                 params: _,
                 callee: _,
@@ -1326,14 +1872,15 @@ impl Generator {
                 stream += out.stream.as_str();
                 let layout = self.get_layout(*out.type_hint.clone());
                 if matches!(layout, None) {
-                    self.consume(CompileError::new(
-                        format!("Not a type,  {}", out.type_hint.debug()),
-                        None,
-                        base.clone().span,
-                        ErrLevel::ERROR,
-                    ));
-                    self.flush();
-                    exit(100);
+                    if *out.type_hint != Type::Poison {
+                        self.consume(CompileError::new(
+                            format!("Not a type,  {}", out.type_hint.debug()),
+                            None,
+                            base.clone().span,
+                            ErrLevel::ERROR,
+                        ));
+                    }
+                    return Self::poisoned();
                 }
                 #[allow(unused_assignments)]
                 let mut type_hint: Box<Type> = Box::new(Type::NoType);
@@ -1344,7 +1891,7 @@ impl Generator {
                         base.clone().span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
+                    return Self::poisoned();
                 }
                 let v_field = layout
                     .unwrap()
@@ -1360,7 +1907,7 @@ impl Generator {
                         base.clone().span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
+                    return Self::poisoned();
                 }
                 if let Some(var) = self.lookup_variable(out.var.clone().unwrap().as_str()) {
                     if var.type_hint.is_pointer() {
@@ -1381,8 +1928,7 @@ impl Generator {
                         base.clone().span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
-                    exit(100);
+                    return Self::poisoned();
                 }
                 return ExprResult {
                     preamble: String::new(),
@@ -1399,8 +1945,8 @@ impl Generator {
                     index: _,
                 } => {
                     let out = self.gen_expr(name, Type::Any, RefStyle::COPY);
-                    let v = self.gen_expr(value, Type::Any, RefStyle::COPY);
-                    stream += &format!("{} = {}", out.stream, v.stream);
+                    let v = self.gen_expr(value, *out.type_hint.clone(), RefStyle::COPY);
+                    stream += &self.assign(&out.stream, &v.stream, &out.type_hint);
                 }
                 Node::MemberAccess { base, field } => {
                     let base_out = self
@@ -1412,19 +1958,19 @@ impl Generator {
                     }
                     let layout = self.get_layout(t);
                     if matches!(layout, None) {
-                        self.consume(CompileError::new(
-                            format!("Not a Type. '{}'", base_out.type_hint.debug()),
-                            None,
-                            base.span,
-                            ErrLevel::ERROR,
-                        ));
-                        self.flush();
-                        exit(1);
+                        if *base_out.type_hint != Type::Poison {
+                            self.consume(CompileError::new(
+                                format!("Not a Type. '{}'", base_out.type_hint.debug()),
+                                None,
+                                base.span,
+                                ErrLevel::ERROR,
+                            ));
+                        }
+                        return Self::poisoned();
                     }
-                    stream += &base_out.clone().stream;
                     let v_field = layout.unwrap().feilds.get(&field).cloned();
-                    if v_field.is_some() {
-                        if !v_field.unwrap().ty.is_mutable() {
+                    if let Some(v_field) = v_field {
+                        if !v_field.ty.is_mutable() {
                             self.consume(CompileError::new(
                                 format!(
                                     "Cannot mutate a const value. '{}'",
@@ -1434,9 +1980,8 @@ impl Generator {
                                 base.span.clone(),
                                 ErrLevel::ERROR,
                             ));
-                            self.flush();
                         }
-                        let out = self.gen_expr(value, Type::Any, RefStyle::COPY).clone();
+                        let out = self.gen_expr(value, v_field.ty.clone(), RefStyle::COPY);
                         let mut modifier = ".";
                         if base_out.type_hint.is_pointer() {
                             if let Type::PTR(ty) = *base_out.clone().type_hint {
@@ -1454,16 +1999,71 @@ impl Generator {
                                         base.span.clone(),
                                         ErrLevel::ERROR,
                                     ));
-                                    self.flush();
                                 }
                             }
                             modifier = "->";
                         }
-                        stream += &format!("{modifier}{} = {}", field, out.stream);
+                        let target = format!("{}{modifier}{field}", base_out.stream);
+                        stream += &self.assign(&target, &out.stream, &v_field.ty);
+                    }
+                }
+                Node::DeRefExpr { expr: pointer } => {
+                    let ptr_out = self.gen_expr(pointer.clone(), Type::Any, RefStyle::COPY);
+                    let mut pointer_type = self.resolve_type(*ptr_out.type_hint.clone());
+                    while let Type::MUT(inner) = pointer_type {
+                        pointer_type = *inner;
+                    }
+                    let pointee = match pointer_type {
+                        Type::PTR(pointee) => *pointee,
+                        Type::Poison => Type::Poison,
+                        other => {
+                            self.consume(CompileError::new(
+                                format!("Cannot dereference a value of type '{}'", other.debug()),
+                                None,
+                                pointer.span.clone(),
+                                ErrLevel::ERROR,
+                            ));
+                            Type::Poison
+                        }
+                    };
+                    /* writing through a pointer needs a mutable pointee, ptr<mut T> */
+                    if pointee != Type::Poison && !pointee.is_mutable() {
+                        let name = ptr_out.var.clone().unwrap_or("pointer".to_owned());
+                        self.consume(CompileError::new(
+                            format!("Cannot mutate a const value. '*{name}'"),
+                            Some(format!(
+                                "'{name}' points to a const object of type '{}'",
+                                pointee.debug()
+                            )),
+                            pointer.span.clone(),
+                            ErrLevel::ERROR,
+                        ));
+                    }
+                    let out = self.gen_expr(value.clone(), pointee.clone(), RefStyle::COPY);
+                    if !self.type_match(pointee.clone(), *out.type_hint.clone()) {
+                        self.consume(CompileError::new(
+                            format!(
+                                "Type Mismatch. expected '{}' but got '{}' instead",
+                                pointee.debug(),
+                                out.type_hint.debug()
+                            ),
+                            None,
+                            value.span,
+                            ErrLevel::ERROR,
+                        ));
                     }
+                    stream += &self.assign(&format!("*{}", ptr_out.stream), &out.stream, &pointee);
                 }
                 Node::Token(var, _d) => {
-                    let val = self.lookup_variable(&var.clone()).unwrap().clone();
+                    let Some(val) = self.lookup_variable(&var.clone()).cloned() else {
+                        self.consume(CompileError::new(
+                            format!("Use of Undeclared Symbol '{var}'"),
+                            None,
+                            name.span.clone(),
+                            ErrLevel::ERROR,
+                        ));
+                        return Self::poisoned();
+                    };
                     if !val.type_hint.is_mutable() {
                         self.consume(CompileError::new(
                             format!("Cannot mutate a const value '{}'.", val.name),
@@ -1471,10 +2071,10 @@ impl Generator {
                             value.clone().span,
                             ErrLevel::ERROR,
                         ));
-                        self.flush();
                     }
-                    let out = self.gen_expr(value.clone(), target_type.clone(), RefStyle::COPY);
-                    if !self.type_match(target_type, *out.clone().type_hint) {
+                    /* the variable's type tells enum constructors and dyn coercions what to make */
+                    let out = self.gen_expr(value.clone(), val.type_hint.clone(), RefStyle::COPY);
+                    if !self.type_match(val.type_hint.clone(), *out.clone().type_hint) {
                         self.consume(CompileError::new(
                             format!(
                                 "Type Mismatch. '{}' expected '{}' but got '{}' instead",
@@ -1486,9 +2086,9 @@ impl Generator {
                             value.span,
                             ErrLevel::ERROR,
                         ));
-                        self.flush();
                     }
-                    stream += &format!("{var} = {}\n", out.stream);
+                    stream += &self.assign(&var, &out.stream, &val.type_hint);
+                    stream += "\n";
                     return ExprResult {
                         preamble: String::new(),
                         stream,
@@ -1505,7 +2105,6 @@ impl Generator {
                         expression.span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
                 }
             },
             Node::PluginStatement {
@@ -1518,6 +2117,13 @@ impl Generator {
             } => {
                 let saved_type = self.current_scope_return_type.clone();
                 self.current_scope_return_type = *ret_type.clone();
+                let saved_func = self.current_func.clone();
+                let owner = match &targ_type {
+                    Type::Custom(inst) => self.instance_sources.get(inst).cloned(),
+                    _ => None,
+                };
+                self.current_func =
+                    format!("{}::{name}", owner.unwrap_or_else(|| targ_type.c_impl()));
                 let plugin_name = name.clone();
                 let plugin_type = ret_type.clone();
                 let mut oplugin_name = String::new();
@@ -1537,10 +2143,11 @@ impl Generator {
                     let mut modifier = "";
                     if arg.name.clone() == "self" {
                         if arg.type_hint == Type::NoType {
+                            /* a bare 'self' is passed as 'T* self' */
                             modifier = "*";
                             self.current_context.add(Var::new(
                                 "self".into(),
-                                targ_type.clone(),
+                                Type::PTR(Box::new(Type::MUT(Box::new(targ_type.clone())))),
                                 true,
                                 None,
                                 expr.clone().span,
@@ -1568,12 +2175,11 @@ impl Generator {
                         ));
                     }
 
-                    stream.push_str(&format!(
-                        "{} {}{}",
-                        arg.type_hint.to_str(),
-                        modifier,
-                        arg.name
-                    ));
+                    let c_type = match modifier {
+                        "*" => arg.type_hint.c_impl(),
+                        _ => arg.type_hint.to_str(),
+                    };
+                    stream.push_str(&format!("{c_type} {modifier}{}", arg.name));
                     if index != args.len() - 1 {
                         stream.push_str(",");
                     }
@@ -1605,6 +2211,7 @@ impl Generator {
                 self.cur_section = Section::TEXT;
                 self.exit_scope();
                 self.current_scope_return_type = saved_type;
+                self.current_func = saved_func;
             }
             Node::IfStmt {
                 cond,
@@ -1618,13 +2225,15 @@ impl Generator {
                     fix = "";
                 }
                 stream.push_str(&format!("if ("));
-                let cout = self.gen_expr(cond, Type::Any, RefStyle::COPY);
+                let cout = self.gen_expr(cond.clone(), Type::Any, RefStyle::COPY);
+                self.check_condition(*cout.type_hint.clone(), cond.span);
                 stream.push_str(&format!("{}", cout.stream));
                 stream.push_str(&format!("){{{fix}\n"));
                 match body.node {
                     Node::Program(k) => {
                         for node in k.clone() {
                             let o = self.gen_expr(Box::new(node), Type::Any, RefStyle::COPY);
+                            stream += &o.preamble.replace('\n', &format!("{fix}\n"));
                             stream += &o.stream;
                             stream += &format!(";{fix}");
                         }
@@ -1645,7 +2254,7 @@ impl Generator {
                         Node::Program(k) => {
                             for node in k {
                                 let n = self.gen_expr(Box::new(node), Type::Any, RefStyle::COPY);
-                                stream += &format!("{};\n", n.stream);
+                                stream += &format!("{}{};\n", n.preamble, n.stream);
                             }
                         }
                         _ => {}
@@ -1666,7 +2275,6 @@ impl Generator {
                         value.clone().span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
                 }
                 if let Some(v) = self.lookup_variable(&name).cloned() {
                     self.consume(CompileError::new(
@@ -1681,17 +2289,21 @@ impl Generator {
                         v.definition,
                         ErrLevel::WARNING,
                     ));
-                    self.flush();
                 }
                 let l = self.get_layout(type_hint.clone());
-                if !l.is_some() && type_hint != Type::Any {
+                let poisoned = matches!(&type_hint, Type::Poison)
+                    || matches!(&type_hint, Type::MUT(t) if **t == Type::Poison);
+                /* a mismatch with a type that does not exist says nothing new */
+                let is_list = matches!(&type_hint, Type::List(..))
+                    || matches!(&type_hint, Type::MUT(t) if matches!(**t, Type::List(..)));
+                let unknown = !l.is_some() && type_hint != Type::Any && !is_list && !poisoned;
+                if unknown {
                     self.consume(CompileError::new(
                         format!("Not a Type, '{}'", type_hint.debug()),
                         None,
                         expr.clone().span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
                 }
                 let mut temp_stream: String = String::new();
                 let out = self.gen_expr(value.clone(), type_hint.clone(), RefStyle::COPY);
@@ -1702,9 +2314,8 @@ impl Generator {
                         value.clone().span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
                 }
-                if !self.type_match(type_hint.clone(), *out.type_hint.clone()) {
+                if !unknown && !self.type_match(type_hint.clone(), *out.type_hint.clone()) {
                     self.consume(CompileError::new(
                         format!(
                             "Type Mismatch. '{}' expected '{}' but got '{}' instead",
@@ -1716,7 +2327,6 @@ impl Generator {
                         value.clone().span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
                 }
 
                 temp_stream += out.stream.as_str();
@@ -1773,9 +2383,10 @@ impl Generator {
                 stream.push_str("\nfor (");
                 if let Node::ReVal { name, value: _ } = init.clone().node {
                     if let Node::Token(v, _) = name.node.clone() {
+                        /* the loop variable is stepped by the increment, so it is mutable */
                         self.current_context.add(Var::new(
                             v,
-                            Type::INT,
+                            Type::MUT(Box::new(Type::INT)),
                             false,
                             None,
                             name.clone().span,
@@ -1786,7 +2397,8 @@ impl Generator {
                 stream.push_str("jaguar_int ");
                 stream.push_str(&iniout.stream);
                 stream.push_str(";");
-                let condout = self.gen_expr(cond, Type::Any, RefStyle::COPY);
+                let condout = self.gen_expr(cond.clone(), Type::Any, RefStyle::COPY);
+                self.check_condition(*condout.type_hint.clone(), cond.span);
                 stream.push_str(&condout.stream);
                 stream.push_str(";");
                 stream.push_str("(");
@@ -1798,6 +2410,7 @@ impl Generator {
                     Node::Program(k) => {
                         for node in k.clone() {
                             let s = self.gen_expr(Box::new(node), Type::Any, RefStyle::COPY);
+                            stream += &s.preamble.replace('\n', &format!("{fix}\n"));
                             stream += &format!("\n{};{fix}", s.stream);
                         }
                     }
@@ -1807,13 +2420,14 @@ impl Generator {
             }
             Node::WhileStmt { cond, body } => {
                 stream += "\nwhile (";
-                let cond_stream = self.gen_expr(cond, Type::Any, RefStyle::COPY);
+                let cond_stream = self.gen_expr(cond.clone(), Type::Any, RefStyle::COPY);
+                self.check_condition(*cond_stream.type_hint.clone(), cond.span);
                 stream += &format!("{}) {{\n", cond_stream.stream);
                 match body.node {
                     Node::Program(k) => {
                         for node in k {
                             let o = self.gen_expr(Box::new(node), Type::Any, RefStyle::COPY);
-                            stream += &format!("{};\n", o.stream);
+                            stream += &format!("{}{};\n", o.preamble, o.stream);
                         }
                     }
                     _ => {}
@@ -1831,7 +2445,7 @@ impl Generator {
                     Node::Program(k) => {
                         for node in k {
                             let o = self.gen_expr(Box::new(node), Type::Any, RefStyle::COPY);
-                            stream += &format!("{};\n", o.stream);
+                            stream += &format!("{}{};\n", o.preamble, o.stream);
                         }
                     }
                     _ => {}
@@ -1882,12 +2496,27 @@ impl Generator {
                 return_val: _,
                 args,
                 vardaic: _,
-                mangled_name: _,
+                mangled_name,
+                doc,
             } => {
                 let saved_type = self.current_scope_return_type.clone();
                 self.current_scope_return_type = ret_type.clone();
+                let saved_func = self.current_func.clone();
+                self.current_func = match self.current_context.name.as_str() {
+                    "global" => self
+                        .instance_sources
+                        .get(&name)
+                        .cloned()
+                        .unwrap_or(name.clone()),
+                    scope => format!("{scope}::{name}"),
+                };
                 let mut fn_ret = ret_type.clone();
-                let mut fmangled_name = self.name_mangler(name.clone());
+                /* generic instances bring a C name shared by every file */
+                let mut fmangled_name = if mangled_name == name {
+                    self.name_mangler(name.clone())
+                } else {
+                    mangled_name
+                };
                 if name == "main".to_string() {
                     fmangled_name = name.clone();
                     fn_ret = Type::INT;
@@ -1895,6 +2524,7 @@ impl Generator {
                 stream.push_str("\n");
                 self.change_scope(name.as_str());
                 self.cur_section = Section::FUNC;
+                stream.push_str(&c_doc(&doc, ""));
                 stream.push_str("\n");
                 if self.is_included {
                     stream.push_str("extern inline ");
@@ -1926,6 +2556,7 @@ impl Generator {
                         for node in k.clone() {
                             let o =
                                 self.gen_expr(Box::new(node.clone()), Type::Any, RefStyle::COPY);
+                            stream.push_str(&o.preamble);
                             stream.push_str(&o.stream);
                             stream.push_str(";");
                         }
@@ -1950,6 +2581,7 @@ impl Generator {
                 self.exit_scope();
                 self.cur_section = Section::TEXT;
                 self.current_scope_return_type = saved_type;
+                self.current_func = saved_func;
                 return ExprResult {
                     preamble: String::new(),
                     stream,
@@ -1959,7 +2591,23 @@ impl Generator {
                     var: None,
                 };
             }
+            /* the lexer reported it already */
+            Node::Poison => return Self::poisoned(),
             Node::NULLPTR => match target_type.clone() {
+                _ if target_type.is_dyn() => {
+                    /* a dyn is a value and its vtable, null has neither */
+                    let mut dyn_type = target_type.clone();
+                    while let Type::MUT(inner) = dyn_type {
+                        dyn_type = *inner;
+                    }
+                    self.consume(CompileError::new(
+                        format!("Cannot make a '{}' from null", dyn_type.debug()),
+                        Some("a dyn always points to a value".to_string()),
+                        expr.span.clone(),
+                        ErrLevel::ERROR,
+                    ));
+                    return Self::poisoned();
+                }
                 Type::PTR(_v) => {
                     return ExprResult {
                         preamble: String::new(),
@@ -1998,7 +2646,6 @@ impl Generator {
                     expr.clone().span,
                     ErrLevel::ERROR,
                 ));
-                self.flush();
             }
         }
         ExprResult {
@@ -2010,6 +2657,24 @@ impl Generator {
             var: None,
         }
     }
+    /// Result for an expression that already reported an error.
+    fn poisoned() -> ExprResult {
+        ExprResult {
+            preamble: String::new(),
+            stream: String::new(),
+            is_ref: false,
+            refed_var: None,
+            type_hint: Box::new(Type::Poison),
+            var: None,
+        }
+    }
+    fn poisoned_call() -> FResult {
+        FResult {
+            stream: String::new(),
+            type_hint: Type::Poison,
+            preamble: String::new(),
+        }
+    }
     fn gen_func_call(&mut self, expression: Box<Spanned<Node>>, target_type: Type) -> FResult {
         let mut stream = String::new();
         let Node::FcCall { params, callee } = expression.node.clone() else {
@@ -2035,9 +2700,21 @@ impl Generator {
                 }
                 let out = self.gen_expr(base.clone(), target_type.clone(), RefStyle::COPY);
                 let layout = self.get_layout(*out.clone().type_hint).clone();
+                if layout.is_none() {
+                    if *out.type_hint != Type::Poison {
+                        self.consume(CompileError::new(
+                            format!("Not a type, {}", out.type_hint.debug()),
+                            None,
+                            base.clone().span,
+                            ErrLevel::ERROR,
+                        ));
+                    }
+                    return Self::poisoned_call();
+                }
                 #[allow(unused)]
                 let mut base_type: Type = Type::NoType;
-                if out.var.is_some() {
+                /* for `a.b.f()` the var is `a`, the method belongs to the type of `a.b` */
+                if out.var.is_some() && matches!(base.node, Node::Token(..)) {
                     base_type = self
                         .lookup_variable(&out.var.clone().unwrap())
                         .unwrap()
@@ -2046,7 +2723,15 @@ impl Generator {
                 } else {
                     base_type = *out.type_hint.clone();
                 }
-                let method = self.get_field_item(layout.unwrap(), &field, callee.clone().span);
+                /* methods are named after the struct, whether it is held by value or through a pointer */
+                while let Type::MUT(inner) | Type::PTR(inner) = base_type {
+                    base_type = *inner;
+                }
+                let Some(method) =
+                    self.get_field_item(layout.unwrap(), &field, callee.clone().span)
+                else {
+                    return Self::poisoned_call();
+                };
 
                 fargs = method.clone().args;
                 #[allow(unused_assignments)]
@@ -2066,15 +2751,25 @@ impl Generator {
                     gvalmod = "";
                     gmod = "";
                 }
-                let g = self.gb();
+                /* a dyn is passed as it is, it holds a pointer already */
+                if dyn_trait(&base_type).is_some() {
+                    gvalmod = "";
+                }
                 let mut preamble = String::new();
-                preamble += &format!(
-                    "{}{gmod} __{} = {gvalmod}{};\n",
-                    base_type.c_impl(),
-                    g.clone(),
-                    out.stream
-                );
-                stream += &format!("{}_{}({modifier}__{g}", base_type.c_impl(), field);
+                /* a call result has no address, it is stored first */
+                let receiver = if is_f {
+                    let g = self.gb();
+                    preamble += &format!(
+                        "{}{gmod} __{} = {gvalmod}{};\n",
+                        base_type.c_impl(),
+                        g.clone(),
+                        out.stream
+                    );
+                    format!("__{g}")
+                } else {
+                    format!("{gvalmod}({})", out.stream)
+                };
+                stream += &format!("{}_{}({modifier}{receiver}", base_type.c_impl(), field);
                 if !params.clone().is_empty() {
                     stream += ",";
                 }
@@ -2096,7 +2791,7 @@ impl Generator {
                         expression.clone().span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
+                    return Self::poisoned_call();
                 }
                 let mut i = 1;
                 for arg in params.clone() {
@@ -2117,8 +2812,6 @@ impl Generator {
                             arg.span.clone(),
                             ErrLevel::ERROR,
                         ));
-                        self.flush();
-                        exit(2);
                     }
                     if *a.type_hint.clone() == Type::NoType {
                         self.consume(CompileError::new(
@@ -2127,7 +2820,6 @@ impl Generator {
                             arg.span.clone(),
                             ErrLevel::ERROR,
                         ));
-                        self.flush();
                     }
                     stream += &a.stream;
                     if i <= params.len() - 1 {
@@ -2142,23 +2834,21 @@ impl Generator {
                     type_hint: method.ty,
                 };
             }
-            Node::BundleAccess { base, field } => {
+            Node::BundleAccess { base, field: _ } => {
                 if let Node::Token(var, _i) = base.node.clone() {
                     let bundle: Option<Bundle> =
                         self.bundles.clone().iter().find(|b| b.name == var).cloned();
                     if matches!(bundle, None) {
+                        if self.failed_bundles.contains(&var) {
+                            return Self::poisoned_call();
+                        }
                         self.consume(CompileError::new(
                             format!("Could not resolve scope {var}"),
                             Some("Confirm Bundle path".into()),
                             base.span,
                             ErrLevel::ERROR,
                         ));
-                        self.flush();
-                        let _sf = self.func_table.clone();
-                        self.func_table = bundle.clone().unwrap().functions;
-                        let out = self.gen_expr(field.clone(), target_type, RefStyle::COPY);
-                        stream += &out.stream;
-                        exit(100); /* should never reach here */
+                        return Self::poisoned_call();
                     }
                 } else if let Node::BundleAccess {
                     base: _inner,
@@ -2185,8 +2875,7 @@ impl Generator {
                         expression.span,
                         ErrLevel::ERROR,
                     ));
-                    self.flush();
-                    exit(100);
+                    return Self::poisoned_call();
                 }
                 fargs = func.unwrap().args.clone();
                 fret_type = func.unwrap().ty.clone();
@@ -2194,8 +2883,13 @@ impl Generator {
                 stream += format!("{}(", func.unwrap().gen_name).as_str();
             }
             _ => {
-                println!("TODO: ERR CALLEE");
-                exit(100);
+                self.consume(CompileError::new(
+                    "Not a callable expression".to_string(),
+                    None,
+                    callee.span.clone(),
+                    ErrLevel::ERROR,
+                ));
+                return Self::poisoned_call();
             }
         }
         let parlen = params.len();
@@ -2206,7 +2900,6 @@ impl Generator {
                 expression.span,
                 ErrLevel::ERROR,
             ));
-            self.flush();
         }
         for (index, param) in params.iter().enumerate() {
             let arg = fargs
@@ -2233,9 +2926,13 @@ impl Generator {
                     param.span.clone(),
                     ErrLevel::ERROR,
                 ));
-                self.flush();
             }
-            stream += expr_code.stream.as_str();
+            /* `...` takes integers the size they are, {d} reads a jaguar_int */
+            if index >= fargs.len() && is_widened(&expr_code.type_hint) {
+                stream += &format!("((jaguar_int)({}))", expr_code.stream);
+            } else {
+                stream += expr_code.stream.as_str();
+            }
             if index != params.len() - 1 {
                 stream += ",";
             }
@@ -2251,9 +2948,11 @@ impl Generator {
                     param.clone().span,
                     ErrLevel::ERROR,
                 ));
-                self.flush();
             }
-            if arg.type_hint.is_mutable() && !expr_code.type_hint.is_mutable() {
+            if arg.type_hint.is_mutable()
+                && !expr_code.type_hint.is_mutable()
+                && *expr_code.type_hint != Type::Poison
+            {
                 self.consume(CompileError::new(
                     format!(
                         "Passing a const value '{}' to an argument that expects a mutable value is forbidden",
@@ -2263,7 +2962,6 @@ impl Generator {
                     param.clone().span,
                     ErrLevel::ERROR,
                 ));
-                self.flush();
             }
         }
         stream += &format!(")");
@@ -2274,124 +2972,1496 @@ impl Generator {
             type_hint: ty,
         };
     }
-    fn lookup_variable(&mut self, name: &str) -> Option<&mut Var> {
-        self.current_context.look_up_var(name)
+    /// Generates a call of a generic function, instantiating it for the call's type
+    /// arguments first. `origin` is the bundle the call goes through, if any.
+    fn gen_generic_call(
+        &mut self,
+        origin: Option<String>,
+        call: Spanned<Node>,
+        target_type: Type,
+    ) -> ExprResult {
+        let Node::GenericFnCall {
+            callee,
+            generics,
+            args,
+        } = call.node
+        else {
+            return Self::poisoned();
+        };
+        let Node::Token(name, _) = callee.node.clone() else {
+            self.consume(CompileError::new(
+                "Not a callable expression".to_string(),
+                None,
+                callee.span.clone(),
+                ErrLevel::ERROR,
+            ));
+            return Self::poisoned();
+        };
+        let Some(func) = self.instantiate_fn(origin, &name, &generics, &call.span) else {
+            return Self::poisoned();
+        };
+        /* the instance may have been made in a scope that is gone by now */
+        if !self.func_table.iter().any(|f| f.name == func.name) {
+            self.func_table.push(func.clone());
+        }
+        let call = Spanned {
+            node: Node::FcCall {
+                params: args,
+                callee: Box::new(Spanned {
+                    node: Node::Token(func.name, false),
+                    span: callee.span,
+                }),
+            },
+            span: call.span,
+        };
+        let out = self.gen_func_call(Box::new(call), target_type);
+        ExprResult {
+            preamble: out.preamble,
+            stream: out.stream,
+            is_ref: false,
+            refed_var: None,
+            type_hint: Box::new(out.type_hint),
+            var: None,
+        }
     }
-
-    fn emit(&mut self, text: &str) {
-        match self.cur_section {
-            Section::TEXT => {
-                self.text += text;
+    /// Replaces the generic types written in `node` with their instances.
+    fn instantiate_types(&mut self, node: &mut Spanned<Node>) {
+        generics::walk(node, &mut |n| {
+            let span = n.span.clone();
+            for ty in generics::types_mut(&mut n.node) {
+                self.concrete_type(ty, &span);
             }
-            Section::FUNC => {
-                self.func += text;
+        });
+    }
+    fn concrete_type(&mut self, ty: &mut Type, span: &Span) {
+        match ty {
+            Type::PTR(inner) | Type::MUT(inner) | Type::List(inner, _) => {
+                self.concrete_type(inner, span);
             }
-            Section::HEADER => {
-                self.header += text;
+            Type::Generic { name, args } => {
+                let (name, mut args) = (name.clone(), args.clone());
+                args.iter_mut().for_each(|a| self.concrete_type(a, span));
+                *ty = self.instantiate_struct(None, &name, &args, span);
+            }
+            Type::BundledType { bundle, ty: inner } => {
+                if let Type::Generic { name, args } = inner.as_ref() {
+                    let (bundle, name, mut args) = (bundle.clone(), name.clone(), args.clone());
+                    args.iter_mut().for_each(|a| self.concrete_type(a, span));
+                    *ty = self.instantiate_struct(Some(bundle), &name, &args, span);
+                }
             }
+            _ => {}
         }
     }
-
-    fn error(&mut self, err_msg: CompileError) {
-        self.has_error = true;
-        let span = err_msg.span.clone();
-        let source = self.source_code.clone();
-        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
-        let line_end = source[span.start..]
-            .find('\n')
-            .map(|i| span.start + i)
-            .unwrap_or(source.len());
-        let line = &source[line_start..line_end];
-        let col_start = span.start - line_start;
-        let col_end = span.end - line_start;
-        let line_number = source[..span.start].chars().filter(|&c| c == '\n').count() + 1;
-        eprintln!(
-            "{}",
-            format!("{} {}", "[Tixie Error] :".red().bold(), err_msg.errmsg)
-        );
-        let filename = self.inputpath.clone();
-        eprintln!(" ---> line : [{filename}:{line_number}]");
-        eprintln!("   |\n{: >2} | {}", line_number, line);
-        let mut caret_line = String::new();
-        for (i, c) in line.chars().enumerate() {
-            if i >= col_start && i < col_end {
-                caret_line.push('^');
-            } else if i < col_start {
-                caret_line.push(if c == '\t' { '\t' } else { ' ' });
-            }
+    /// The generic struct or function `name` declared in this file or, given `origin`,
+    /// in the bundle with that alias.
+    fn find_template(
+        &mut self,
+        origin: Option<&str>,
+        name: &str,
+        is_struct: bool,
+        span: &Span,
+    ) -> Option<Spanned<Node>> {
+        let templates = match origin {
+            None => Some(&self.templates),
+            Some(alias) => self
+                .bundles
+                .iter()
+                .find(|b| b.name == alias)
+                .map(|b| &b.templates),
+        };
+        let template = templates.and_then(|t| {
+            t.iter()
+                .find(|t| match &t.node {
+                    Node::GenericStructStmt { name: n, .. }
+                    | Node::GenericEnumStmt { name: n, .. } => is_struct && n == name,
+                    Node::GenericFnStmt { name: n, .. } => !is_struct && n == name,
+                    _ => false,
+                })
+                .cloned()
+        });
+        if template.is_none() {
+            let kind = if is_struct { "struct" } else { "function" };
+            let name = match origin {
+                Some(alias) => format!("{alias}::{name}"),
+                None => name.to_string(),
+            };
+            self.consume(CompileError::new(
+                format!("No generic {kind} named '{name}'"),
+                None,
+                span.clone(),
+                ErrLevel::ERROR,
+            ));
         }
-        eprint!("   | {}", format!("{caret_line}").as_str().yellow().bold());
-        if !matches!(err_msg.help, None) {
-            eprint!(" help : {}", err_msg.help.unwrap().yellow().bold());
+        template
+    }
+    /// How the instance of `name` for `args` is written in the source, e.g. `opt<int>`.
+    fn instance_source(&self, name: &str, args: &[Type]) -> String {
+        let args: Vec<String> = args
+            .iter()
+            .map(|arg| match arg {
+                Type::Custom(inst) => self
+                    .instance_sources
+                    .get(inst)
+                    .cloned()
+                    .unwrap_or(inst.clone()),
+                other => other.debug(),
+            })
+            .collect();
+        format!("{name}<{}>", args.join(", "))
+    }
+    /// The file the templates found with `origin` are declared in, this one or the bundle's.
+    fn template_file(&self, origin: Option<&str>) -> String {
+        origin
+            .and_then(|alias| self.bundles.iter().find(|b| b.name == alias))
+            .map_or(self.inputpath.clone(), |b| b.path.clone())
+    }
+    /// Reports a type argument list that does not fit `params`. Returns false if it did.
+    fn check_type_args(
+        &mut self,
+        name: &str,
+        params: &[String],
+        args: &[Type],
+        span: &Span,
+    ) -> bool {
+        if params.len() == args.len() {
+            return true;
         }
-        eprintln!("");
+        self.consume(CompileError::new(
+            format!(
+                "'{name}' takes {} type arguments but {} were given",
+                params.len(),
+                args.len()
+            ),
+            Some(format!(
+                "'{name}' is declared as {name}[{}]",
+                params.join(", ")
+            )),
+            span.clone(),
+            ErrLevel::ERROR,
+        ));
+        false
     }
-
-    fn warn(&mut self, err_msg: CompileError) {
-        let span = err_msg.span.clone();
-        let source = self.source_code.clone();
-        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
-        let line_end = source[span.start..]
-            .find('\n')
-            .map(|i| span.start + i)
-            .unwrap_or(source.len());
-        let line = &source[line_start..line_end];
-        let col_start = span.start - line_start;
-        let col_end = span.end - line_start;
-        let line_number = source[..span.start].chars().filter(|&c| c == '\n').count() + 1;
-        eprintln!(
-            "{}",
-            format!("[Tixie Warning]: {}", err_msg.errmsg)
-                .yellow()
-                .bold()
-        );
-        let filename = self.inputpath.clone();
-        eprintln!(" ---> line : [{filename}:{line_number}]");
-        eprintln!("   |\n{: >2} | {}", line_number, line);
-        let mut caret_line = String::new();
-        for (i, c) in line.chars().enumerate() {
-            if i >= col_start && i < col_end {
-                caret_line.push('^');
-            } else if i < col_start {
-                caret_line.push(if c == '\t' { '\t' } else { ' ' });
+    /// Generates the instance of the generic struct `name` for `args`, once, and returns
+    /// its type.
+    fn instantiate_struct(
+        &mut self,
+        origin: Option<String>,
+        name: &str,
+        args: &[Type],
+        span: &Span,
+    ) -> Type {
+        if args.contains(&Type::Poison) {
+            return Type::Poison;
+        }
+        let Some(template) = self.find_template(origin.as_deref(), name, true, span) else {
+            return Type::Poison;
+        };
+        let (params, bounds) = match &template.node {
+            Node::GenericStructStmt {
+                generics, bounds, ..
             }
+            | Node::GenericEnumStmt {
+                generics, bounds, ..
+            } => (generics.clone(), bounds.clone()),
+            _ => return Type::Poison,
+        };
+        if !self.check_type_args(name, &params, args, span)
+            || !self.check_bounds(name, &params, &bounds, args, origin.as_deref(), span)
+        {
+            return Type::Poison;
         }
-        eprint!("   | {}", format!("{caret_line}").as_str().yellow().bold());
-        if !matches!(err_msg.help, None) {
-            eprint!(" help : {}", err_msg.help.unwrap().blue())
+        let inst = generics::file_instance_name(&self.template_file(origin.as_deref()), name, args);
+        if self.instances.contains(&inst) {
+            return Type::Custom(inst);
         }
-        eprintln!("");
-    }
-
-    fn get_layout(&mut self, type_hint: Type) -> Option<StructLayout> {
-        if let Type::BundledType { bundle, ty } = type_hint {
-            let bd = self.bundles.iter().find(|b| b.name == bundle).cloned();
-            if matches!(bd, None) {
-                return None;
-            }
-            let sb = self.bundles.clone();
-            let st = self.types.clone();
-            let sc = self.current_context.clone();
-            let sv = self.var_table.clone();
-            self.bundles = bd.clone().unwrap().bundles.clone();
-            self.types = bd.clone().unwrap().types.clone();
-            self.var_table = bd.unwrap().vars;
-            let g = self.get_layout(*ty);
-            self.bundles = sb.clone();
-            self.types = st.clone();
-            self.current_context = sc.clone();
-            self.var_table = sv.clone();
-            return g;
-        } else if let Type::PTR(ty) = type_hint {
-            return self.get_layout(*ty);
-        } else if let Type::MUT(ty) = type_hint {
-            return self.get_layout(*ty);
+        self.instances.push(inst.clone());
+        let source = self.instance_source(name, args);
+        self.instance_sources.insert(inst.clone(), source);
+        let node = match template.node {
+            Node::GenericStructStmt {
+                fields,
+                meths,
+                statics,
+                doc,
+                ..
+            } => Node::StructStmt {
+                name: inst.clone(),
+                fields,
+                meths,
+                statics,
+                doc,
+            },
+            Node::GenericEnumStmt {
+                variants,
+                meths,
+                doc,
+                ..
+            } => Node::EnumStmt {
+                name: inst.clone(),
+                variants,
+                meths,
+                doc,
+            },
+            _ => return Type::Poison,
+        };
+        let mut node = Spanned {
+            node,
+            span: template.span,
+        };
+        generics::substitute(&mut node, &params, args, origin.as_deref());
+        /* a bundle header may hold the same instance, the guards keep the first one */
+        let guard = format!("JAGUAR_GENERIC_{inst}");
+        self.in_template_scope(origin.as_deref(), |g| {
+            /* instances this one uses go before it, outside its guards */
+            g.instantiate_types(&mut node);
+            g.cur_section = Section::HEADER;
+            g.emit(&format!("\n#ifndef {guard}\n#define {guard}"));
+            g.cur_section = Section::TEXT;
+            g.emit(&format!("\n#ifndef {guard}_IMPL\n#define {guard}_IMPL"));
+            g.generate(vec![node]);
+            g.cur_section = Section::HEADER;
+            g.emit("\n#endif");
+            g.cur_section = Section::TEXT;
+            g.emit("\n#endif");
+        });
+        /* constructors name the template, `opt::None`, and find the instance by the type */
+        if let Some(en) = self.enums.iter_mut().find(|e| e.name == inst) {
+            en.template = Some(match &origin {
+                Some(alias) => format!("{alias}::{name}"),
+                None => name.to_string(),
+            });
         }
-        self.types.get_layout(type_hint)
+        Type::Custom(inst)
     }
-
-    fn consume(&mut self, err: CompileError) {
-        self.errorbox.push(err);
+    /// Generates the instance of the generic function `name` for `args`, once.
+    fn instantiate_fn(
+        &mut self,
+        origin: Option<String>,
+        name: &str,
+        args: &[Type],
+        span: &Span,
+    ) -> Option<Function> {
+        if args.contains(&Type::Poison) {
+            return None;
+        }
+        let template = self.find_template(origin.as_deref(), name, false, span)?;
+        let Node::GenericFnStmt {
+            generics: params,
+            bounds,
+            body,
+            args: fargs,
+            ret_type,
+            returns,
+            return_val,
+            vardaic,
+            doc,
+            ..
+        } = template.node
+        else {
+            return None;
+        };
+        if !self.check_type_args(name, &params, args, span)
+            || !self.check_bounds(name, &params, &bounds, args, origin.as_deref(), span)
+        {
+            return None;
+        }
+        let inst = generics::file_instance_name(&self.template_file(origin.as_deref()), name, args);
+        if let Some(f) = self.fn_instances.iter().find(|f| f.name == inst) {
+            return Some(f.clone());
+        }
+        if self.instances.contains(&inst) {
+            /* called from its own body, which plain functions cannot do either */
+            self.consume(CompileError::new(
+                format!("Use of undeclared symbol {name}"),
+                None,
+                span.clone(),
+                ErrLevel::ERROR,
+            ));
+            return None;
+        }
+        self.instances.push(inst.clone());
+        let source = self.instance_source(name, args);
+        self.instance_sources.insert(inst.clone(), source);
+        let mut node = Spanned {
+            node: Node::FnStmt {
+                body,
+                args: fargs,
+                name: inst.clone(),
+                ret_type,
+                returns,
+                return_val,
+                vardaic,
+                mangled_name: format!("_Jaguar_generic_{inst}"),
+                doc,
+            },
+            span: template.span,
+        };
+        generics::substitute(&mut node, &params, args, origin.as_deref());
+        let guard = format!("JAGUAR_GENERIC_FN_{inst}");
+        self.in_template_scope(origin.as_deref(), |g| {
+            g.instantiate_types(&mut node);
+            g.cur_section = Section::TEXT;
+            g.emit(&format!("\n#ifndef {guard}\n#define {guard}"));
+            g.generate(vec![node]);
+            g.cur_section = Section::TEXT;
+            g.emit("\n#endif");
+        });
+        let func = self
+            .func_table
+            .iter()
+            .rev()
+            .find(|f| f.name == inst)
+            .cloned()?;
+        self.fn_instances.push(func.clone());
+        Some(func)
+    }
+    /// Runs `f` at global scope, with the functions and bundles of the bundle `origin`
+    /// visible the way they are to the templates declared in it. What `f` declares is kept.
+    fn in_template_scope(&mut self, origin: Option<&str>, f: impl FnOnce(&mut Self)) {
+        let sc = self.current_context.clone();
+        let ss = self.cur_section.clone();
+        let sf = self.func_table.clone();
+        let sb = self.bundles.clone();
+        let rsp = self.track_rsp;
+        if let Some(bundle) = origin.and_then(|alias| sb.iter().find(|b| b.name == alias)) {
+            self.func_table = [bundle.functions.clone(), sf.clone()].concat();
+            self.bundles = [bundle.bundles.clone(), sb.clone()].concat();
+        }
+        let (nf, nb) = (self.func_table.len(), self.bundles.len());
+        self.current_context = self.global_context.clone();
+        f(self);
+        let new_funcs = self.func_table.split_off(nf);
+        let new_bundles = self.bundles.split_off(nb);
+        self.current_context = sc;
+        self.cur_section = ss;
+        self.func_table = sf;
+        self.func_table.extend(new_funcs);
+        self.bundles = sb;
+        self.bundles.extend(new_bundles);
+        self.track_rsp = rsp;
+    }
+    /// The trait `ty` names, `Name` or `alias::Name`. The method signatures of a trait from
+    /// a bundle are qualified with the bundle's alias, like its templates.
+    fn find_trait(&self, ty: &Type) -> Option<Trait> {
+        match ty {
+            Type::Custom(name) => self.traits.iter().find(|t| t.name == *name).cloned(),
+            Type::BundledType { bundle, ty } => {
+                let Type::Custom(name) = ty.as_ref() else {
+                    return None;
+                };
+                let b = self.bundles.iter().find(|b| b.name == *bundle)?;
+                let mut tr = b.traits.iter().find(|t| t.name == *name).cloned()?;
+                for meth in &mut tr.meths {
+                    generics::substitute(meth, &[], &[], Some(bundle));
+                }
+                Some(tr)
+            }
+            _ => None,
+        }
+    }
+    fn implements(&mut self, ty: &Type, tr: &Trait) -> bool {
+        self.get_layout(ty.clone())
+            .is_some_and(|l| l.traits.contains(&tr.id()))
+    }
+    /// Reports the type arguments that do not implement the traits their parameter is
+    /// bound by. Returns false if there were any.
+    fn check_bounds(
+        &mut self,
+        name: &str,
+        params: &[String],
+        bounds: &[(String, Type)],
+        args: &[Type],
+        origin: Option<&str>,
+        span: &Span,
+    ) -> bool {
+        let mut ok = true;
+        for (param, bound) in bounds {
+            let Some(i) = params.iter().position(|p| p == param) else {
+                continue;
+            };
+            let bound = match (origin, bound) {
+                (Some(alias), Type::Custom(_)) => Type::BundledType {
+                    bundle: alias.to_string(),
+                    ty: Box::new(bound.clone()),
+                },
+                _ => bound.clone(),
+            };
+            /* an unknown trait was reported with the template */
+            let Some(tr) = self.find_trait(&bound) else {
+                continue;
+            };
+            if !self.implements(&args[i], &tr) {
+                self.consume(CompileError::new(
+                    format!(
+                        "'{}' does not implement '{}'",
+                        args[i].debug(),
+                        bound.debug()
+                    ),
+                    Some(format!(
+                        "required by '{param}: {}' on '{name}'",
+                        bound.debug()
+                    )),
+                    span.clone(),
+                    ErrLevel::ERROR,
+                ));
+                ok = false;
+            }
+        }
+        ok
+    }
+    /// Declares the vtable of `tr`, the `dyn` type holding one, and a function per method
+    /// calling through it, e.g. `dyn_Show_show`. Methods called on a `dyn` go to these.
+    fn gen_dyn(&mut self, tr: &Trait) {
+        let name = &tr.name;
+        let mut stream = format!("\ntypedef struct {name}_vtable {{");
+        for (meth, args, ret) in tr.dyn_methods() {
+            /* prefixed, a method may be named like a C keyword */
+            let entry = format!("fn_{meth}");
+            stream += &format!("\n\t{};", traits::vtable_entry(&entry, args, ret));
+        }
+        stream += &format!(
+            "\n}} {name}_vtable;\ntypedef struct dyn_{name} {{\n\tvoid* data;\n\tconst {name}_vtable* vtable;\n}} dyn_{name};"
+        );
+        let mut methods = vec![];
+        for (meth, args, ret) in tr.dyn_methods() {
+            let mut params = vec![];
+            let mut call = vec![];
+            for arg in args {
+                if arg.name == "self" {
+                    params.push(format!("dyn_{name} self"));
+                    call.push("self.data".to_string());
+                } else {
+                    params.push(format!("{} {}", arg.type_hint.to_str(), arg.name));
+                    call.push(arg.name.clone());
+                }
+            }
+            let ret_kw = if *ret == Type::NoType { "" } else { "return " };
+            stream += &format!(
+                "\nstatic inline {} dyn_{name}_{meth}({}) {{\n\t{ret_kw}self.vtable->fn_{meth}({});\n}}",
+                ret.c_impl(),
+                params.join(", "),
+                call.join(", ")
+            );
+            let mut f = Function::new(
+                meth.clone(),
+                Context::new(meth.clone(), None),
+                ret.clone(),
+                true,
+                Node::Program(vec![]),
+            );
+            f.args = args.to_vec();
+            methods.push(f);
+        }
+        let save = self.cur_section.clone();
+        self.cur_section = Section::HEADER;
+        self.emit(&stream);
+        self.cur_section = save;
+        let ty = Type::Dyn(Box::new(Type::Custom(name.clone())));
+        self.types.add_type(
+            ty.clone(),
+            StructLayout {
+                name: ty,
+                feilds: HashMap::new(),
+                methods,
+                traits: vec![tr.id()],
+                file: self.current_file.clone(),
+            },
+        );
+    }
+    /// Turns `out`, a pointer to a value whose type implements `trait_name`, into a
+    /// `dyn trait_name` holding it and the type's vtable.
+    fn make_dyn(&mut self, out: ExprResult, trait_name: Type, span: &Span) -> ExprResult {
+        let dyn_type = Type::Dyn(Box::new(trait_name.clone()));
+        let mut ty = *out.type_hint.clone();
+        while let Type::MUT(inner) = ty {
+            ty = *inner;
+        }
+        let Type::PTR(mut pointee) = ty.clone() else {
+            /* a value of a type that could be behind it, anything else is a plain mismatch */
+            if !matches!(ty, Type::Dyn(_) | Type::Poison) && self.get_layout(ty.clone()).is_some() {
+                self.consume(CompileError::new(
+                    format!(
+                        "Expected a pointer for '{}', got '{}'",
+                        dyn_type.debug(),
+                        ty.debug()
+                    ),
+                    Some("a dyn points to its value, take its address with &".to_string()),
+                    span.clone(),
+                    ErrLevel::ERROR,
+                ));
+                return Self::poisoned();
+            }
+            return out;
+        };
+        while let Type::MUT(inner) = *pointee {
+            pointee = inner;
+        }
+        let Some(tr) = self.find_trait(&trait_name) else {
+            return out;
+        };
+        if matches!(*pointee, Type::Dyn(_) | Type::Poison) {
+            return out;
+        }
+        if !self.implements(&pointee, &tr) {
+            self.consume(CompileError::new(
+                format!(
+                    "'{}' does not implement '{}'",
+                    pointee.debug(),
+                    trait_name.debug()
+                ),
+                None,
+                span.clone(),
+                ErrLevel::ERROR,
+            ));
+            return Self::poisoned();
+        }
+        ExprResult {
+            preamble: out.preamble,
+            stream: format!(
+                "(({}){{.data = (void*)({}), .vtable = &{}_{}_vtable}})",
+                dyn_type.c_impl(),
+                out.stream,
+                pointee.c_impl(),
+                tr.name
+            ),
+            is_ref: false,
+            refed_var: None,
+            type_hint: Box::new(dyn_type),
+            var: None,
+        }
+    }
+    /// Checks an `impl` block against its trait and generates its methods, along with the
+    /// trait's defaults for the methods it leaves out, and the vtable a `dyn` of the trait
+    /// uses for the type.
+    fn gen_impl(&mut self, trait_name: Type, target: Type, meths: Vec<Spanned<Node>>, span: Span) {
+        let Some(tr) = self.find_trait(&trait_name) else {
+            self.consume(CompileError::new(
+                format!("No trait named '{}'", trait_name.debug()),
+                None,
+                span,
+                ErrLevel::ERROR,
+            ));
+            return;
+        };
+        if let Type::BundledType { .. } = target {
+            self.consume(CompileError::new(
+                format!(
+                    "Cannot implement '{}' for '{}' outside of the bundle that declares it",
+                    trait_name.debug(),
+                    target.debug()
+                ),
+                None,
+                span,
+                ErrLevel::ERROR,
+            ));
+            return;
+        }
+        let Some(layout) = self.get_layout(target.clone()) else {
+            if target != Type::Poison {
+                self.consume(CompileError::new(
+                    format!("Not a type, {}", target.debug()),
+                    None,
+                    span,
+                    ErrLevel::ERROR,
+                ));
+            }
+            return;
+        };
+        let (tname, ty) = (trait_name.debug(), target.debug());
+        if layout.traits.contains(&tr.id()) {
+            self.consume(CompileError::new(
+                format!("'{ty}' already implements '{tname}'"),
+                None,
+                span,
+                ErrLevel::ERROR,
+            ));
+            return;
+        }
+        let this = [String::from("Self")];
+        let target_args = [target.clone()];
+        let mut plugins = vec![];
+        for mut meth in meths {
+            generics::substitute(&mut meth, &this, &target_args, None);
+            let Node::FnStmt {
+                name,
+                args,
+                ret_type,
+                ..
+            } = &meth.node
+            else {
+                continue;
+            };
+            let Some((targs, tret)) = tr.method(name) else {
+                self.consume(CompileError::new(
+                    format!("'{name}' is not a method of trait '{tname}'"),
+                    None,
+                    meth.span.clone(),
+                    ErrLevel::ERROR,
+                ));
+                continue;
+            };
+            let mut targs = targs.to_vec();
+            let mut tret = tret.clone();
+            for ty in targs
+                .iter_mut()
+                .map(|a| &mut a.type_hint)
+                .chain([&mut tret])
+            {
+                if *ty == Type::Custom("Self".into()) {
+                    *ty = target.clone();
+                }
+            }
+            let matches = args.len() == targs.len()
+                && args.iter().zip(&targs).all(|(a, b)| {
+                    (a.name == "self") == (b.name == "self")
+                        && self.type_match(a.type_hint.clone(), b.type_hint.clone())
+                })
+                && self.type_match(ret_type.clone(), tret.clone());
+            if !matches {
+                self.consume(CompileError::new(
+                    format!("Method '{name}' does not match its declaration in trait '{tname}'"),
+                    Some(format!(
+                        "'{tname}' declares {}",
+                        traits::signature(name, &targs, &tret)
+                    )),
+                    meth.span.clone(),
+                    ErrLevel::ERROR,
+                ));
+                continue;
+            }
+            plugins.push(meth);
+        }
+        for meth in &tr.meths {
+            match &meth.node {
+                Node::FnSig {
+                    name,
+                    args,
+                    ret_type,
+                    ..
+                } if !plugins.iter().any(|p| fn_name(p) == Some(name)) => {
+                    self.consume(CompileError::new(
+                        format!("Missing method '{name}' in impl of '{tname}' for '{ty}'"),
+                        Some(format!(
+                            "'{tname}' declares {}",
+                            traits::signature(name, args, ret_type)
+                        )),
+                        span.clone(),
+                        ErrLevel::ERROR,
+                    ));
+                }
+                Node::FnStmt { name, .. } if !plugins.iter().any(|p| fn_name(p) == Some(name)) => {
+                    let mut default = meth.clone();
+                    generics::substitute(&mut default, &this, &target_args, None);
+                    plugins.push(default);
+                }
+                _ => {}
+            }
+        }
+        for plugin in plugins.iter_mut() {
+            self.instantiate_types(plugin);
+        }
+        /* recorded even when methods are missing, they were reported already */
+        self.types.add_trait(target.clone(), tr.id());
+        self.gen_plugins(target.clone(), plugins);
+        let vtable = format!("{}_vtable", tr.name);
+        let mut stream = format!("\nconst {vtable} {}_{vtable} = {{", target.c_impl());
+        for (name, args, ret) in tr.dyn_methods() {
+            stream += &format!(
+                "\n\t.fn_{name} = ({}){}_{name},",
+                traits::vtable_entry("", args, ret),
+                target.c_impl()
+            );
+        }
+        stream += "\n};";
+        self.cur_section = Section::TEXT;
+        self.emit(&stream);
+    }
+    /// Declares the enum `name`: its tagged struct, see [`super::enums`], then its methods.
+    fn gen_enum(
+        &mut self,
+        name: String,
+        variants: Vec<Variant>,
+        meths: Vec<Spanned<Node>>,
+        doc: Option<String>,
+    ) {
+        let en = Enum {
+            name: name.clone(),
+            variants: variants.clone(),
+            template: None,
+        };
+        let tags = en.tags();
+        let mut union = String::new();
+        for (i, v) in variants.iter().enumerate() {
+            if variants[..i].iter().any(|w| w.name == v.name) {
+                self.consume(CompileError::new(
+                    format!("Variant '{}' is declared twice in enum '{name}'", v.name),
+                    None,
+                    v.span.clone(),
+                    ErrLevel::ERROR,
+                ));
+                continue;
+            }
+            if let Some(j) = tags[..i].iter().position(|t| *t == tags[i]) {
+                self.consume(CompileError::new(
+                    format!(
+                        "Discriminant {} of '{}' is already used by '{}'",
+                        tags[i], v.name, variants[j].name
+                    ),
+                    None,
+                    v.span.clone(),
+                    ErrLevel::ERROR,
+                ));
+            }
+            if v.payload.is_empty() {
+                continue;
+            }
+            let mut members = String::new();
+            for (k, ty) in v.payload.iter().enumerate() {
+                if self.get_layout(ty.clone()).is_none() {
+                    if *ty != Type::Poison {
+                        self.consume(CompileError::new(
+                            format!("Not a type, {}", ty.debug()),
+                            None,
+                            v.span.clone(),
+                            ErrLevel::ERROR,
+                        ));
+                    }
+                    continue;
+                }
+                members += &format!(" {} _{k};", ty.to_str());
+            }
+            union += &c_doc(&v.doc, "\t\t");
+            union += &format!("\n\t\tstruct {{{members} }} {};", v.name);
+        }
+        self.cur_section = Section::HEADER;
+        self.emit(&c_doc(&doc, ""));
+        self.emit(&format!("\ntypedef struct {name} {{"));
+        self.emit(&format!("\n\t{} tag;", Type::I64.to_str()));
+        if !union.is_empty() {
+            self.emit(&format!("\n\tunion {{{union}\n\t}} as;"));
+        }
+        self.emit(&format!("\n}} {name};"));
+        let layout = StructLayout {
+            name: Type::Custom(name.clone()),
+            feilds: HashMap::new(),
+            methods: Vec::new(),
+            traits: Vec::new(),
+            file: self.current_file.clone(),
+        };
+        self.types.add_type(Type::Custom(name.clone()), layout);
+        self.enums.push(en);
+        self.gen_plugins(Type::Custom(name), meths);
+    }
+    /// `lhs == rhs` or `lhs != rhs` on values of the enum `en`. Only the tags are compared,
+    /// so only an enum whose variants hold no values can be.
+    fn gen_enum_compare(
+        &mut self,
+        en: Enum,
+        lhs: ExprResult,
+        op: &str,
+        rhs: Box<Spanned<Node>>,
+        span: Span,
+    ) -> ExprResult {
+        let mut lhs_type = *lhs.type_hint;
+        while let Type::MUT(inner) = lhs_type {
+            lhs_type = *inner;
+        }
+        let out = self.gen_expr(rhs, lhs_type.clone(), RefStyle::COPY);
+        if !self.type_match(lhs_type.clone(), *out.type_hint.clone()) {
+            self.consume(CompileError::new(
+                format!(
+                    "mismatched types. cannot compare '{}' and '{}'",
+                    lhs_type.debug(),
+                    out.type_hint.debug()
+                ),
+                None,
+                span,
+                ErrLevel::ERROR,
+            ));
+        } else if !en.is_plain() {
+            self.consume(CompileError::new(
+                format!(
+                    "Cannot compare '{}' values with '{op}', some of its variants hold values",
+                    lhs_type.debug()
+                ),
+                Some("Compare their discriminants instead, 'a as int == b as int'".to_owned()),
+                span,
+                ErrLevel::ERROR,
+            ));
+        }
+        ExprResult {
+            preamble: String::new(),
+            stream: format!("( ({}).tag {op} ({}).tag )", lhs.stream, out.stream),
+            is_ref: false,
+            refed_var: None,
+            type_hint: Box::new(Type::BOOL),
+            var: None,
+        }
+    }
+    /// The enum a value of type `ty` is, with the payload types of one from a bundle
+    /// qualified with its alias.
+    fn enum_of(&self, ty: &Type) -> Option<Enum> {
+        match ty {
+            Type::MUT(inner) => self.enum_of(inner),
+            Type::Custom(name) => self.enums.iter().find(|e| e.name == *name).cloned(),
+            Type::BundledType { bundle, ty } => {
+                let Type::Custom(name) = ty.as_ref() else {
+                    return None;
+                };
+                let b = self.bundles.iter().find(|b| b.name == *bundle)?;
+                let en = b.enums.iter().find(|e| e.name == *name)?;
+                Some(en.qualified(bundle))
+            }
+            _ => None,
+        }
+    }
+    /// The type of the enum `name` a constructor names, declared here or, given `origin`,
+    /// in the bundle with that alias. A generic enum is found through `target`, the
+    /// instance the value is expected to be; when it is not one, that is reported and
+    /// the type is `Poison`. None if `name` is no enum.
+    fn find_enum(
+        &mut self,
+        origin: Option<&str>,
+        name: &str,
+        target: &Type,
+        span: &Span,
+    ) -> Option<Type> {
+        let qualified = match origin {
+            Some(alias) => format!("{alias}::{name}"),
+            None => name.to_string(),
+        };
+        let is_generic = match origin {
+            None => self
+                .templates
+                .iter()
+                .any(|t| matches!(&t.node, Node::GenericEnumStmt { name: n, .. } if n == name)),
+            Some(alias) => self.bundles.iter().any(|b| {
+                b.name == alias
+                    && b.templates.iter().any(
+                        |t| matches!(&t.node, Node::GenericEnumStmt { name: n, .. } if n == name),
+                    )
+            }),
+        };
+        if is_generic {
+            let instance = self
+                .enum_of(target)
+                .filter(|en| en.template.as_deref() == Some(qualified.as_str()));
+            if let Some(en) = instance {
+                return Some(Type::Custom(en.name));
+            }
+            if *target != Type::Poison {
+                self.consume(CompileError::new(
+                    format!("Cannot tell which '{qualified}' this is"),
+                    Some(format!(
+                        "'{qualified}' is generic, give the value a type, e.g. 'let x: {qualified}<int> = ...'"
+                    )),
+                    span.clone(),
+                    ErrLevel::ERROR,
+                ));
+            }
+            return Some(Type::Poison);
+        }
+        match origin {
+            None => self
+                .enums
+                .iter()
+                .any(|e| e.name == name && e.template.is_none())
+                .then(|| Type::Custom(name.to_string())),
+            Some(alias) => {
+                let ty = Type::BundledType {
+                    bundle: alias.to_string(),
+                    ty: Box::new(Type::Custom(name.to_string())),
+                };
+                self.enum_of(&ty).map(|_| ty)
+            }
+        }
+    }
+    /// Generates `Enum::Variant` or `Enum::Variant(values)`, `variant` being what follows
+    /// the `::`, as a value of the enum type `ty`.
+    fn gen_variant(&mut self, ty: Type, variant: Spanned<Node>, span: &Span) -> ExprResult {
+        let Some(en) = self.enum_of(&ty) else {
+            return Self::poisoned();
+        };
+        let (vname, params) = match variant.node {
+            Node::Token(vname, _) => (vname, vec![]),
+            Node::FcCall { callee, params } => match callee.node {
+                Node::Token(vname, _) => (vname, params),
+                _ => (String::new(), params),
+            },
+            _ => (String::new(), vec![]),
+        };
+        let Some(v) = en.variant(&vname).cloned() else {
+            let msg = match vname.as_str() {
+                "" => format!("Expected a variant of enum '{}'", ty.debug()),
+                _ => format!("No variant '{vname}' in enum '{}'", ty.debug()),
+            };
+            self.consume(CompileError::new(msg, None, variant.span, ErrLevel::ERROR));
+            return Self::poisoned();
+        };
+        let full = format!("{}::{vname}", ty.debug());
+        if params.len() != v.payload.len() {
+            self.consume(CompileError::new(
+                format!(
+                    "'{full}' holds {} values but {} were given",
+                    v.payload.len(),
+                    params.len()
+                ),
+                None,
+                span.clone(),
+                ErrLevel::ERROR,
+            ));
+            return Self::poisoned();
+        }
+        let mut preamble = String::new();
+        let mut values = vec![];
+        for (param, pty) in params.iter().zip(v.payload.iter()) {
+            let out = self.gen_expr(Box::new(param.clone()), pty.clone(), RefStyle::COPY);
+            if !self.type_match(pty.clone(), *out.type_hint.clone()) {
+                self.consume(CompileError::new(
+                    format!(
+                        "Type Mismatch. '{full}' expected '{}' but got '{}' instead",
+                        pty.debug(),
+                        out.type_hint.debug()
+                    ),
+                    None,
+                    param.span.clone(),
+                    ErrLevel::ERROR,
+                ));
+            }
+            preamble += &out.preamble;
+            values.push(out.stream);
+        }
+        let tag = en.tag(&vname).unwrap_or_default();
+        let mut payload = String::new();
+        if !values.is_empty() {
+            payload = format!(", .as.{vname} = {{{}}}", values.join(", "));
+        }
+        ExprResult {
+            preamble,
+            stream: format!("(({}){{.tag = {tag}{payload}}})", ty.to_str()),
+            is_ref: false,
+            refed_var: None,
+            type_hint: Box::new(ty),
+            var: None,
+        }
+    }
+    /// Generates `match scrutinee { arms }`. Each pattern is checked against the type of
+    /// the value, an arm earlier ones leave no value to is warned about, and values no arm
+    /// takes are reported. When every arm is an expression the match is one too and picks
+    /// its value with `?:`. Otherwise it is a `switch`, or an if chain when there are
+    /// guards, ranges or strings, or a `break` the `switch` would take for its own.
+    fn gen_match(
+        &mut self,
+        scrutinee: Box<Spanned<Node>>,
+        arms: Vec<MatchArm>,
+        target_type: Type,
+    ) -> ExprResult {
+        let value = self.gen_expr(scrutinee.clone(), Type::Any, RefStyle::COPY);
+        let mut ty = *value.type_hint.clone();
+        while let Type::MUT(inner) = ty {
+            ty = *inner;
+        }
+        let en = self.enum_of(&ty);
+        let coverage = match &en {
+            Some(en) => {
+                let prefix = en.template.clone().unwrap_or(ty.debug());
+                Some(Coverage::variants(&prefix, en))
+            }
+            None => Coverage::of(&ty),
+        };
+        let Some(mut coverage) = coverage else {
+            if ty != Type::Poison {
+                self.consume(CompileError::new(
+                    format!("Cannot match on a value of type '{}'", ty.debug()),
+                    Some("match takes integers, chars, bools, strings and enums".to_owned()),
+                    scrutinee.span.clone(),
+                    ErrLevel::ERROR,
+                ));
+            }
+            return Self::poisoned();
+        };
+        let m = self.gb();
+        let subject = match (&en, coverage.bounds()) {
+            (Some(_), _) => format!("{m}.tag"),
+            (None, Some((_, _, Kind::Char))) => format!("((unsigned char){m})"),
+            _ => m.clone(),
+        };
+        let mut has_error = false;
+        let mut result_type = target_type;
+        while let Type::MUT(inner) = result_type {
+            result_type = *inner;
+        }
+        /* pattern, guard, body of the arms left to emit, the body as statements or an expression */
+        let mut emitted: Vec<(CheckedPattern, Option<String>, ArmBody)> = vec![];
+        let mut all_expressions = true;
+        let mut arms_break = false;
+        for arm in arms {
+            let checked =
+                self.check_pattern(&arm.pattern, &ty, en.as_ref(), &coverage, &m, &subject);
+            let Some(checked) = checked else {
+                has_error = true;
+                continue;
+            };
+            let reachable = !coverage.covers(&checked.part);
+            if !reachable {
+                self.consume(CompileError::new(
+                    "Unreachable match arm".to_owned(),
+                    Some("the arms before it already match every value it does".to_owned()),
+                    arm.pattern.span.clone(),
+                    ErrLevel::WARNING,
+                ));
+            }
+            if arm.guard.is_none() {
+                coverage.add(checked.part.clone());
+            }
+            /* exit_scope would leave for the global scope, the arm's scope is dropped instead */
+            let saved_context = self.current_context.clone();
+            self.change_scope("match");
+            let mut binds = String::new();
+            for (name, bty, val) in &checked.binds {
+                self.current_context.add(Var::new(
+                    name.clone(),
+                    bty.clone(),
+                    false,
+                    None,
+                    arm.pattern.span.clone(),
+                ));
+                binds += &format!("{} {name} = {val}; ", bty.to_str());
+            }
+            let guard = arm.guard.map(|g| {
+                let out = self.gen_expr(Box::new(g.clone()), Type::Any, RefStyle::COPY);
+                self.check_condition(*out.type_hint.clone(), g.span);
+                format!("({{ {binds}{}{}; }})", out.preamble, out.stream)
+            });
+            let body = match arm.body.node {
+                Node::Program(k) => {
+                    all_expressions = false;
+                    arms_break |= k.iter().any(|n| breaks_out(&n.node));
+                    let mut body = binds.clone();
+                    for node in k {
+                        let o = self.gen_expr(Box::new(node), Type::Any, RefStyle::COPY);
+                        body += &format!("{}{};\n", o.preamble, o.stream);
+                    }
+                    ArmBody::Block(body)
+                }
+                _ => {
+                    let out = self.gen_expr(
+                        Box::new(arm.body.clone()),
+                        result_type.clone(),
+                        RefStyle::COPY,
+                    );
+                    let mut arm_type = *out.type_hint.clone();
+                    while let Type::MUT(inner) = arm_type {
+                        arm_type = *inner;
+                    }
+                    if result_type == Type::Any {
+                        result_type = arm_type;
+                    } else if result_type != Type::NoType
+                        && !self.type_match(result_type.clone(), arm_type.clone())
+                    {
+                        self.consume(CompileError::new(
+                            format!(
+                                "Type Mismatch. the match arms before this one are '{}' but it is '{}'",
+                                result_type.debug(),
+                                arm_type.debug()
+                            ),
+                            None,
+                            arm.body.span.clone(),
+                            ErrLevel::ERROR,
+                        ));
+                    }
+                    ArmBody::Expr {
+                        preamble: format!("{binds}{}", out.preamble),
+                        value: out.stream,
+                    }
+                }
+            };
+            self.current_context = saved_context;
+            if reachable {
+                emitted.push((checked, guard, body));
+            }
+        }
+        /* an arm whose pattern is wrong may have been meant to take what is missing */
+        let missing = coverage.missing().filter(|_| !has_error);
+        if let Some(missing) = missing {
+            self.consume(CompileError::new(
+                format!("Non-exhaustive match, {missing}"),
+                Some("add an arm for it, or a '_' arm".to_owned()),
+                scrutinee.span.clone(),
+                ErrLevel::ERROR,
+            ));
+        }
+        let decl = format!("{} {m} = {};", ty.to_str(), value.stream);
+        let is_value = all_expressions && !matches!(result_type, Type::NoType | Type::Any);
+        let stream = if is_value {
+            /* the last arm is taken when no other is, the match is exhaustive */
+            let mut choice = String::new();
+            let count = emitted.len();
+            for (i, (checked, guard, body)) in emitted.into_iter().enumerate() {
+                let ArmBody::Expr { preamble, value } = body else {
+                    continue;
+                };
+                let value = if preamble.is_empty() {
+                    format!("({value})")
+                } else {
+                    format!("({{ {preamble}{value}; }})")
+                };
+                let cond = match (checked.cond, guard) {
+                    (Some(c), Some(g)) => Some(format!("({c} && {g})")),
+                    (c, g) => c.or(g),
+                };
+                match cond {
+                    Some(cond) if i + 1 < count => choice += &format!("{cond} ? {value} : "),
+                    _ => {
+                        choice += &value;
+                        break;
+                    }
+                }
+            }
+            format!("({{ {decl} {choice}; }})")
+        } else {
+            let as_switch = !arms_break
+                && emitted.iter().all(|(checked, guard, _)| {
+                    guard.is_none() && (checked.case.is_some() || checked.cond.is_none())
+                });
+            let mut stream = format!("{{\n{decl}\n");
+            if as_switch {
+                stream += &format!("switch ({subject}) {{\n");
+                for (checked, _, body) in emitted {
+                    let label = match checked.case {
+                        Some(case) => format!("case {case}"),
+                        None => "default".to_owned(),
+                    };
+                    stream += &format!("{label}: {{\n{}}} break;\n", arm_statements(body));
+                }
+                stream += "}\n";
+            } else {
+                for (i, (checked, guard, body)) in emitted.into_iter().enumerate() {
+                    let cond = match (checked.cond, guard) {
+                        (Some(c), Some(g)) => Some(format!("{c} && {g}")),
+                        (c, g) => c.or(g),
+                    };
+                    let body = arm_statements(body);
+                    match (i, cond) {
+                        (0, Some(cond)) => stream += &format!("if ({cond}) {{\n{body}}}"),
+                        (_, Some(cond)) => stream += &format!(" else if ({cond}) {{\n{body}}}"),
+                        (0, None) => stream += &format!("{{\n{body}}}"),
+                        (_, None) => stream += &format!(" else {{\n{body}}}"),
+                    }
+                }
+                stream += "\n";
+            }
+            stream += "}";
+            result_type = Type::NoType;
+            stream
+        };
+        ExprResult {
+            preamble: value.preamble,
+            stream,
+            is_ref: false,
+            refed_var: None,
+            type_hint: Box::new(result_type),
+            var: None,
+        }
+    }
+    /// Checks `pattern` against `ty`, the type of the matched value `m`. `subject` is what
+    /// the value is compared as, `en` its enum and `coverage` the space of its values.
+    /// Reports what is wrong with the pattern and returns None then.
+    fn check_pattern(
+        &mut self,
+        pattern: &Spanned<Pattern>,
+        ty: &Type,
+        en: Option<&Enum>,
+        coverage: &Coverage,
+        m: &str,
+        subject: &str,
+    ) -> Option<CheckedPattern> {
+        let span = &pattern.span;
+        let checked = match &pattern.node {
+            Pattern::Wildcard => CheckedPattern {
+                part: Part::All,
+                cond: None,
+                case: None,
+                binds: vec![],
+            },
+            Pattern::Binding(name) => CheckedPattern {
+                part: Part::All,
+                cond: None,
+                case: None,
+                binds: vec![(name.clone(), ty.clone(), m.to_owned())],
+            },
+            Pattern::Literal(lit) if *ty == Type::STR => {
+                let Node::LiteralStr(text) = lit.as_ref() else {
+                    self.pattern_mismatch(ty, &format!("'{}'", literal_type(lit).debug()), span);
+                    return None;
+                };
+                CheckedPattern {
+                    part: Part::Str(text.clone()),
+                    cond: Some(format!("str_eq({m}, {}) == 0", c_string_literal(text))),
+                    case: None,
+                    binds: vec![],
+                }
+            }
+            Pattern::Literal(lit) => {
+                let value = self.pattern_value(lit, ty, coverage, span)?;
+                let c = c_number(value, ty);
+                CheckedPattern {
+                    part: Part::Range(value, value),
+                    cond: Some(format!("{subject} == {c}")),
+                    case: Some(c),
+                    binds: vec![],
+                }
+            }
+            Pattern::Range { lo, hi, inclusive } => {
+                let Some((min, max, _)) = coverage.bounds() else {
+                    self.consume(CompileError::new(
+                        format!("Cannot match a '{}' with a range", ty.debug()),
+                        None,
+                        span.clone(),
+                        ErrLevel::ERROR,
+                    ));
+                    return None;
+                };
+                let lo = self.pattern_value(lo, ty, coverage, span)?;
+                let mut hi = self.pattern_value(hi, ty, coverage, span)?;
+                if !inclusive {
+                    hi -= 1;
+                }
+                if hi < lo {
+                    self.consume(CompileError::new(
+                        "Empty range, no value is in it".to_owned(),
+                        Some("ranges go from the lower bound up to the higher one".to_owned()),
+                        span.clone(),
+                        ErrLevel::ERROR,
+                    ));
+                    return None;
+                }
+                /* a bound at the end of the type always holds, C compilers warn about it */
+                let mut conds = vec![];
+                if lo > min {
+                    conds.push(format!("{subject} >= {}", c_number(lo, ty)));
+                }
+                if hi < max {
+                    conds.push(format!("{subject} <= {}", c_number(hi, ty)));
+                }
+                CheckedPattern {
+                    part: Part::Range(lo, hi),
+                    cond: (!conds.is_empty()).then(|| format!("({})", conds.join(" && "))),
+                    case: None,
+                    binds: vec![],
+                }
+            }
+            Pattern::Variant { path, bindings } => {
+                let shown = path.join("::");
+                /* the enum is named by the path segment before the variant */
+                let named = path[path.len() - 2].as_str();
+                let en = en.filter(|en| {
+                    let template = en.template.as_deref();
+                    en.name == named || template.and_then(|t| t.rsplit("::").next()) == Some(named)
+                });
+                let Some(en) = en else {
+                    self.pattern_mismatch(ty, &format!("'{shown}'"), span);
+                    return None;
+                };
+                let vname = &path[path.len() - 1];
+                let Some(i) = en.variants.iter().position(|v| v.name == *vname) else {
+                    self.consume(CompileError::new(
+                        format!("No variant '{vname}' in enum '{}'", ty.debug()),
+                        None,
+                        span.clone(),
+                        ErrLevel::ERROR,
+                    ));
+                    return None;
+                };
+                let payload = &en.variants[i].payload;
+                if bindings.len() != payload.len() {
+                    self.consume(CompileError::new(
+                        format!(
+                            "'{shown}' holds {} values but {} are bound",
+                            payload.len(),
+                            bindings.len()
+                        ),
+                        Some("bind the values you do not need to '_'".to_owned()),
+                        span.clone(),
+                        ErrLevel::ERROR,
+                    ));
+                    return None;
+                }
+                let mut binds: Vec<(String, Type, String)> = vec![];
+                for (k, (name, pty)) in bindings.iter().zip(payload).enumerate() {
+                    if name == "_" {
+                        continue;
+                    }
+                    if binds.iter().any(|(b, _, _)| b == name) {
+                        self.consume(CompileError::new(
+                            format!("'{name}' is bound twice in '{shown}'"),
+                            None,
+                            span.clone(),
+                            ErrLevel::ERROR,
+                        ));
+                        return None;
+                    }
+                    binds.push((name.clone(), pty.clone(), format!("{m}.as.{vname}._{k}")));
+                }
+                let tag = c_number(en.tags()[i] as i128, &Type::I64);
+                CheckedPattern {
+                    part: Part::Variant(i),
+                    cond: Some(format!("{subject} == {tag}")),
+                    case: Some(tag),
+                    binds,
+                }
+            }
+        };
+        Some(checked)
+    }
+    /// The number an integer, char or bool literal in a pattern stands for, when it is
+    /// one of the values of `ty`, whose space is `coverage`.
+    fn pattern_value(
+        &mut self,
+        lit: &Node,
+        ty: &Type,
+        coverage: &Coverage,
+        span: &Span,
+    ) -> Option<i128> {
+        let found = literal_type(lit);
+        let (value, kind) = match lit {
+            Node::LiteralInt(num, _) => (num.parse::<i128>().unwrap(), Kind::Int),
+            Node::LiteralCh(c) => (*c as i128, Kind::Char),
+            Node::LiteralBool(b) => (*b as i128, Kind::Bool),
+            _ => (0, Kind::Int),
+        };
+        let bounds = coverage.bounds().filter(|(_, _, k)| *k == kind);
+        let Some((min, max, _)) = bounds.filter(|_| found != Type::STR) else {
+            self.pattern_mismatch(ty, &format!("'{}'", found.debug()), span);
+            return None;
+        };
+        /* int is i32 */
+        let want = match ty {
+            Type::INT => Type::I32,
+            other => other.clone(),
+        };
+        if kind == Kind::Int && found != Type::INT && found != want {
+            self.consume(CompileError::new(
+                format!(
+                    "mismatched type. literal is {} but {} is expected",
+                    found.debug(),
+                    ty.debug()
+                ),
+                Some(format!("Change the suffix to {}", ty.debug())),
+                span.clone(),
+                ErrLevel::ERROR,
+            ));
+            return None;
+        }
+        if value < min || value > max {
+            self.consume(CompileError::new(
+                format!(
+                    "{} does not fit in '{}'",
+                    patterns::show(value, kind),
+                    ty.debug()
+                ),
+                Some(format!(
+                    "'{}' goes from {} to {}",
+                    ty.debug(),
+                    patterns::show(min, kind),
+                    patterns::show(max, kind)
+                )),
+                span.clone(),
+                ErrLevel::ERROR,
+            ));
+            return None;
+        }
+        Some(value)
+    }
+    fn pattern_mismatch(&mut self, ty: &Type, found: &str, span: &Span) {
+        self.consume(CompileError::new(
+            format!(
+                "Mismatched types. expected a pattern of type '{}', found {found}",
+                ty.debug()
+            ),
+            None,
+            span.clone(),
+            ErrLevel::ERROR,
+        ));
+    }
+    /// `target = value` for a value of type `ty`. The members of a struct or enum are const
+    /// in C, which leaves it unassignable as a whole, so one is copied over instead.
+    fn assign(&mut self, target: &str, value: &str, ty: &Type) -> String {
+        let mut ty = ty.clone();
+        while let Type::MUT(inner) = ty {
+            ty = *inner;
+        }
+        if is_builtin(ty.clone()) {
+            return format!("{target} = {value}");
+        }
+        let tmp = self.gb();
+        format!(
+            "({{ {} {tmp} = {value}; __builtin_memcpy((void*)&({target}), &{tmp}, sizeof {tmp}); }})",
+            ty.to_str()
+        )
+    }
+    fn lookup_variable(&mut self, name: &str) -> Option<&mut Var> {
+        self.current_context.look_up_var(name)
+    }
+
+    fn emit(&mut self, text: &str) {
+        match self.cur_section {
+            Section::TEXT => {
+                self.text += text;
+            }
+            Section::FUNC => {
+                self.func += text;
+            }
+            Section::HEADER => {
+                self.header += text;
+            }
+        }
+    }
+
+    fn get_layout(&mut self, type_hint: Type) -> Option<StructLayout> {
+        /* the dyn of a bundle's trait is described by the bundle */
+        let bundled_dyn = match &type_hint {
+            Type::Dyn(tr) => match tr.as_ref() {
+                Type::BundledType { bundle, ty } => Some(Type::BundledType {
+                    bundle: bundle.clone(),
+                    ty: Box::new(Type::Dyn(ty.clone())),
+                }),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(ty) = bundled_dyn {
+            return self.get_layout(ty);
+        }
+        if let Type::BundledType { bundle, ty } = type_hint {
+            let bd = self.bundles.iter().find(|b| b.name == bundle).cloned();
+            if matches!(bd, None) {
+                return None;
+            }
+            let sb = self.bundles.clone();
+            let st = self.types.clone();
+            let sc = self.current_context.clone();
+            let sv = self.var_table.clone();
+            self.bundles = bd.clone().unwrap().bundles.clone();
+            self.types = bd.clone().unwrap().types.clone();
+            self.var_table = bd.unwrap().vars;
+            let g = self.get_layout(*ty);
+            self.bundles = sb.clone();
+            self.types = st.clone();
+            self.current_context = sc.clone();
+            self.var_table = sv.clone();
+            return g;
+        } else if let Type::PTR(ty) = type_hint {
+            return self.get_layout(*ty);
+        } else if let Type::MUT(ty) = type_hint {
+            return self.get_layout(*ty);
+        }
+        self.types.get_layout(type_hint)
+    }
+
+    fn consume(&mut self, err: CompileError) {
+        self.errorbox.push(err);
     }
     fn resolve_type(&mut self, t: Type) -> Type {
         match t.clone() {
@@ -2430,9 +4500,15 @@ impl Generator {
             ) => {
                 let l1 = self.get_layout(*ty_1.clone()).clone();
                 let l2 = self.get_layout(*ty_2.clone()).clone();
-                l1.unwrap().file == l2.unwrap().file && self.type_match(*ty_1, *ty_2)
+                match (l1, l2) {
+                    (Some(l1), Some(l2)) => l1.file == l2.file && self.type_match(*ty_1, *ty_2),
+                    _ => false,
+                }
             }
             (Type::Any, _) => true,
+            (Type::Poison, _) | (_, Type::Poison) => true,
+            /* the C type is named after the trait */
+            (Type::Dyn(a), Type::Dyn(b)) => a.c_impl() == b.c_impl(),
             (_, Type::BundledType { bundle: _, ty: _ })
             | (Type::BundledType { bundle: _, ty: _ }, _) => false,
             (Type::MUT(t), other) => self.type_match(*t, other),
@@ -2447,31 +4523,14 @@ impl Generator {
             (_other, Type::STR) => false,
             (Type::List(t, s), Type::List(t2, s2)) => self.type_match(*t, *t2) && s == s2,
             (Type::CHAR, Type::CHAR) => true,
+            (Type::BOOL, Type::BOOL) => true,
             (Type::NoType, Type::NoType) => true,
             (Type::PTR(v), Type::PTR(c)) => self.type_match(*v, *c),
+            (Type::F32, Type::F32) | (Type::F64, Type::F64) => true,
             (int1, int2) => is_int(int1) && is_int(int2),
         }
     }
 
-    fn flush(&mut self) {
-        let mut shld_err = false;
-        for err in self.errorbox.clone() {
-            match err.level {
-                ErrLevel::WARNING => {
-                    self.warn(err);
-                }
-                ErrLevel::ERROR => {
-                    self.error(err);
-                    shld_err = true;
-                }
-                ErrLevel::INFO => self.warn(err),
-            }
-        }
-        if !self.errorbox.is_empty() && shld_err {
-            exit(1);
-        }
-    }
-
     fn register_plugin(&mut self, ty: Type, plugin: Function) {
         if let Type::BundledType { bundle, ty } = ty {
             let bd = self.bundles.iter().find(|b| b.name == bundle).cloned();
@@ -2498,6 +4557,62 @@ impl Generator {
         name.into()
     }
 
+    /// Reports an operand `opr` cannot be applied to. Returns false if it did.
+    fn check_operand(&mut self, opr: &TokenType, ty: Type, span: Span) -> bool {
+        let TokenType::Operator(op) = opr else {
+            return true;
+        };
+        if ty == Type::Poison {
+            return true;
+        }
+        let (supported, msg) = match op.as_str() {
+            "&&" | "||" => (
+                is_bool(ty.clone()),
+                format!(
+                    "Operator '{op}' expects bool operands, found '{}'",
+                    ty.debug()
+                ),
+            ),
+            "==" | "!=" => (true, String::new()),
+            "%" | "&" | "|" | "^" | "<<" | ">>" => (
+                is_int(ty.clone()),
+                format!("Type {} does not support '{op}'", ty.debug()),
+            ),
+            _ => (
+                is_numeric(ty.clone()),
+                format!("Type {} does not support binary arithmetic'", ty.debug()),
+            ),
+        };
+        if !supported {
+            self.consume(CompileError::new(msg, None, span, ErrLevel::ERROR));
+        }
+        supported
+    }
+
+    /// Conditions of if/while/for must be bool. With `int_conditions` an integer
+    /// only gets a warning, C treats it as `!= 0`.
+    fn check_condition(&mut self, ty: Type, span: Span) {
+        if ty == Type::Poison || is_bool(ty.clone()) {
+            return;
+        }
+        let level = if self.int_conditions && is_int(ty.clone()) {
+            ErrLevel::WARNING
+        } else {
+            ErrLevel::ERROR
+        };
+        let help = if is_numeric(ty.clone()) {
+            Some("Compare it explicitly, e.g. 'x != 0'".to_owned())
+        } else {
+            None
+        };
+        self.consume(CompileError::new(
+            format!("Expected a bool condition, found '{}'", ty.debug()),
+            help,
+            span,
+            level,
+        ));
+    }
+
     fn check_overflow(&mut self, value: i128, target_type: Type, span: Span) {
         match target_type.clone() {
             Type::U8 => {
@@ -2617,14 +4732,14 @@ impl Generator {
                 }
             }
             Type::INT => {
-                if value < i64::MIN as i128 || value > i64::MAX as i128 {
-                    let warp = value as i64;
+                if value < i32::MIN as i128 || value > i32::MAX as i128 {
+                    let warp = value as i32;
                     self.consume(CompileError::new(
-                        format!("u16 overflow. value {value} warped to {warp}"),
+                        format!("int overflow. value {value} warped to {warp}"),
                         Some(format!(
-                            "Use from range {}..{} for type int a.k.a i64",
-                            i64::MIN,
-                            i64::MAX
+                            "Use from range {}..{} for type int a.k.a i32",
+                            i32::MIN,
+                            i32::MAX
                         )),
                         span,
                         ErrLevel::WARNING,
@@ -2643,12 +4758,14 @@ impl Generator {
         let a = self.resolve_type(ty);
         let b = self.resolve_type(type_hint);
         match (a, b) {
+            (Type::Poison, _) | (_, Type::Poison) => true,
             (Type::PTR(_v), Type::PTR(_f)) => true,
             (Type::STR, Type::PTR(_v)) => true,
             (Type::PTR(_v), Type::STR) => true,
             (v, Type::PTR(_v)) => is_int(v),
             (Type::PTR(_v), v) => is_int(v),
-            (int1, int2) => is_int(int1.clone()) && is_int(int2.clone()) || int1 == int2,
+            (Type::BOOL, v) | (v, Type::BOOL) => is_int(v.clone()) || v == Type::BOOL,
+            (num1, num2) => is_numeric(num1.clone()) && is_numeric(num2.clone()) || num1 == num2,
         }
     }
 
@@ -2661,7 +4778,7 @@ impl Generator {
         }
     }
 
-    fn get_field_item(&mut self, layout: StructLayout, name: &str, span: Span) -> Function {
+    fn get_field_item(&mut self, layout: StructLayout, name: &str, span: Span) -> Option<Function> {
         let m = layout.methods.iter().find(|p| p.get_name() == name);
         if m.is_none() {
             self.consume(CompileError::new(
@@ -2670,31 +4787,205 @@ impl Generator {
                 span,
                 ErrLevel::ERROR,
             ));
-            self.flush();
         }
-        m.unwrap().clone()
+        m.cloned()
     }
 
     fn convert_vecnode_nodeprogram(&self, functions: Vec<Spanned<Node>>) -> Box<Spanned<Node>> {
+        let file = functions.first().map_or(FileId::default(), |f| f.span.file);
         return Box::new(Spanned {
             node: Node::Program(functions),
-            span: Span { start: 0, end: 0 },
+            span: Span {
+                start: 0,
+                end: 0,
+                file,
+            },
         });
     }
 }
 
+/// The trait of `ty` if it is a `dyn Trait`, qualified with the bundle it came from.
+/// Whether a value of type `ty` passed through `...` is widened to a `jaguar_int`: the
+/// integers other than `char`, and `bool`.
+fn is_widened(ty: &Type) -> bool {
+    match ty {
+        Type::MUT(inner) => is_widened(inner),
+        Type::CHAR => false,
+        Type::BOOL => true,
+        other => is_int(other.clone()),
+    }
+}
+fn dyn_trait(ty: &Type) -> Option<Type> {
+    match ty {
+        Type::MUT(inner) => dyn_trait(inner),
+        Type::Dyn(tr) => Some(*tr.clone()),
+        Type::BundledType { bundle, ty } => match ty.as_ref() {
+            Type::Dyn(tr) => Some(Type::BundledType {
+                bundle: bundle.clone(),
+                ty: tr.clone(),
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Name of the function `node` declares, if it is one.
+fn fn_name(node: &Spanned<Node>) -> Option<&String> {
+    match &node.node {
+        Node::FnStmt { name, .. } | Node::FnSig { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
 pub fn is_builtin(clone: Type) -> bool {
     match clone {
         Type::Custom(_) => false,
         Type::BundledType { bundle: _, ty: _ } => false,
+        Type::Generic { .. } => false,
         _ => true,
     }
 }
 
+/// Escapes one char for use inside a C literal. Anything outside printable
+/// ASCII is written as octal escapes of its UTF-8 bytes.
+fn c_escape(c: char, quote: char, out: &mut String) {
+    match c {
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        '\\' => out.push_str("\\\\"),
+        c if c == quote => {
+            out.push('\\');
+            out.push(c);
+        }
+        ' '..='~' => out.push(c),
+        /* always three digits, so a following digit is never read as part of the escape */
+        c => {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("\\{byte:03o}"));
+            }
+        }
+    }
+}
+
+/// A C string literal. Strings spanning several lines become one C literal
+/// per line, which the C compiler joins again.
+fn c_string_literal(value: &str) -> String {
+    let mut out = String::from("\"");
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        c_escape(c, '"', &mut out);
+        match (c, chars.peek()) {
+            ('\n', Some(_)) => out.push_str("\"\n\t\""),
+            /* keep '??' from starting a trigraph */
+            ('?', Some('?')) => out.push('\\'),
+            _ => {}
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn c_char_literal(value: char) -> String {
+    let mut out = String::from("'");
+    c_escape(value, '\'', &mut out);
+    out.push('\'');
+    out
+}
+
+/// A `break` in `node` that no loop inside it takes, which a `switch` around it would
+/// take for its own.
+fn breaks_out(node: &Node) -> bool {
+    match node {
+        Node::BREAK => true,
+        Node::Program(nodes) => nodes.iter().any(|n| breaks_out(&n.node)),
+        Node::IfStmt {
+            body,
+            elseifs,
+            elsestmt,
+            ..
+        } => {
+            breaks_out(&body.node)
+                || elseifs.iter().flatten().any(|n| breaks_out(&n.node))
+                || elsestmt.as_ref().is_some_and(|e| breaks_out(&e.node))
+        }
+        Node::MatchExpr { arms, .. } => arms.iter().any(|a| breaks_out(&a.body.node)),
+        _ => false,
+    }
+}
+
+/// The C statements of a match arm, from its block or its expression.
+fn arm_statements(body: ArmBody) -> String {
+    match body {
+        ArmBody::Block(statements) => statements,
+        ArmBody::Expr { preamble, value } => format!("{preamble}{value};\n"),
+    }
+}
+
+/// The type of a literal in a pattern, the one its suffix names for an integer.
+fn literal_type(lit: &Node) -> Type {
+    match lit {
+        Node::LiteralInt(_, suffix) => suffix.clone().unwrap_or(Type::INT),
+        Node::LiteralCh(_) => Type::CHAR,
+        Node::LiteralBool(_) => Type::BOOL,
+        _ => Type::STR,
+    }
+}
+
+/// An integer, char or bool of a pattern as a C constant of type `ty`.
+fn c_number(value: i128, ty: &Type) -> String {
+    if *ty == Type::U64 {
+        format!("{value}ULL")
+    } else if value > i32::MAX as i128 || value < i32::MIN as i128 {
+        format!("{value}LL")
+    } else {
+        value.to_string()
+    }
+}
+
 fn is_int(target_type: Type) -> bool {
     use crate::backend::ttype::Type::*;
     match target_type {
         INT | U8 | U64 | U32 | U16 | I8 | I16 | I32 | I64 | CHAR => true,
+        MUT(inner) => is_int(*inner),
         _ => false,
     }
 }
+
+fn is_float(target_type: Type) -> bool {
+    match target_type {
+        Type::F32 | Type::F64 => true,
+        Type::MUT(inner) => is_float(*inner),
+        _ => false,
+    }
+}
+
+fn is_numeric(target_type: Type) -> bool {
+    is_int(target_type.clone()) || is_float(target_type)
+}
+
+fn is_bool(target_type: Type) -> bool {
+    match target_type {
+        Type::BOOL => true,
+        Type::MUT(inner) => is_bool(*inner),
+        _ => false,
+    }
+}
+
+/// A doc comment as a C block comment on its own lines, so it ends up in the
+/// emitted headers. Empty when there is no doc comment.
+fn c_doc(doc: &Option<String>, indent: &str) -> String {
+    let Some(doc) = doc else {
+        return String::new();
+    };
+    let mut comment = format!("\n{indent}/**");
+    for line in doc.lines() {
+        /* the text must not be able to close the C comment early */
+        let line = format!("{indent} * {}", line.replace("*/", "* /"));
+        comment += &format!("\n{}", line.trim_end());
+    }
+    comment += &format!("\n{indent} */");
+    comment
+}
@@ -1,4 +1,18 @@
+use colored::Colorize;
+use serde::Serialize;
+
 use crate::lexer::Span;
+use crate::source_map::SourceMap;
+
+/// How diagnostics are printed, picked with `--error-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// Colored text with the offending source line.
+    #[default]
+    Human,
+    /// One JSON object per line, for editors and CI.
+    Json,
+}
 
 #[derive(Debug, Clone)]
 pub enum ErrLevel {
@@ -23,4 +37,110 @@ impl CompileError {
             level: lvl,
         }
     }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self.level, ErrLevel::ERROR)
+    }
+
+    fn level_name(&self) -> &'static str {
+        match self.level {
+            ErrLevel::ERROR => "error",
+            ErrLevel::WARNING => "warning",
+            ErrLevel::INFO => "info",
+        }
+    }
+
+    /// Prints the diagnostic as a single line of JSON.
+    pub fn render_json(&self, sources: &SourceMap) {
+        let file = sources.file(&self.span);
+        let (line, column) = file.line_column(self.span.start);
+        let diagnostic = JsonDiagnostic {
+            level: self.level_name(),
+            message: &self.errmsg,
+            help: self.help.as_deref(),
+            file: &file.name,
+            span: JsonSpan {
+                start: self.span.start,
+                end: self.span.end,
+            },
+            line,
+            column,
+        };
+        eprintln!("{}", serde_json::to_string(&diagnostic).unwrap());
+    }
+
+    /// Prints the diagnostic along with the offending line of the file it points into.
+    pub fn render(&self, sources: &SourceMap) {
+        let file = sources.file(&self.span);
+        let (source, filename) = (file.source.as_str(), file.name.as_str());
+        /* spans past the end (e.g. at EOF) are clamped onto the last line */
+        let start = self.span.start.min(source.len());
+        let end = self.span.end.clamp(start, source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map(|i| start + i)
+            .unwrap_or(source.len());
+        let line = &source[line_start..line_end];
+        let (line_number, column) = file.line_column(start);
+        let col_start = column - 1;
+        let col_end = col_start + source[start..end].chars().count().max(1);
+        match self.level {
+            ErrLevel::ERROR => eprintln!("{} {}", "[Tixie Error] :".red().bold(), self.errmsg),
+            ErrLevel::WARNING | ErrLevel::INFO => eprintln!(
+                "{}",
+                format!("[Tixie Warning]: {}", self.errmsg).yellow().bold()
+            ),
+        }
+        eprintln!(" ---> line : [{filename}:{line_number}:{column}]");
+        eprintln!("   |\n{: >2} | {}", line_number, line);
+        let mut caret_line = String::new();
+        for (i, c) in line.chars().enumerate() {
+            if i >= col_start && i < col_end {
+                caret_line.push('^');
+            } else if i < col_start {
+                caret_line.push(if c == '\t' { '\t' } else { ' ' });
+            }
+        }
+        if col_start >= line.chars().count() {
+            caret_line.push('^');
+        }
+        eprint!("   | {}", caret_line.yellow().bold());
+        if let Some(help) = &self.help {
+            match self.level {
+                ErrLevel::ERROR => eprint!(" help : {}", help.yellow().bold()),
+                _ => eprint!(" help : {}", help.blue()),
+            }
+        }
+        eprintln!();
+    }
+}
+
+#[derive(Serialize)]
+struct JsonSpan {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    level: &'static str,
+    message: &'a str,
+    help: Option<&'a str>,
+    file: &'a str,
+    span: JsonSpan,
+    line: usize,
+    column: usize,
+}
+
+/// Prints every diagnostic in source order. Returns true if any of them is an error.
+pub fn report(diagnostics: &mut [CompileError], sources: &SourceMap, format: ErrorFormat) -> bool {
+    diagnostics.sort_by_key(|d| (d.span.file.0, d.span.start, d.span.end));
+    for d in diagnostics.iter() {
+        match format {
+            ErrorFormat::Human => d.render(sources),
+            ErrorFormat::Json => d.render_json(sources),
+        }
+    }
+    diagnostics.iter().any(|d| d.is_error())
 }
@@ -0,0 +1,61 @@
+//! Enums: a tag telling which variant a value is, and the values that variant carries.
+//!
+//! `enum Shape { Circle(int), Rect(int, int), Empty }` is generated as
+//!
+//! ```c
+//! typedef struct Shape {
+//!     const jaguar_i64 tag;
+//!     union {
+//!         struct { const jaguar_i32 _0; } Circle;
+//!         struct { const jaguar_i32 _0; const jaguar_i32 _1; } Rect;
+//!     } as;
+//! } Shape;
+//! ```
+//!
+//! and `Shape::Rect(2, 3)` as the compound literal `((const Shape){.tag = 1, .as.Rect = {2, 3}})`.
+//! Variants without values have no member in the union, and an enum with none has no union.
+use super::generics;
+use super::parser::Variant;
+
+#[derive(Debug, Clone)]
+pub struct Enum {
+    pub name: String,
+    pub variants: Vec<Variant>,
+    /// The generic enum this is an instance of, as it is named here: `opt` or `alias::opt`.
+    pub template: Option<String>,
+}
+
+impl Enum {
+    pub fn variant(&self, name: &str) -> Option<&Variant> {
+        self.variants.iter().find(|v| v.name == name)
+    }
+    /// Discriminant of each variant, in order. One not written is one more than the
+    /// previous, the first is 0, as in C.
+    pub fn tags(&self) -> Vec<i64> {
+        let mut next = 0i64;
+        self.variants
+            .iter()
+            .map(|v| {
+                let tag = v.value.unwrap_or(next);
+                next = tag.wrapping_add(1);
+                tag
+            })
+            .collect()
+    }
+    pub fn tag(&self, name: &str) -> Option<i64> {
+        let i = self.variants.iter().position(|v| v.name == name)?;
+        Some(self.tags()[i])
+    }
+    /// No variant carries a value, the enum is a plain set of named integers.
+    pub fn is_plain(&self) -> bool {
+        self.variants.iter().all(|v| v.payload.is_empty())
+    }
+    /// The enum as seen through the bundle `alias`: its payload types are qualified.
+    pub fn qualified(&self, alias: &str) -> Enum {
+        let mut en = self.clone();
+        for ty in en.variants.iter_mut().flat_map(|v| v.payload.iter_mut()) {
+            generics::qualify(ty, alias);
+        }
+        en
+    }
+}
@@ -1 +1,280 @@
+//! Monomorphization helpers.
+//!
+//! A generic struct or function is kept as a template. Every list of type arguments it is
+//! used with gets its own copy, with the type parameters substituted, named by
+//! [`file_instance_name`] and generated like any other struct or function.
+use super::parser::{Node, Spanned};
+use super::ttype::Type;
 
+/// C name of the instance of `name` for `args`, e.g. `vec__int` for `vec<int>` and
+/// `result__ptr_char__str` for `result<ptr<char>, str>`.
+pub(crate) fn instance_name(name: &str, args: &[Type]) -> String {
+    let mut out = name.to_string();
+    for arg in args {
+        out.push_str("__");
+        out.push_str(&type_tag(arg));
+    }
+    out
+}
+
+/// C name of the instance of `name`, declared in the file at `path`, for `args`, e.g.
+/// `vec_vec__int` for `vec<int>` from `lib/vec.jr`. Templates of the same name from two
+/// files never share an instance, and every file that uses one names it the same.
+pub(crate) fn file_instance_name(path: &str, name: &str, args: &[Type]) -> String {
+    let file = std::path::Path::new(path)
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    /* a bundle is known by its header, `vec.jr.h`, the file itself by `vec.jr` */
+    let stem: String = file
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{stem}_{}", instance_name(name, args))
+}
+
+/// Identifier-safe spelling of `ty` for [`instance_name`].
+fn type_tag(ty: &Type) -> String {
+    match ty {
+        Type::PTR(inner) => format!("ptr_{}", type_tag(inner)),
+        Type::Dyn(tr) => format!("dyn_{}", type_tag(tr)),
+        Type::MUT(inner) => type_tag(inner),
+        Type::List(inner, size) => format!("list_{}_{size}", type_tag(inner)),
+        Type::BundledType { ty, .. } => type_tag(ty),
+        Type::Generic { name, args } => instance_name(name, args),
+        Type::Custom(name) => name.clone(),
+        other => other.debug().to_lowercase(),
+    }
+}
+
+/// Calls `f` on `node` and every node below it, children first. Templates are not
+/// entered, their types only mean something once instantiated.
+pub(crate) fn walk(node: &mut Spanned<Node>, f: &mut dyn FnMut(&mut Spanned<Node>)) {
+    match &mut node.node {
+        Node::BinaryExpr { lhs, rhs, .. } => {
+            walk(lhs, f);
+            walk(rhs, f);
+        }
+        Node::UnaryExpr { expr, .. }
+        | Node::Cast { expr, .. }
+        | Node::DeRefExpr { expr }
+        | Node::RefExpr { expr }
+        | Node::Ret(expr)
+        | Node::Pair { value: expr, .. }
+        | Node::LetStmt { value: expr, .. }
+        | Node::NameSpace { body: expr, .. } => walk(expr, f),
+        Node::StructInit { fields: nodes } | Node::Program(nodes) => {
+            nodes.iter_mut().for_each(|n| walk(n, f));
+        }
+        Node::ListInit { content } => content.iter_mut().for_each(|n| walk(n, f)),
+        Node::EnumStmt { meths, .. } => meths.iter_mut().for_each(|n| walk(n, f)),
+        Node::StructStmt {
+            fields,
+            meths,
+            statics,
+            ..
+        } => {
+            fields.iter_mut().for_each(|n| walk(n, f));
+            meths.iter_mut().flatten().for_each(|n| walk(n, f));
+            statics.iter_mut().flatten().for_each(|n| walk(n, f));
+        }
+        Node::BundleAccess { base, field }
+        | Node::ReVal {
+            name: base,
+            value: field,
+        }
+        | Node::ListAccess {
+            name: base,
+            index: field,
+        }
+        | Node::WhileStmt {
+            cond: base,
+            body: field,
+        } => {
+            walk(base, f);
+            walk(field, f);
+        }
+        Node::MemberAccess { base, .. } => walk(base, f),
+        Node::FcCall { params, callee }
+        | Node::GenericFnCall {
+            callee,
+            args: params,
+            ..
+        } => {
+            walk(callee, f);
+            params.iter_mut().for_each(|n| walk(n, f));
+        }
+        Node::FnStmt {
+            body, return_val, ..
+        } => {
+            walk(body, f);
+            if let Some(value) = return_val.as_mut() {
+                walk(value, f);
+            }
+        }
+        Node::PluginStatement { body, ret_val, .. } => {
+            walk(body, f);
+            if let Some(value) = ret_val.as_mut() {
+                walk(value, f);
+            }
+        }
+        Node::ForStmt {
+            init,
+            cond,
+            inc,
+            body,
+        } => {
+            walk(init, f);
+            walk(cond, f);
+            walk(inc, f);
+            walk(body, f);
+        }
+        Node::IfStmt {
+            cond,
+            body,
+            elseifs,
+            elsestmt,
+        } => {
+            walk(cond, f);
+            walk(body, f);
+            elseifs.iter_mut().flatten().for_each(|n| walk(n, f));
+            if let Some(e) = elsestmt {
+                walk(e, f);
+            }
+        }
+        Node::MatchExpr { scrutinee, arms } => {
+            walk(scrutinee, f);
+            for arm in arms {
+                if let Some(guard) = arm.guard.as_mut() {
+                    walk(guard, f);
+                }
+                walk(&mut arm.body, f);
+            }
+        }
+        /* the methods may name `Self`, they are walked once it is replaced */
+        Node::ImplStmt { .. } => {}
+        _ => {}
+    }
+    f(node);
+}
+
+/// The types written in `node` itself, not in the nodes below it.
+pub(crate) fn types_mut(node: &mut Node) -> Vec<&mut Type> {
+    match node {
+        Node::Cast { ty, .. } | Node::SizeOf(ty) => vec![ty],
+        Node::Feilds { type_hint, .. } | Node::LetStmt { type_hint, .. } => vec![type_hint],
+        Node::ExTernStmt {
+            args, return_type, ..
+        } => {
+            let mut types: Vec<&mut Type> = args.iter_mut().map(|a| &mut a.type_hint).collect();
+            types.push(return_type);
+            types
+        }
+        Node::FnStmt { args, ret_type, .. } | Node::FnSig { args, ret_type, .. } => {
+            let mut types: Vec<&mut Type> = args.iter_mut().map(|a| &mut a.type_hint).collect();
+            types.push(ret_type);
+            types
+        }
+        Node::PluginStatement {
+            args,
+            ret_type,
+            targ_type,
+            ..
+        } => {
+            let mut types: Vec<&mut Type> = args.iter_mut().map(|a| &mut a.type_hint).collect();
+            types.push(ret_type);
+            types.push(targ_type);
+            types
+        }
+        Node::GenericFnCall { generics, .. } => generics.iter_mut().collect(),
+        Node::ImplStmt { target, .. } => vec![target],
+        Node::EnumStmt { variants, .. } => variants
+            .iter_mut()
+            .flat_map(|v| v.payload.iter_mut())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Replaces the type parameters `params` with `args` everywhere in `node`.
+///
+/// A template that came from a bundle names the bundle's types and generic functions
+/// unqualified, so every other custom or generic type and every generic call is qualified
+/// with `origin`, the alias the bundle is known by here.
+pub(crate) fn substitute(
+    node: &mut Spanned<Node>,
+    params: &[String],
+    args: &[Type],
+    origin: Option<&str>,
+) {
+    walk(node, &mut |n| {
+        for ty in types_mut(&mut n.node) {
+            substitute_type(ty, params, args, origin);
+        }
+        let Some(bundle) = origin else {
+            return;
+        };
+        let Node::GenericFnCall { callee, .. } = &n.node else {
+            return;
+        };
+        if matches!(callee.node, Node::Token(..)) {
+            let base = Spanned {
+                node: Node::Token(bundle.to_string(), false),
+                span: callee.span.clone(),
+            };
+            *n = Spanned {
+                span: n.span.clone(),
+                node: Node::BundleAccess {
+                    base: Box::new(base),
+                    field: Box::new(n.clone()),
+                },
+            };
+        }
+    });
+}
+
+/// Qualifies the types `ty` names with `bundle`, for a type read out of the bundle.
+pub(crate) fn qualify(ty: &mut Type, bundle: &str) {
+    substitute_type(ty, &[], &[], Some(bundle));
+}
+
+fn substitute_type(ty: &mut Type, params: &[String], args: &[Type], origin: Option<&str>) {
+    match ty {
+        Type::Custom(name) => {
+            if let Some(i) = params.iter().position(|p| p == name) {
+                *ty = args[i].clone();
+            } else if let Some(bundle) = origin {
+                *ty = Type::BundledType {
+                    bundle: bundle.to_string(),
+                    ty: Box::new(ty.clone()),
+                };
+            }
+        }
+        Type::Generic { args: inner, .. } => {
+            for arg in inner.iter_mut() {
+                substitute_type(arg, params, args, origin);
+            }
+            if let Some(bundle) = origin {
+                *ty = Type::BundledType {
+                    bundle: bundle.to_string(),
+                    ty: Box::new(ty.clone()),
+                };
+            }
+        }
+        Type::PTR(inner) | Type::MUT(inner) | Type::List(inner, _) | Type::Dyn(inner) => {
+            substitute_type(inner, params, args, origin);
+        }
+        /* qualified already, only its type arguments can name a parameter */
+        Type::BundledType { ty: inner, .. } => {
+            if let Type::Generic { args: inner, .. } = inner.as_mut() {
+                for arg in inner.iter_mut() {
+                    substitute_type(arg, params, args, origin);
+                }
+            }
+        }
+        _ => {}
+    }
+}
@@ -1,11 +1,15 @@
+pub mod ast_dump;
 pub(crate) mod bundle;
-pub(crate) mod codegen;
-pub(crate) mod compile_error;
+pub mod codegen;
+pub mod compile_error;
 pub(crate) mod context;
+pub(crate) mod enums;
 pub(crate) mod function;
 pub(crate) mod generics;
-pub(crate) mod parser;
-pub(crate) mod ttype;
+pub mod parser;
+pub(crate) mod patterns;
+pub(crate) mod traits;
+pub mod ttype;
 pub(crate) mod type_table;
 pub(crate) mod var;
 pub(crate) mod var_table;
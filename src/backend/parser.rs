@@ -1,24 +1,71 @@
+use crate::backend::compile_error::{CompileError, ErrLevel};
 use crate::backend::ttype::Type;
-pub(crate) use colored::Colorize;
-use core::panic;
-use std::{fmt::Debug, process::exit};
+use std::collections::HashMap;
+use std::fmt::Debug;
 
 use crate::lexer::{Span, Token, TokenType};
+use crate::source_map::FileId;
 #[derive(Debug, Clone)]
 pub struct FunctionArg {
     pub name: String,
     pub type_hint: Type,
     pub is_ref: bool,
 }
+/// A variant of an `enum`, `Name` or `Name(T, U)`, with its discriminant if one is written.
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub name: String,
+    /// Types of the values the variant carries, empty for a plain one.
+    pub payload: Vec<Type>,
+    pub value: Option<i64>,
+    pub doc: Option<String>,
+    pub span: Span,
+}
+/// What a `match` arm compares the value with.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// `_`, anything.
+    Wildcard,
+    /// A name, anything, which is then bound to it.
+    Binding(String),
+    /// An integer, char, string or bool literal.
+    Literal(Box<Node>),
+    /// `lo..hi`, or `lo..=hi` when `inclusive`.
+    Range {
+        lo: Box<Node>,
+        hi: Box<Node>,
+        inclusive: bool,
+    },
+    /// `Enum::Name` or `Enum::Name(a, _)`, the path as written and the names its values
+    /// are bound to, `_` for one that is not.
+    Variant {
+        path: Vec<String>,
+        bindings: Vec<String>,
+    },
+}
+/// `pattern if guard => body`. The body is a `Program` when it is a block.
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Spanned<Pattern>,
+    pub guard: Option<Spanned<Node>>,
+    pub body: Spanned<Node>,
+}
 #[derive(Debug, Clone)]
 pub enum Node {
     NULLPTR,
+    /// An expression made of a character the lexer did not know, which it reported already.
+    Poison,
     BREAK,
     BinaryExpr {
         lhs: Box<Spanned<Node>>,
         opr: TokenType,
         rhs: Box<Spanned<Node>>,
     },
+    /// `-x`, `!x` or `~x`. Dereference and address-of have their own nodes.
+    UnaryExpr {
+        opr: TokenType,
+        expr: Box<Spanned<Node>>,
+    },
     StructInit {
         fields: Vec<Spanned<Node>>,
     },
@@ -27,6 +74,7 @@ pub enum Node {
         fields: Vec<Spanned<Node>>,
         meths: Vec<Result<Spanned<Node>, String>>,
         statics: Option<Vec<Spanned<Node>>>,
+        doc: Option<String>,
     },
     BundleAccess {
         base: Box<Spanned<Node>>,
@@ -49,6 +97,7 @@ pub enum Node {
         args: Vec<FunctionArg>,
         return_type: Type,
         vardaic: bool,
+        doc: Option<String>,
     },
     FcCall {
         params: Vec<Spanned<Node>>,
@@ -57,6 +106,7 @@ pub enum Node {
     Feilds {
         name: String,
         type_hint: Type,
+        doc: Option<String>,
     },
     FnStmt {
         body: Box<Spanned<Node>>,
@@ -67,6 +117,7 @@ pub enum Node {
         return_val: Box<Option<Spanned<Node>>>,
         vardaic: bool,
         mangled_name: String,
+        doc: Option<String>,
     },
     ForStmt {
         init: Box<Spanned<Node>>,
@@ -95,6 +146,8 @@ pub enum Node {
     },
     GenericFnStmt {
         generics: Vec<String>,
+        /// `T: Trait` bounds on the type parameters, one entry per trait.
+        bounds: Vec<(String, Type)>,
         body: Box<Spanned<Node>>,
         args: Vec<FunctionArg>,
         name: String,
@@ -103,10 +156,22 @@ pub enum Node {
         return_val: Box<Option<Spanned<Node>>>,
         vardaic: bool,
         mangled_name: String,
+        doc: Option<String>,
     },
+    /// `JLINE`, `JCOLUMN`, `JFILE`, `JFUNC` or `JBUNDLE`, replaced by a literal during codegen.
+    Intrinsic(String),
+    LiteralBool(bool),
     LiteralCh(char),
-    LiteralInt(String),
+    /// Decimal value and the type named by its suffix, if it has one.
+    LiteralInt(String, Option<Type>),
+    /// Value as a C constant and the type named by its suffix, if it has one.
+    LiteralFloat(String, Option<Type>),
     LiteralStr(String),
+    /// `match scrutinee { arms }`.
+    MatchExpr {
+        scrutinee: Box<Spanned<Node>>,
+        arms: Vec<MatchArm>,
+    },
     MemberAccess {
         base: Box<Spanned<Node>>,
         field: String,
@@ -145,6 +210,8 @@ pub enum Node {
         expr: Box<Spanned<Node>>,
     },
     Ret(Box<Spanned<Node>>),
+    /// `sizeof(T)`, the size of a value of type `T` in bytes, as a `u64`.
+    SizeOf(Type),
     Token(String, bool),
     UnpackStmt {
         alias: String,
@@ -153,14 +220,51 @@ pub enum Node {
     GenericStructStmt {
         name: String,
         generics: Vec<String>,
+        bounds: Vec<(String, Type)>,
         fields: Vec<Spanned<Node>>,
         meths: Vec<Result<Spanned<Node>, String>>,
+        statics: Option<Vec<Spanned<Node>>>,
+        doc: Option<String>,
     },
     GenericFnCall {
         callee: Box<Spanned<Node>>,
         generics: Vec<Type>,
         args: Vec<Spanned<Node>>,
     },
+    /// `fn name(args): ret;`, a trait method without a default body.
+    FnSig {
+        name: String,
+        args: Vec<FunctionArg>,
+        ret_type: Type,
+        doc: Option<String>,
+    },
+    /// `trait Name { ... }`. Its methods are `FnSig`s, or `FnStmt`s with a default body.
+    TraitStmt {
+        name: String,
+        meths: Vec<Spanned<Node>>,
+        doc: Option<String>,
+    },
+    /// `enum Name { A, B(int) = 4, ... }`, and the methods written in it.
+    EnumStmt {
+        name: String,
+        variants: Vec<Variant>,
+        meths: Vec<Spanned<Node>>,
+        doc: Option<String>,
+    },
+    GenericEnumStmt {
+        name: String,
+        generics: Vec<String>,
+        bounds: Vec<(String, Type)>,
+        variants: Vec<Variant>,
+        meths: Vec<Spanned<Node>>,
+        doc: Option<String>,
+    },
+    /// `impl Trait for Type { ... }`.
+    ImplStmt {
+        trait_name: Type,
+        target: Type,
+        meths: Vec<Spanned<Node>>,
+    },
     WhileStmt {
         cond: Box<Spanned<Node>>,
         body: Box<Spanned<Node>>,
@@ -172,6 +276,57 @@ pub struct Spanned<T> {
     pub span: Span,
 }
 
+type PResult<T> = Result<T, CompileError>;
+
+/// Type parameters of a generic item, and their `T: Trait` bounds.
+type TypeParams = (Vec<String>, Vec<(String, Type)>);
+
+/// `=` and the compound assignments.
+const ASSIGNMENTS: [&str; 11] = [
+    "=", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<=", ">>=",
+];
+
+/// Binding power of the operand of a prefix operator.
+const PREFIX_BP: u8 = 25;
+/// Source location intrinsics, resolved at compile time.
+const INTRINSICS: [&str; 5] = ["JLINE", "JCOLUMN", "JFILE", "JFUNC", "JBUNDLE"];
+
+/// Left binding power of `as`: tighter than `*`, looser than prefix operators.
+const CAST_BP: u8 = 23;
+
+/// Left and right binding power of a binary operator. A right power above the
+/// left one makes the operator left associative.
+fn infix_binding_power(op: &str) -> Option<(u8, u8)> {
+    let bp = match op {
+        "||" => (3, 4),
+        "&&" => (5, 6),
+        "|" => (7, 8),
+        "^" => (9, 10),
+        "&" => (11, 12),
+        "==" | "!=" => (13, 14),
+        "<" | ">" | "<=" | ">=" => (15, 16),
+        "<<" | ">>" => (17, 18),
+        "+" | "-" => (19, 20),
+        "*" | "/" | "%" => (21, 22),
+        _ => return None,
+    };
+    Some(bp)
+}
+
+/// The type an integer literal suffix such as `u8` names.
+fn int_suffix(suffix: &str) -> Type {
+    match suffix {
+        "u8" => Type::U8,
+        "u16" => Type::U16,
+        "u32" => Type::U32,
+        "u64" => Type::U64,
+        "i8" => Type::I8,
+        "i16" => Type::I16,
+        "i32" => Type::I32,
+        _ => Type::I64,
+    }
+}
+
 #[derive(Clone)]
 pub struct Parser {
     tokens: Vec<Token>,
@@ -179,169 +334,294 @@ pub struct Parser {
     source_code: String,
     has_error: bool,
     is_inloop: bool,
+    diagnostics: Vec<CompileError>,
+    file: FileId,
+    /* `///` lines, joined, by the index of the token they precede */
+    docs: HashMap<usize, String>,
+    /* spans of dropped error tokens, by the index of the token they precede */
+    after_error: HashMap<usize, Span>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>, input: String) -> Self {
+        /* the lexer has reported error tokens already, the grammar never expects one, but an
+        expression missing for one is poisoned. doc comments are set aside for the item that
+        follows them */
+        let mut kept: Vec<Token> = Vec::new();
+        let mut docs = HashMap::new();
+        let mut after_error = HashMap::new();
+        let mut doc_lines: Vec<String> = Vec::new();
+        for token in tokens {
+            match token.kind {
+                TokenType::Error => {
+                    after_error.entry(kept.len()).or_insert(token.span);
+                }
+                TokenType::DocComment(line) => doc_lines.push(line),
+                _ => {
+                    if !doc_lines.is_empty() {
+                        docs.insert(kept.len(), doc_lines.join("\n"));
+                        doc_lines.clear();
+                    }
+                    kept.push(token);
+                }
+            }
+        }
+        let tokens = kept;
+        /* every token comes from the same file, the EOF token at least is always there */
+        let file = tokens.first().map(|t| t.span.file).unwrap_or_default();
         Self {
+            file,
             tokens,
             current: 0,
             source_code: input,
             has_error: false,
             is_inloop: false,
+            diagnostics: Vec::new(),
+            docs,
+            after_error,
         }
     }
+    /// The doc comment written right before the current token, if any.
+    fn take_doc(&mut self) -> Option<String> {
+        self.docs.remove(&self.current)
+    }
+    pub fn has_errors(&self) -> bool {
+        self.has_error
+    }
+    /// Every error and warning collected while parsing.
+    pub fn diagnostics(&self) -> &[CompileError] {
+        &self.diagnostics
+    }
+    pub fn source(&self) -> &str {
+        &self.source_code
+    }
     fn peek(&self) -> Option<&Token> {
         self.tokens.get(self.current)
     }
     fn advance(&mut self) -> Option<&Token> {
-        self.current += 1;
+        /* never step past the trailing EOF token */
+        if self.current + 1 < self.tokens.len() {
+            self.current += 1;
+        }
         self.peek()
     }
-    fn expect_keyword(&mut self, expected: &str) -> Result<(), String> {
-        match self.peek().unwrap().kind.clone() {
+    fn expect_keyword(&mut self, expected: &str) -> PResult<()> {
+        match self.next().kind {
             TokenType::Keyword(k) if k == expected => {
                 self.advance();
                 Ok(())
             }
-            TokenType::EOF => Err(format!("Expected {:?}, found EOF", self.peek().unwrap())),
-            tok => Err(format!("Expected Keyword '{}', got {:?}", expected, tok)),
+            TokenType::EOF => Err(self.fail(
+                format!("Expected Keyword '{expected}', found EOF"),
+                &self.next().span,
+            )),
+            _ => Err(self.fail(
+                format!("Expected Keyword '{}', got {}", expected, self.next()),
+                &self.next().span,
+            )),
         }
     }
     pub fn parse_program(&mut self) -> Vec<Spanned<Node>> {
         let mut program = Vec::new();
-        while let Some(token) = self.peek() {
-            match token.kind.clone() {
-                TokenType::Comment(_comment) => {
-                    self.advance();
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "for" => {
-                    let forstmt = self.parse_forstmt();
-                    program.push(forstmt);
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "fn" => {
-                    let func = self.parse_func().unwrap();
-                    program.push(func);
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "let" => {
-                    let var_stmt = self.parse_let_stmt();
-                    program.push(var_stmt);
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "if" => {
-                    let ifstmt = self.parse_ifstmt();
-                    program.push(ifstmt);
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "extern" => {
-                    let extrn_stmt = self.parse_extern();
-                    program.push(extrn_stmt);
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "struct" => {
-                    let struct_stmt = self.parse_struct();
-                    program.push(struct_stmt);
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "unpack" => {
-                    let unpack_stmt = self.parse_unpack();
-                    program.push(unpack_stmt);
-                    continue;
-                }
-                TokenType::Ident(_val)
-                    if self.tokens.get(self.current + 1).unwrap().clone().kind
-                        == TokenType::Separator("(".into()) =>
-                {
-                    let func_call = self.parse_func_call(true);
-                    program.push(func_call);
-                    continue;
+        while self.next().kind != TokenType::EOF {
+            let before = self.current;
+            match self.parse_toplevel() {
+                Ok(Some(node)) => program.push(node),
+                Ok(None) => {}
+                Err(e) => self.recover(e, before, false),
+            }
+        }
+        program
+    }
+    fn parse_toplevel(&mut self) -> PResult<Option<Spanned<Node>>> {
+        let token = self.next();
+        let node = match token.kind.clone() {
+            TokenType::Comment(_comment) => {
+                self.advance();
+                return Ok(None);
+            }
+            TokenType::Keyword(k) if k == "for" => self.parse_forstmt()?,
+            TokenType::Keyword(k) if k == "fn" => self.parse_func()?,
+            TokenType::Keyword(k) if k == "let" => self.parse_let_stmt()?,
+            TokenType::Keyword(k) if k == "if" => self.parse_ifstmt()?,
+            TokenType::Keyword(k) if k == "match" => self.parse_match_stmt()?,
+            TokenType::Keyword(k) if k == "extern" => self.parse_extern()?,
+            TokenType::Keyword(k) if k == "struct" => self.parse_struct()?,
+            TokenType::Keyword(k) if k == "trait" => self.parse_trait()?,
+            TokenType::Keyword(k) if k == "enum" => self.parse_enum()?,
+            TokenType::Keyword(k) if k == "impl" => self.parse_impl()?,
+            TokenType::Keyword(k) if k == "unpack" => self.parse_unpack()?,
+            TokenType::Ident(_val)
+                if self.get(1).map(|t| t.kind) == Some(TokenType::Separator("(".into())) =>
+            {
+                self.parse_func_call(true)?
+            }
+            TokenType::Ident(_val)
+                if self.get(1).map(|t| t.kind) == Some(TokenType::Operator("=".to_owned())) =>
+            {
+                self.parse_reval()?
+            }
+            TokenType::Keyword(k) if k == "bundle" => self.parse_bundle()?,
+            kind => {
+                if !self.is_expr_start(kind) {
+                    return Err(
+                        self.fail(format!("Expected a statement, got {token}"), &token.span)
+                    );
                 }
-                TokenType::Ident(_val)
-                    if self.tokens.get(self.current + 1).unwrap().clone().kind
-                        == TokenType::Operator("=".to_owned()) =>
-                {
-                    let reval_stmt = self.parse_reval();
-                    program.push(reval_stmt);
-                    continue;
+                let expr = self.parse_expr()?;
+                self.advance();
+                expr
+            }
+        };
+        Ok(Some(node))
+    }
+    /// Records `err` and skips ahead to the next statement boundary so parsing
+    /// can resume. Always makes progress past the token that started the statement.
+    fn recover(&mut self, err: CompileError, before: usize, in_struct: bool) {
+        self.report(err);
+        self.synchronize(in_struct);
+        if self.current == before {
+            self.advance();
+        }
+    }
+    /// Skips tokens until just past a ';' (or ',' between struct members), past a
+    /// balanced '{ ... }' block, or up to the '}' that closes the enclosing block.
+    fn synchronize(&mut self, in_struct: bool) {
+        let mut depth = 0usize;
+        loop {
+            match self.next().kind {
+                TokenType::EOF => return,
+                TokenType::Separator(s) if s == ";" && depth == 0 => {
+                    self.advance();
+                    return;
                 }
-                TokenType::Keyword(k) if k == "bundle" => {
-                    let bundle_stmt = self.parse_bundle();
-                    program.push(bundle_stmt);
-                    continue;
+                TokenType::Separator(s) if s == "," && depth == 0 && in_struct => {
+                    self.advance();
+                    return;
                 }
-                _ => {
-                    if !self
-                        .is_expr_start(self.peek().unwrap().clone().kind)
-                        .clone()
-                    {
-                        break;
+                TokenType::Separator(s) if s == "{" => depth += 1,
+                TokenType::Separator(s) if s == "}" => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                    if depth == 0 {
+                        self.advance();
+                        return;
                     }
-                    let expr = self.parse_logic_or();
-                    program.push(expr);
                 }
+                _ => {}
             }
             self.advance();
         }
-        program
     }
-    fn parse_let_stmt(&mut self) -> Spanned<Node> {
+    fn parse_let_stmt(&mut self) -> PResult<Spanned<Node>> {
         // let mut x : int = y;
-        let start = self.peek().unwrap().clone().span.start;
+        let start = self.next().span.start;
         let mut is_const = true;
         self.advance(); // skip mk keyword
         if self.next().kind == TokenType::Keyword("mut".to_owned()) {
             self.advance();
             is_const = false;
         }
-        let vname = {
-            let name = self.expect_identifier();
-            name.unwrap().clone()
-        };
-        self.expect_separator(":");
+        let vname = self.expect_identifier()?;
+        self.expect_separator(":")?;
         let mut vtype = Type::Any;
         if self.next().kind != TokenType::Operator("=".to_owned()) {
-            vtype = self.parse_type().unwrap();
+            vtype = self.parse_type()?;
             if !is_const {
-                if let Type::MUT(t) = vtype.clone() {
+                if let Type::MUT(_) = vtype.clone() {
                 } else {
                     vtype = Type::MUT(Box::new(vtype.clone()));
                 }
             }
             self.advance();
         }
-        self.expect_operator("=");
-        let value = { self.parse_logic_or() };
-        self.expect_separator(";");
-        let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
+        self.expect_operator("=")?;
+        let value = self.parse_expr()?;
+        self.expect_separator(";")?;
+        let end = self.before().span.end;
         let ast_node = Node::LetStmt {
             name: vname,
             type_hint: vtype,
             value: Box::new(value),
             is_mut: !is_const,
         };
-        Spanned {
+        Ok(Spanned {
             node: ast_node,
-            span: Span { start, end },
-        }
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
     }
-    fn parse_func(&mut self) -> Result<Spanned<Node>, String> {
-        let start = self.peek().unwrap().span.start;
+    fn parse_func(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        let doc = self.take_doc();
         self.advance(); // skip fn keyword
-        let fname = {
-            let name = self.expect_identifier();
-            name.unwrap().clone()
-        };
+        let fname = self.expect_identifier()?;
+        let (generics, bounds) = self.parse_type_params()?;
+        let (args, vardaic, ret_type) = self.parse_signature()?;
+        self.expect_separator("{")?;
 
+        let body = self.parse_body(false, false)?;
+        let mut has_ret = false;
+        let mut ret_val = None;
+        if self.next().kind == TokenType::Keyword("ret".to_string()) {
+            self.expect_keyword("ret")?;
+            has_ret = true;
+            ret_val = Some(self.parse_expr()?);
+            self.expect_separator(";")?;
+        }
+        self.expect_separator("}")?;
+        let end = self.before().span.end;
+        let node = if generics.is_empty() {
+            Node::FnStmt {
+                name: fname.clone(),
+                returns: has_ret,
+                ret_type,
+                body: Box::new(body),
+                return_val: Box::new(ret_val),
+                args,
+                vardaic,
+                mangled_name: fname.clone(),
+                doc,
+            }
+        } else {
+            Node::GenericFnStmt {
+                generics,
+                bounds,
+                name: fname.clone(),
+                returns: has_ret,
+                ret_type,
+                body: Box::new(body),
+                return_val: Box::new(ret_val),
+                args,
+                vardaic,
+                mangled_name: fname.clone(),
+                doc,
+            }
+        };
+        Ok(Spanned {
+            node,
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
+    }
+    /// Parses the `(args): ret` of a function, both optional. Returns the arguments,
+    /// whether they end in `...`, and the return type.
+    fn parse_signature(&mut self) -> PResult<(Vec<FunctionArg>, bool, Type)> {
         let mut args = Vec::new();
         let mut vardaic: bool = false;
         if self.next().kind == TokenType::Separator("(".to_owned()) {
-            self.expect_separator("(");
-            while self.peek().unwrap().clone().kind != TokenType::Separator(')'.to_string()) {
+            self.expect_separator("(")?;
+            while self.next().kind != TokenType::Separator(')'.to_string()) {
                 let mut type_hint = Type::NoType;
-                let arg_name: String = self.expect_identifier().unwrap();
+                let arg_name: String = self.expect_identifier()?;
                 if arg_name == "self" {
                     if self.next().kind == TokenType::Separator(",".to_owned()) {
                         args.push(FunctionArg {
@@ -352,14 +632,22 @@ impl Parser {
                         self.advance();
                         continue;
                     }
+                    if self.next().kind == TokenType::Separator(")".to_owned()) {
+                        args.push(FunctionArg {
+                            name: arg_name.clone(),
+                            type_hint,
+                            is_ref: true,
+                        });
+                        continue;
+                    }
                 }
-                self.expect_separator(":");
+                self.expect_separator(":")?;
                 let mut is_ref = false;
-                if self.peek().unwrap().clone().kind == TokenType::Operator('%'.to_string()) {
+                if self.next().kind == TokenType::Operator('%'.to_string()) {
                     is_ref = true;
                     self.advance();
                 }
-                type_hint = self.parse_type().unwrap();
+                type_hint = self.parse_type()?;
                 self.advance();
                 let arg = FunctionArg {
                     name: arg_name,
@@ -367,324 +655,357 @@ impl Parser {
                     is_ref,
                 };
                 args.push(arg);
-                if self.peek().unwrap().clone().kind == TokenType::Separator(",".to_string()) {
-                    self.expect_separator(",");
-                    if self.peek().unwrap().clone().kind == TokenType::Vardaic {
+                if self.next().kind == TokenType::Separator(",".to_string()) {
+                    self.expect_separator(",")?;
+                    if self.next().kind == TokenType::Vardaic {
                         vardaic = true;
                         self.advance();
                         break;
                     }
+                    if self.next().kind == TokenType::DOTDOT {
+                        return Err(self.variadic_typo());
+                    }
+                } else if self.next().kind != TokenType::Separator(")".to_string()) {
+                    return Err(self.fail(
+                        format!("Expected , or ) after argument, got {}", self.next()),
+                        &self.next().span,
+                    ));
                 }
             }
-            self.expect_separator(")");
+            self.expect_separator(")")?;
         }
 
         let mut ret_type = Type::NoType;
-        if self.peek().unwrap().clone().kind == TokenType::Separator(":".to_owned()) {
-            self.expect_separator(":");
-            ret_type = self.parse_type().unwrap();
+        if self.next().kind == TokenType::Separator(":".to_owned()) {
+            self.expect_separator(":")?;
+            ret_type = self.parse_type()?;
             self.advance();
         }
-        self.expect_separator("{");
-
-        let body = self.parse_body(false, false);
-        let mut has_ret = false;
-        let mut ret_val = None;
-        if self.peek().unwrap().clone().kind == TokenType::Keyword("ret".to_string()) {
-            self.expect_keyword(&"ret")?;
-            has_ret = true;
-            ret_val = Some(self.parse_logic_or());
-            self.expect_separator(";");
+        Ok((args, vardaic, ret_type))
+    }
+    /// Parses the `[T, U: Trait + Other]` type parameters after the name of a struct or
+    /// function, if any. Returns the parameters and their trait bounds.
+    fn parse_type_params(&mut self) -> PResult<TypeParams> {
+        let mut params = Vec::new();
+        let mut bounds = Vec::new();
+        if self.next().kind != TokenType::Separator("[".to_owned()) {
+            return Ok((params, bounds));
         }
-        self.expect_separator("}");
-        let end = self.tokens.get(self.current - 1).cloned().unwrap().span.end;
-        return Ok(Spanned {
-            node: Node::FnStmt {
-                name: fname.clone(),
-                returns: has_ret,
-                ret_type,
-                body: Box::new(body),
-                return_val: Box::new(ret_val),
-                args,
-                vardaic,
-                mangled_name: fname.clone(),
-            },
-            span: Span { start, end },
-        });
+        self.advance();
+        loop {
+            let span = self.next().span;
+            let param = self.expect_identifier()?;
+            if params.contains(&param) {
+                self.error(format!("Type parameter '{param}' is declared twice"), &span);
+            }
+            if self.next().kind == TokenType::Separator(":".to_owned()) {
+                loop {
+                    self.advance();
+                    bounds.push((param.clone(), self.parse_type()?));
+                    self.advance();
+                    if self.next().kind != TokenType::Operator("+".to_owned()) {
+                        break;
+                    }
+                }
+            }
+            params.push(param);
+            if self.next().kind == TokenType::Separator(",".to_owned()) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.expect_separator("]")?;
+        Ok((params, bounds))
     }
-    fn expect_identifier(&mut self) -> Option<String> {
-        match self.peek().unwrap().clone().kind {
+    fn expect_identifier(&mut self) -> PResult<String> {
+        match self.next().kind {
             TokenType::Ident(ident) => {
                 self.advance();
-                Some(ident.clone())
-            }
-            _ => {
-                self.error(
-                    format!("Expected an identifier, got {}", self.peek().unwrap()),
-                    &self.peek().unwrap().clone().span,
-                );
-                exit(100);
+                Ok(ident)
             }
+            _ => Err(self.fail(
+                format!("Expected an identifier, got {}", self.next()),
+                &self.next().span,
+            )),
         }
     }
-    fn expect_separator(&mut self, expected: &str) {
-        match self.peek().unwrap().clone().kind {
+    fn expect_separator(&mut self, expected: &str) -> PResult<()> {
+        match self.next().kind {
             TokenType::Separator(sep) if sep == expected => {
                 self.advance();
+                Ok(())
             }
-            _ => {
-                self.error(
-                    format!("Expected {}, got {}", expected, self.peek().unwrap()),
-                    &self.peek().unwrap().clone().span,
-                );
-                exit(100);
-            }
+            _ => Err(self.fail(
+                format!("Expected {}, got {}", expected, self.next()),
+                &self.next().span,
+            )),
         }
     }
-    fn expect_operator(&mut self, expected: &str) {
-        match self.peek().unwrap().clone().kind {
+    fn expect_operator(&mut self, expected: &str) -> PResult<()> {
+        match self.next().kind {
             TokenType::Operator(k) if k == expected => {
                 self.advance();
+                Ok(())
             }
-            _ => {
-                self.error(
-                    format!("Expected {} and got {}", expected, self.peek().unwrap()),
-                    &self.peek().unwrap().clone().span,
-                );
-                exit(100);
-            }
+            _ => Err(self.fail(
+                format!("Expected {} and got {}", expected, self.next()),
+                &self.next().span,
+            )),
         }
     }
-    fn parse_body(&mut self, take_rets: bool, is_loop: bool) -> Spanned<Node> {
-        let start = self.peek().unwrap().clone().span.start;
+    fn parse_body(&mut self, take_rets: bool, is_loop: bool) -> PResult<Spanned<Node>> {
+        let _ = (take_rets, is_loop);
+        let start = self.next().span.start;
         let mut stmts = Vec::new();
         loop {
-            let token = self.peek();
-            match token.unwrap().clone().kind {
-                TokenType::Comment(_) => {
-                    self.advance();
-                }
-                TokenType::Keyword(k) if k == "let" => {
-                    let m = self.parse_let_stmt();
-                    stmts.push(m);
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "for" => {
-                    let forstmt = self.parse_forstmt();
-                    stmts.push(forstmt);
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "while" => {
-                    let while_stmt = self.parse_while();
-                    stmts.push(while_stmt);
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "fn" => {
-                    let f = self.parse_func();
-                    stmts.push(f.unwrap());
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "break" => {
-                    let span = self.next().span;
-                    if !self.is_inloop {
-                        self.error(format!("Use of break outside of loop body"), &span);
-                        exit(1);
-                    }
-                    self.advance();
-                    self.expect_separator(";");
-                    stmts.push(Spanned {
-                        node: Node::BREAK,
-                        span,
-                    })
-                }
-                TokenType::Keyword(k) if k == "continue" => {
-                    let span = self.next().span;
-                    if !self.is_inloop {
-                        self.error(format!("Use of Continue outside of loop body"), &span);
-                        exit(1);
-                    }
-                    self.advance();
-                    self.expect_separator(";");
-                    stmts.push(Spanned {
-                        node: Node::CONTINUE,
-                        span,
-                    })
-                }
-                TokenType::Keyword(k) if k == "if" => {
-                    let f = self.parse_ifstmt();
-                    stmts.push(f);
-                    continue;
+            match self.next().kind {
+                TokenType::Separator(s) if s == "}" => break,
+                TokenType::EOF => {
+                    return Err(self.fail("Not a valid statment".to_string(), &self.next().span));
                 }
-                TokenType::Ident(val)
-                    if self.tokens.get(self.current + 1).unwrap().clone().kind
-                        == TokenType::Separator('('.to_string()) =>
-                {
-                    let func_call = self.parse_func_call(true);
-                    stmts.push(func_call);
-                    continue;
+                _ => {}
+            }
+            let before = self.current;
+            match self.parse_statement() {
+                Ok(Some(stmt)) => stmts.push(stmt),
+                Ok(None) => {}
+                Err(e) => self.recover(e, before, false),
+            }
+        }
+        let end = self.before().span.end;
+        Ok(Spanned {
+            node: Node::Program(stmts),
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
+    }
+    fn parse_statement(&mut self) -> PResult<Option<Spanned<Node>>> {
+        let start = self.next().span.start;
+        let token = self.next();
+        let stmt = match token.kind.clone() {
+            TokenType::Comment(_) => {
+                self.advance();
+                return Ok(None);
+            }
+            TokenType::Keyword(k) if k == "let" => self.parse_let_stmt()?,
+            TokenType::Keyword(k) if k == "for" => self.parse_forstmt()?,
+            TokenType::Keyword(k) if k == "while" => self.parse_while()?,
+            TokenType::Keyword(k) if k == "fn" => self.parse_func()?,
+            TokenType::Keyword(k) if k == "break" || k == "continue" => {
+                let span = self.next().span;
+                if !self.is_inloop {
+                    let what = if k == "break" { "break" } else { "Continue" };
+                    self.error(format!("Use of {what} outside of loop body"), &span);
                 }
-                TokenType::Ident(val)
-                    if self.tokens.get(self.current + 1).unwrap().clone().kind
-                        == TokenType::Operator("=".to_owned()) =>
-                {
-                    let reval_stmt = self.parse_expr();
-                    self.expect_separator(";");
-                    stmts.push(reval_stmt);
-                    continue;
+                self.advance();
+                self.expect_separator(";")?;
+                Spanned {
+                    node: if k == "break" {
+                        Node::BREAK
+                    } else {
+                        Node::CONTINUE
+                    },
+                    span,
                 }
-                TokenType::Keyword(k) if k == "ret" => {
-                    self.advance();
-                    let expr = self.parse_expr();
-                    self.expect_separator(";");
-
-                    stmts.push(Spanned {
-                        node: Node::Ret(Box::new(expr)),
-                        span: Span {
-                            start,
-                            end: self.before().span.end,
-                        },
-                    });
-                    continue;
+            }
+            TokenType::Keyword(k) if k == "if" => self.parse_ifstmt()?,
+            TokenType::Keyword(k) if k == "match" => self.parse_match_stmt()?,
+            TokenType::Ident(_val)
+                if self.get(1).map(|t| t.kind) == Some(TokenType::Separator('('.to_string())) =>
+            {
+                self.parse_func_call(true)?
+            }
+            TokenType::Ident(_val)
+                if self.get(1).map(|t| t.kind) == Some(TokenType::Operator("=".to_owned())) =>
+            {
+                let reval_stmt = self.parse_expr()?;
+                self.expect_separator(";")?;
+                reval_stmt
+            }
+            TokenType::Keyword(k) if k == "ret" => {
+                self.advance();
+                let expr = self.parse_expr()?;
+                self.expect_separator(";")?;
+                Spanned {
+                    node: Node::Ret(Box::new(expr)),
+                    span: Span {
+                        start,
+                        end: self.before().span.end,
+                        file: self.file,
+                    },
                 }
-                TokenType::EOF => {
-                    self.error(
-                        format!("Not a valid statment"),
-                        &self.peek().unwrap().clone().span,
+            }
+            kind => {
+                if !self.is_expr_start(kind) {
+                    return Err(
+                        self.fail(format!("Expected a statement, got {token}"), &token.span)
                     );
-                    exit(100)
                 }
-                _ => {
-                    if !self.is_expr_start(self.peek().unwrap().clone().kind) {
-                        break;
-                    }
-                    // debug
-                    let expr = self.parse_logic_or();
-                    if self.next().kind == TokenType::Separator(';'.to_string()) {
-                        self.expect_separator(";");
-                    }
-                    stmts.push(expr);
-                    continue;
+                let expr = self.parse_expr()?;
+                if self.next().kind == TokenType::Separator(';'.to_string()) {
+                    self.expect_separator(";")?;
                 }
+                expr
             }
-        }
-        let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-        Spanned {
-            node: Node::Program(stmts),
-            span: Span { start, end },
-        }
+        };
+        Ok(Some(stmt))
     }
-    fn parse_type(&mut self) -> Option<Type> {
-        match Some(self.peek().unwrap().clone().kind) {
-            Some(TokenType::Ident(custom)) => {
+    fn parse_type(&mut self) -> PResult<Type> {
+        let not_a_type =
+            |p: &Self| p.fail(format!("Expected a type, got {}", p.next()), &p.next().span);
+        match self.next().kind {
+            TokenType::Ident(custom) => {
                 let first = custom.clone();
-                match self.get(1).unwrap().kind {
-                    TokenType::DCOLON => {
-                        let pos = self.current - 1;
+                let Some(after) = self.get(1) else {
+                    return Err(not_a_type(self));
+                };
+                match after.kind {
+                    TokenType::Operator(op) if op == "<" => {
                         self.advance();
                         self.advance();
-                        let second = self.parse_type();
-                        if matches!(second, None) {
-                            return None;
+                        let mut args = vec![];
+                        loop {
+                            args.push(self.parse_type()?);
+                            self.advance();
+                            self.split_angle();
+                            match self.next().kind {
+                                TokenType::Separator(s) if s == "," => {
+                                    self.advance();
+                                }
+                                TokenType::Operator(s) if s == ">" => break,
+                                _ => {
+                                    return Err(self.fail(
+                                        format!(
+                                            "Expected , or > after type argument, got {}",
+                                            self.next()
+                                        ),
+                                        &self.next().span,
+                                    ));
+                                }
+                            }
                         }
-                        if self.get(1).unwrap().kind == TokenType::Separator("{".to_string())
-                            || self.get(1).unwrap().kind == TokenType::Separator(",".to_owned())
-                            || (self.get(1).unwrap().kind == TokenType::Operator("=".to_owned())
-                                && self.tokens.get(pos).unwrap().kind
-                                    == TokenType::Separator(":".to_owned()))
-                            || !(self.tokens.get(pos).unwrap().kind
-                                == TokenType::Operator("=".to_owned())
-                                && self.get(1).unwrap().kind
-                                    == TokenType::Separator("(".to_owned()))
+                        Ok(Type::Generic { name: first, args })
+                    }
+                    TokenType::DCOLON => {
+                        let pos = self.current.saturating_sub(1);
+                        self.advance();
+                        self.advance();
+                        let second = self.parse_type()?;
+                        let next = self.get(1).map(|t| t.kind);
+                        if next == Some(TokenType::Separator("{".to_string()))
+                            || next == Some(TokenType::Separator(",".to_owned()))
+                            || (next == Some(TokenType::Operator("=".to_owned()))
+                                && self.tokens[pos].kind == TokenType::Separator(":".to_owned()))
+                            || !(self.tokens[pos].kind == TokenType::Operator("=".to_owned())
+                                && next == Some(TokenType::Separator("(".to_owned())))
                         {
-                            return Some(Type::BundledType {
+                            return Ok(Type::BundledType {
                                 bundle: first,
-                                ty: Box::new(second.unwrap()),
+                                ty: Box::new(second),
                             });
                         }
                         if self.before().kind == TokenType::Keyword("for".into()) {
-                            return Some(Type::BundledType {
+                            return Ok(Type::BundledType {
                                 bundle: first,
-                                ty: Box::new(second.unwrap()),
+                                ty: Box::new(second),
                             });
                         }
-                        None
+                        Err(not_a_type(self))
                     }
 
-                    _ => {
-                        if self.get(1).unwrap().kind == TokenType::Separator("{".to_string())
-                            || (self.get(1).unwrap().kind == TokenType::Separator(",".to_owned())
-                                && self.tokens.get(self.current - 2).unwrap().kind
+                    next => {
+                        let before = self.before().kind;
+                        if next == TokenType::Separator("{".to_string())
+                            || (next == TokenType::Separator(",".to_owned())
+                                && self.current >= 2
+                                && self.tokens[self.current - 2].kind
                                     == TokenType::Separator(":".to_owned()))
-                            || (self.get(1).unwrap().kind == TokenType::Operator("=".to_owned())
-                                && self.before().kind == TokenType::Separator(":".to_owned()))
-                            || (self.get(1).unwrap().kind == TokenType::Separator(")".to_owned())
-                                && self.get(2).unwrap().kind
-                                    == TokenType::Separator(":".to_owned()))
-                            || (self.before().kind == TokenType::DCOLON
-                                && (self.get(1).unwrap().kind
-                                    != TokenType::Separator("(".to_owned())))
-                            || self.get(1).unwrap().kind == TokenType::Operator(">".to_owned())
-                            || !(self.get(1).unwrap().kind == TokenType::Separator(";".to_owned()))
+                            || (next == TokenType::Operator("=".to_owned())
+                                && before == TokenType::Separator(":".to_owned()))
+                            || (next == TokenType::Separator(")".to_owned())
+                                && self.get(2).map(|t| t.kind)
+                                    == Some(TokenType::Separator(":".to_owned())))
+                            || (before == TokenType::DCOLON
+                                && (next != TokenType::Separator("(".to_owned())))
+                            || next == TokenType::Operator(">".to_owned())
+                            || !(next == TokenType::Separator(";".to_owned()))
+                            || before == TokenType::Separator(":".to_owned())
                         {
-                            return Some(Type::Custom(first));
+                            return Ok(Type::Custom(first));
                         }
-                        if self.before().kind == TokenType::Keyword("for".into()) {
-                            return Some(Type::Custom(first));
+                        if before == TokenType::Keyword("for".into()) {
+                            return Ok(Type::Custom(first));
                         }
-                        None
+                        Err(not_a_type(self))
                     }
                 }
             }
-            Some(TokenType::Keyword(t)) if t == "int" => Some(Type::INT),
-            Some(TokenType::Keyword(t)) if t == "str" => Some(Type::STR),
-            Some(TokenType::Keyword(t)) if t == "i8" => Some(Type::I8),
-            Some(TokenType::Keyword(t)) if t == "i16" => Some(Type::I16),
-            Some(TokenType::Keyword(t)) if t == "u64" => Some(Type::U64),
-            Some(TokenType::Keyword(t)) if t == "i32" => Some(Type::I32),
-            Some(TokenType::Keyword(t)) if t == "i64" => Some(Type::I64),
-            Some(TokenType::Keyword(t)) if t == "u8" => Some(Type::U8),
-            Some(TokenType::Keyword(t)) if t == "u16" => Some(Type::U16),
-            Some(TokenType::Keyword(t)) if t == "u32" => Some(Type::U32),
-            Some(TokenType::Keyword(t)) if t == "char" => Some(Type::CHAR),
-            Some(TokenType::Keyword(t)) if t == "void" => Some(Type::NoType),
-            Some(TokenType::Keyword(k)) if k == "mut" => {
+            TokenType::Keyword(t) if t == "int" => Ok(Type::INT),
+            TokenType::Keyword(t) if t == "str" => Ok(Type::STR),
+            TokenType::Keyword(t) if t == "i8" => Ok(Type::I8),
+            TokenType::Keyword(t) if t == "i16" => Ok(Type::I16),
+            TokenType::Keyword(t) if t == "u64" => Ok(Type::U64),
+            TokenType::Keyword(t) if t == "i32" => Ok(Type::I32),
+            TokenType::Keyword(t) if t == "i64" => Ok(Type::I64),
+            TokenType::Keyword(t) if t == "u8" => Ok(Type::U8),
+            TokenType::Keyword(t) if t == "u16" => Ok(Type::U16),
+            TokenType::Keyword(t) if t == "u32" => Ok(Type::U32),
+            TokenType::Keyword(t) if t == "f32" => Ok(Type::F32),
+            TokenType::Keyword(t) if t == "f64" => Ok(Type::F64),
+            TokenType::Keyword(t) if t == "char" => Ok(Type::CHAR),
+            TokenType::Keyword(t) if t == "bool" => Ok(Type::BOOL),
+            TokenType::Keyword(t) if t == "void" => Ok(Type::NoType),
+            TokenType::Keyword(k) if k == "mut" => {
+                self.advance();
+                let inner = self.parse_type()?;
+                Ok(Type::MUT(Box::new(inner)))
+            }
+            TokenType::Keyword(k) if k == "dyn" => {
                 self.advance();
-                let inner = self.parse_type().unwrap();
-                return Some(Type::MUT(Box::new(inner)));
+                let tr = self.parse_type()?;
+                Ok(Type::Dyn(Box::new(tr)))
             }
-            Some(TokenType::Keyword(t)) if t == "list" => {
+            TokenType::Keyword(t) if t == "list" => {
                 self.advance();
-                self.expect_operator("<");
-                let inner = self.parse_type();
+                self.expect_operator("<")?;
+                let inner = self.parse_type()?;
                 self.advance();
-                self.expect_separator(",");
-                if let TokenType::Number(size) = self.next().kind {
+                self.expect_separator(",")?;
+                if let TokenType::Number(size, _) = self.next().kind {
                     self.advance();
+                    self.split_angle();
                     if let TokenType::Operator(_) = self.next().kind {
-                        return Some(Type::List(Box::new(inner.unwrap()), size));
+                        Ok(Type::List(Box::new(inner), size))
                     } else {
-                        self.expect_operator(">");
-                        exit(100);
+                        Err(self.fail(
+                            format!("Expected > and got {}", self.next()),
+                            &self.next().span,
+                        ))
                     }
                 } else {
-                    let span = self.next().span;
-                    self.error(format!("Expected list size"), &span);
-                    exit(100);
+                    Err(self.fail("Expected list size".to_string(), &self.next().span))
                 }
             }
-            Some(TokenType::Keyword(t)) if t == "ptr" => {
+            TokenType::Keyword(t) if t == "ptr" => {
                 self.advance();
-                self.expect_operator("<");
-                let inner = self.parse_type();
+                self.expect_operator("<")?;
+                let inner = self.parse_type()?;
                 self.advance();
+                self.split_angle();
                 if self.next().kind == TokenType::Operator(">".into()) {
-                    return Some(Type::PTR(Box::new(inner.unwrap())));
+                    return Ok(Type::PTR(Box::new(inner)));
                 }
-                None
+                Err(self.fail(
+                    format!("Expected > and got {}", self.next()),
+                    &self.next().span,
+                ))
             }
 
-            Some(_) => None,
-            None => None,
+            _ => Err(not_a_type(self)),
         }
     }
     fn is_generic_context(&mut self) -> bool {
@@ -692,8 +1013,9 @@ impl Parser {
         let mut i = 1;
         while let Some(token) = self.get(i) {
             match token.kind {
-                TokenType::Operator(val) if val == ">" => {
-                    depth -= 1;
+                /* `>>` closes two lists at once, as in `f<ptr<int>>()` */
+                TokenType::Operator(val) if val == ">" || (val == ">>" && depth >= 2) => {
+                    depth -= val.len();
                     if depth == 0 {
                         if let Some(next) = self.get(i + 1) {
                             match next.kind {
@@ -711,7 +1033,7 @@ impl Parser {
                 }
                 TokenType::Operator(val) if val == "<" => depth += 1,
                 TokenType::Operator(v)
-                    if ["+", "-", "*", "/", "%", "==", "!="].contains(&v.as_str()) =>
+                    if ["+", "-", "*", "/", "%", "==", "!=", "&&", "||"].contains(&v.as_str()) =>
                 {
                     return false;
                 }
@@ -727,940 +1049,1304 @@ impl Parser {
         }
         false
     }
-    fn parse_logic_or(&mut self) -> Spanned<Node> {
-        let start = self.next().span.start;
-        let mut left = self.parse_logic_and();
-        if self.next().kind == TokenType::Operator("||".to_owned()) {
-            let op = self.next().kind;
-            self.advance();
-            let right = self.parse_logic_and();
-            let end = self.before().span.end;
-            left = Spanned {
-                node: Node::BinaryExpr {
-                    lhs: Box::new(left),
-                    opr: op,
-                    rhs: Box::new(right),
-                },
-                span: Span { start, end },
-            }
-        }
-        left
-    }
-    fn parse_logic_and(&mut self) -> Spanned<Node> {
-        let start = self.next().span.start;
-        let mut left = self.parse_equality();
-        if self.next().kind == TokenType::Operator("&&".to_owned()) {
-            let op = self.next().kind;
-            self.advance();
-            let right = self.parse_equality();
-            let end = self.before().span.end;
-            left = Spanned {
-                node: Node::BinaryExpr {
-                    lhs: Box::new(left),
-                    opr: op,
-                    rhs: Box::new(right),
-                },
-                span: Span { start, end },
-            }
-        }
-        left
-    }
-    fn parse_equality(&mut self) -> Spanned<Node> {
-        let start = self.next().span.start;
-        let mut left = self.parse_comparison();
-        if self.next().kind == TokenType::Operator("==".to_owned()) {
-            let op = self.next().kind;
-            self.advance();
-            let right = self.parse_comparison();
-            let end = self.before().span.end;
-            left = Spanned {
-                node: Node::BinaryExpr {
-                    lhs: Box::new(left),
-                    opr: op,
-                    rhs: Box::new(right),
-                },
-                span: Span { start, end },
-            }
-        }
-        left
+    /// Parses a whole expression, assignments included.
+    ///
+    /// Expressions are parsed by binding power (a Pratt parser). From the loosest
+    /// to the tightest binding:
+    ///
+    /// | operators                              | associativity |
+    /// |----------------------------------------|---------------|
+    /// | `=` `+=` `-=` `*=` `/=` `%=` `&=` `\|=` `^=` `<<=` `>>=` | right |
+    /// | `\|\|`                                 | left          |
+    /// | `&&`                                   | left          |
+    /// | `\|`                                   | left          |
+    /// | `^`                                    | left          |
+    /// | `&`                                    | left          |
+    /// | `==` `!=`                              | left          |
+    /// | `<` `>` `<=` `>=`                      | left          |
+    /// | `<<` `>>`                              | left          |
+    /// | `+` `-`                                | left          |
+    /// | `*` `/` `%`                            | left          |
+    /// | `as`                                   | left          |
+    /// | prefix `-` `!` `~` `*` `&`             | right         |
+    /// | postfix `.` `()` `[]` `::` `<T>()`     | left          |
+    fn parse_expr(&mut self) -> PResult<Spanned<Node>> {
+        self.parse_expr_bp(0)
     }
 
-    fn parse_comparison(&mut self) -> Spanned<Node> {
+    /// Parses an expression whose operators all bind tighter than `min_bp`.
+    fn parse_expr_bp(&mut self, min_bp: u8) -> PResult<Spanned<Node>> {
         let start = self.next().span.start;
-        let mut left = self.parse_expr();
-        match self.next().kind {
-            TokenType::Operator(v)
-                if v == ">" || v == "<" || v == ">=" || v == "<=" || v == "!=" =>
-            {
-                let op = self.next().kind;
-                self.advance();
-                let right = self.parse_expr();
-                let end = self.before().span.end;
-                left = Spanned {
-                    node: Node::BinaryExpr {
-                        lhs: Box::new(left),
-                        opr: op,
-                        rhs: Box::new(right),
-                    },
-                    span: Span { start, end },
-                };
-                left
-            }
-            _ => left,
-        }
-    }
-    fn parse_expr(&mut self) -> Spanned<Node> {
-        let start = self.peek().unwrap().clone().span.start;
-        match self.peek().cloned().unwrap().kind {
-            TokenType::Operator(v) if v == "*" => {
-                // dereference operation
-                self.advance();
-                let expr = self.parse_logic_or();
-                let end = self.tokens.get(self.current - 1).unwrap().span.end;
-                return Spanned {
-                    node: Node::DeRefExpr {
-                        expr: Box::new(expr),
-                    },
-                    span: Span { start, end },
-                };
-            }
-            TokenType::Operator(v) if v == "&" => {
-                // dereference operation
-                self.advance();
-                let expr = self.parse_logic_or();
-                let end = self.tokens.get(self.current - 1).unwrap().span.end;
-                return Spanned {
-                    node: Node::RefExpr {
-                        expr: Box::new(expr),
-                    },
-                    span: Span { start, end },
-                };
-            }
-            TokenType::Keyword(k) if k == "null" => {
-                self.advance();
-                let end = self.tokens.get(self.current - 1).unwrap().span.end;
-                return Spanned {
-                    node: Node::NULLPTR,
-                    span: Span { start, end },
-                };
-            }
-            TokenType::Ident(val) | TokenType::Number(val) => {
-                // debug
-                let mut left = self.parse_term();
-                while matches!(
-                    self.peek().cloned().unwrap().kind,
-                    TokenType::Operator(v) | TokenType::Operator(v) if v == "+" || v == "-"
-                ) {
-                    let opr = self.peek().cloned().unwrap().kind;
+        let mut lhs = self.parse_prefix()?;
+        loop {
+            lhs = self.parse_postfix(lhs)?;
+            let TokenType::Operator(op) = self.next().kind else {
+                if self.next().kind == TokenType::Keyword("as".into()) && CAST_BP > min_bp {
                     self.advance();
-                    let right = self.parse_term();
-                    let end = self.tokens.get(self.current - 1).unwrap().span.end;
-                    left = Spanned {
-                        node: Node::BinaryExpr {
-                            lhs: Box::new(left),
-                            opr,
-                            rhs: Box::new(right),
-                        },
-                        span: Span { start, end },
-                    }
-                }
-                if self.next().kind == TokenType::Operator('='.to_string()) {
-                    self.expect_operator("=");
-                    let mut value = self.parse_expr();
-                    left = Spanned {
-                        node: Node::ReVal {
-                            name: Box::new(left.clone()),
-                            value: Box::new(value),
-                        },
-                        span: left.span,
-                    }
-                } else if self.next().kind == TokenType::Separator('['.to_string()) {
+                    let ty = self.parse_type()?;
                     self.advance();
-                    let index = self.parse_logic_or();
-                    left = Spanned {
-                        node: Node::ListAccess {
-                            name: Box::new(left.clone()),
-                            index: Box::new(index),
+                    lhs = Spanned {
+                        node: Node::Cast {
+                            expr: Box::new(lhs),
+                            ty,
                         },
-                        span: left.span.clone(),
+                        span: self.span_from(start),
                     };
-                    self.expect_separator("]");
-                    if self.next().kind == TokenType::Separator('.'.to_string()) {
-                        left = self.parse_postfix(left.clone());
-                    }
-                }
-
-                return left;
-            }
-            // TokenType::Ident(var) => {
-            //     let c = self.parse_term();
-            //     return c;
-            // }
-            TokenType::Separator(sep) if sep == "[" => {
-                self.advance();
-                let mut contents = Vec::new();
-                loop {
-                    let expr = self.parse_logic_or();
-                    contents.push(Box::new(expr));
-                    if self.next().kind != TokenType::Separator(",".to_owned()) {
-                        break;
-                    } else {
-                        self.advance();
-                    }
-                }
-                self.expect_separator("]");
-                let end = self.before().span.end;
-                Spanned {
-                    node: Node::ListInit { content: contents },
-                    span: Span { start, end },
-                }
-            }
-            TokenType::Separator(sep) if sep == "(" => {
-                let mut left = self.parse_term();
-
-                while matches!(
-                    self.peek().cloned().unwrap().kind,
-                    TokenType::Operator(v) | TokenType::Operator(v) if v == "+" || v == "-"
-                ) {
-                    let opr = self.peek().cloned().unwrap().kind;
-                    self.advance();
-                    let right = self.parse_term();
-                    let end = self.tokens.get(self.current - 1).unwrap().span.end;
-                    left = Spanned {
-                        node: Node::BinaryExpr {
-                            lhs: Box::new(left),
-                            opr,
-                            rhs: Box::new(right),
-                        },
-                        span: Span { start, end },
-                    }
-                }
-                if self.next().kind == TokenType::Operator('='.to_string()) {
-                    self.expect_operator("=");
-                    let value = self.parse_logic_or();
-                    left = Spanned {
-                        node: Node::ReVal {
-                            name: Box::new(left.clone()),
-                            value: Box::new(value),
-                        },
-                        span: left.span,
-                    }
+                    continue;
                 }
-                return left;
-            }
-            TokenType::Separator(val) if val == "{".to_owned() => {
-                let start = self.peek().cloned().unwrap().span.start;
-                self.expect_separator("{");
-                let mut fields = Vec::new();
-                while self.peek().cloned().unwrap().kind != TokenType::Separator('}'.to_string()) {
-                    let pstart = self.peek().cloned().unwrap().span.start;
-                    let pname = self.expect_identifier().clone().unwrap();
-                    self.expect_separator(":");
-                    let pvalue = self.parse_logic_or();
-                    if self.next().kind == TokenType::Separator(','.to_string()) {
-                        self.expect_separator(",");
-                    }
-                    let pend = self.before().span.end;
-                    fields.push(Spanned {
-                        node: Node::Pair {
-                            field: pname.clone(),
-                            value: Box::new(pvalue),
-                        },
-                        span: Span {
-                            start: pstart,
-                            end: pend,
-                        },
-                    });
-                    let pend = self.before().span.end;
+                break;
+            };
+            if ASSIGNMENTS.contains(&op.as_str()) {
+                if min_bp > 0 {
+                    break;
                 }
-                self.expect_separator("}");
-                let end = self.before().span.end;
-                return Spanned {
-                    node: Node::StructInit { fields },
-                    span: Span { start, end },
-                };
+                return self.parse_assignment(lhs);
             }
-            TokenType::StrLit(var) => self.parse_primary(),
-            TokenType::Char(c) => self.parse_primary(),
-
-            _ => {
-                self.error(
-                    format!("Not an expression {}", self.peek().cloned().unwrap()),
-                    &self.peek().cloned().unwrap().span,
-                );
-                exit(100)
-            }
-        }
-    }
-    fn parse_term(&mut self) -> Spanned<Node> {
-        let start = self.peek().unwrap().clone().span.start;
-        let mut lhs = { self.parse_primary() };
-        lhs = self.parse_postfix(lhs.clone());
-        while let TokenType::Operator(v) = self.peek().unwrap().clone().kind {
-            if v != "*" && v != "/" {
+            let Some((left_bp, right_bp)) = infix_binding_power(&op) else {
+                break;
+            };
+            if left_bp <= min_bp {
                 break;
             }
-            let op = self.peek().unwrap().clone();
+            let opr = self.next().kind;
             self.advance();
-            let mut rhs = { self.parse_primary() };
-            rhs = self.parse_postfix(rhs.clone());
-            let end = self.tokens.get(self.current - 1).unwrap().span.end;
+            let rhs = self.parse_expr_bp(right_bp)?;
             lhs = Spanned {
                 node: Node::BinaryExpr {
                     lhs: Box::new(lhs),
-                    opr: op.clone().kind,
+                    opr,
                     rhs: Box::new(rhs),
                 },
-                span: Span { start, end },
-            }
+                span: self.span_from(start),
+            };
+        }
+        Ok(lhs)
+    }
+
+    /// Parses a prefix operator applied to its operand, or a primary expression.
+    fn parse_prefix(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        let TokenType::Operator(op) = self.next().kind else {
+            return self.parse_primary();
+        };
+        if !["-", "!", "~", "*", "&"].contains(&op.as_str()) {
+            return Err(self.fail(
+                format!("Not an expression {}", self.next()),
+                &self.next().span,
+            ));
         }
-        lhs
+        self.advance();
+        let operand = Box::new(self.parse_expr_bp(PREFIX_BP)?);
+        let node = match op.as_str() {
+            "*" => Node::DeRefExpr { expr: operand },
+            "&" => Node::RefExpr { expr: operand },
+            _ => Node::UnaryExpr {
+                opr: TokenType::Operator(op),
+                expr: operand,
+            },
+        };
+        Ok(Spanned {
+            node,
+            span: self.span_from(start),
+        })
     }
-    fn parse_postfix(&mut self, mut expr: Spanned<Node>) -> Spanned<Node> {
-        let start = self.peek().cloned().unwrap().span.start;
+
+    /// Applies the postfix operators following `expr`: member access, calls,
+    /// indexing, bundle paths and generic calls.
+    fn parse_postfix(&mut self, mut expr: Spanned<Node>) -> PResult<Spanned<Node>> {
+        let start = expr.span.start;
         loop {
-            match self.peek().cloned().unwrap().kind {
+            match self.next().kind {
                 TokenType::DOT => {
                     self.advance();
-                    let field = self.expect_identifier();
-
-                    let end = self.tokens.get(self.current - 1).cloned().unwrap().span.end;
+                    let field = self.expect_identifier()?;
                     expr = Spanned {
                         node: Node::MemberAccess {
                             base: Box::new(expr),
-                            field: field.unwrap(),
+                            field,
                         },
-                        span: Span { start, end },
+                        span: self.span_from(start),
                     };
-                    continue;
                 }
                 TokenType::Separator(v) if v == "(" => {
-                    self.expect_separator("(");
-                    let args: Vec<Spanned<Node>> = self.parse_args();
-                    self.expect_separator(")");
-                    let end = self.tokens.get(self.current - 1).cloned().unwrap().span.end;
+                    self.expect_separator("(")?;
+                    let args: Vec<Spanned<Node>> = self.parse_args()?;
+                    self.expect_separator(")")?;
                     expr = Spanned {
                         node: Node::FcCall {
                             callee: Box::new(expr),
                             params: args,
                         },
-                        span: Span { start, end },
+                        span: self.span_from(start),
                     };
-                    continue;
                 }
                 TokenType::DCOLON => {
                     self.advance();
-                    let field = self.parse_logic_or();
-                    let end = self.tokens.get(self.current - 1).cloned().unwrap().span.end;
+                    let field = self.parse_path_segment()?;
                     expr = Spanned {
                         node: Node::BundleAccess {
                             base: Box::new(expr),
                             field: Box::new(field),
                         },
-                        span: Span { start, end },
+                        span: self.span_from(start),
                     };
                 }
                 TokenType::Separator(sep) if sep == "[" => {
                     self.advance();
-                    let index = self.parse_logic_or();
-                    self.expect_separator("]");
-                    let end = self.before().span.end;
+                    let index = self.parse_expr()?;
+                    self.expect_separator("]")?;
                     expr = Spanned {
                         node: Node::ListAccess {
                             name: Box::new(expr),
                             index: Box::new(index),
                         },
-                        span: Span { start, end },
+                        span: self.span_from(start),
                     }
                 }
-                TokenType::Operator(v) if v == "<" => {
-                    if !self.is_generic_context() {
-                        return expr;
-                    }
-                    self.advance();
-                    let mut g = vec![];
-                    while self.next().kind != TokenType::Operator(">".to_owned()) {
-                        let t = self.parse_type();
-                        if t.is_none() {
-                            break;
-                        }
-                        g.push(t.unwrap());
-                        self.advance();
-                        if self.next().kind == TokenType::Separator(",".to_owned()) {
-                            self.advance();
-                        }
-                    }
-                    self.expect_operator(">");
-                    self.expect_separator("(");
-                    let a = self.parse_args();
-                    self.expect_separator(")");
-                    let end = self.before().span.end;
+                TokenType::Operator(v) if v == "<" && self.is_generic_context() => {
+                    expr = self.parse_generic_call(expr)?;
+                }
+                _ => break,
+            }
+        }
+        Ok(expr)
+    }
+
+    /// Parses what follows a `::`: a name, optionally called, or a further path.
+    /// Member access and indexing are left to the whole path, so `a::b().c` reads
+    /// as `(a::b()).c`.
+    fn parse_path_segment(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        let name = self.expect_identifier()?;
+        let mut expr = Spanned {
+            node: Node::Token(name, false),
+            span: self.span_from(start),
+        };
+        loop {
+            match self.next().kind {
+                TokenType::Separator(v) if v == "(" => {
+                    self.expect_separator("(")?;
+                    let args = self.parse_args()?;
+                    self.expect_separator(")")?;
                     expr = Spanned {
-                        node: Node::GenericFnCall {
+                        node: Node::FcCall {
                             callee: Box::new(expr),
-                            generics: g,
-                            args: a,
+                            params: args,
                         },
-                        span: Span { start, end },
+                        span: self.span_from(start),
                     };
                 }
-                TokenType::Keyword(v) if v == "as" => {
-                    self.advance();
-                    let ty = self.parse_type().unwrap();
+                TokenType::Operator(v) if v == "<" && self.is_generic_context() => {
+                    expr = self.parse_generic_call(expr)?;
+                }
+                TokenType::DCOLON => {
                     self.advance();
-                    let end = self.before().span.end;
-                    expr = Spanned {
-                        node: Node::Cast {
-                            expr: Box::new(expr),
-                            ty,
+                    let field = self.parse_path_segment()?;
+                    return Ok(Spanned {
+                        node: Node::BundleAccess {
+                            base: Box::new(expr),
+                            field: Box::new(field),
                         },
-                        span: Span { start, end },
-                    }
+                        span: self.span_from(start),
+                    });
                 }
-                _ => break,
+                _ => return Ok(expr),
             }
         }
-        let end = self.tokens.get(self.current - 1).cloned().unwrap().span.end;
-        expr
-    }
-    fn parse_primary(&mut self) -> Spanned<Node> {
-        let start = self.peek().unwrap().clone().span.start;
-        let mut is_deref = false;
-        let base = match self.peek().cloned().unwrap().kind {
-            TokenType::Number(val) => {
+    }
+
+    /// Parses `<T, ...>(args)` after `callee`.
+    fn parse_generic_call(&mut self, callee: Spanned<Node>) -> PResult<Spanned<Node>> {
+        let start = callee.span.start;
+        self.expect_operator("<")?;
+        let mut g = vec![];
+        while self.next().kind != TokenType::Operator(">".to_owned()) {
+            let Ok(t) = self.parse_type() else {
+                break;
+            };
+            g.push(t);
+            self.advance();
+            self.split_angle();
+            if self.next().kind == TokenType::Separator(",".to_owned()) {
                 self.advance();
-                let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-                Spanned {
-                    node: Node::LiteralInt(val),
-                    span: Span { start, end },
-                }
             }
-            TokenType::StrLit(val) => {
+        }
+        self.expect_operator(">")?;
+        self.expect_separator("(")?;
+        let a = self.parse_args()?;
+        self.expect_separator(")")?;
+        Ok(Spanned {
+            node: Node::GenericFnCall {
+                callee: Box::new(callee),
+                generics: g,
+                args: a,
+            },
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_primary(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        let node = match self.next().kind {
+            TokenType::Number(val, suffix) => {
                 self.advance();
-                let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-                Spanned {
-                    node: Node::LiteralStr(val),
-                    span: Span { start, end },
-                }
+                Node::LiteralInt(val, suffix.map(|s| int_suffix(&s)))
             }
-            TokenType::Char(c) => {
+            TokenType::Float(val, suffix) => {
                 self.advance();
-                let end = self.before().span.end;
-                Spanned {
-                    node: Node::LiteralCh(c),
-                    span: Span { start, end },
-                }
+                let ty = suffix.map(|s| match s.as_str() {
+                    "f32" => Type::F32,
+                    _ => Type::F64,
+                });
+                Node::LiteralFloat(val, ty)
             }
-            TokenType::Ident(ident)
-                if self.tokens.get(self.current + 1).unwrap().clone().kind == TokenType::DOT =>
-            {
+            TokenType::Keyword(k) if k == "true" || k == "false" => {
                 self.advance();
-                let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-                Spanned {
-                    node: Node::Token(ident, is_deref),
-                    span: Span { start, end },
-                }
+                Node::LiteralBool(k == "true")
+            }
+            TokenType::Keyword(k) if k == "null" => {
+                self.advance();
+                Node::NULLPTR
+            }
+            TokenType::Keyword(k) if INTRINSICS.contains(&k.as_str()) => {
+                self.advance();
+                Node::Intrinsic(k)
+            }
+            TokenType::Keyword(k) if k == "match" => return self.parse_match(),
+            TokenType::Keyword(k) if k == "sizeof" => {
+                self.advance();
+                self.expect_separator("(")?;
+                let ty = self.parse_type()?;
+                self.advance();
+                self.expect_separator(")")?;
+                Node::SizeOf(ty)
+            }
+            TokenType::StrLit(val) => {
+                self.advance();
+                Node::LiteralStr(val)
+            }
+            TokenType::Char(c) => {
+                self.advance();
+                Node::LiteralCh(c)
             }
             TokenType::Ident(ident) => {
                 self.advance();
-                let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-                Spanned {
-                    node: Node::Token(ident, is_deref),
-                    span: Span { start, end },
-                }
+                Node::Token(ident, false)
             }
             TokenType::Separator(val) if val == "(" => {
                 self.advance();
-                let m = self.parse_logic_or();
-                if self.peek().unwrap().clone().kind != TokenType::Separator(")".to_owned()) {
-                    panic!("Expected Matching Closing Parethesis");
+                let inner = self.parse_expr()?;
+                self.expect_separator(")")?;
+                return Ok(inner);
+            }
+            TokenType::Separator(sep) if sep == "[" => {
+                self.advance();
+                let mut contents = Vec::new();
+                loop {
+                    let expr = self.parse_expr()?;
+                    contents.push(Box::new(expr));
+                    if self.next().kind != TokenType::Separator(",".to_owned()) {
+                        break;
+                    } else {
+                        self.advance();
+                    }
                 }
-                self.advance(); // skip closing braket
-                m
+                self.expect_separator("]")?;
+                Node::ListInit { content: contents }
+            }
+            TokenType::Separator(val) if val == "{" => {
+                self.expect_separator("{")?;
+                let mut fields = Vec::new();
+                while self.next().kind != TokenType::Separator('}'.to_string()) {
+                    let pstart = self.next().span.start;
+                    let pname = self.expect_identifier()?;
+                    self.expect_separator(":")?;
+                    let pvalue = self.parse_expr()?;
+                    if self.next().kind == TokenType::Separator(','.to_string()) {
+                        self.expect_separator(",")?;
+                    } else if self.next().kind != TokenType::Separator('}'.to_string()) {
+                        return Err(self.fail(
+                            format!("Expected , or }} after field value, got {}", self.next()),
+                            &self.next().span,
+                        ));
+                    }
+                    fields.push(Spanned {
+                        node: Node::Pair {
+                            field: pname.clone(),
+                            value: Box::new(pvalue),
+                        },
+                        span: self.span_from(pstart),
+                    });
+                }
+                self.expect_separator("}")?;
+                Node::StructInit { fields }
             }
-
             _ => {
-                let tok = self.next().kind;
-                if self.is_expr_start(tok) {
-                    return self.parse_logic_or();
+                if let Some(span) = self.after_error.get(&self.current) {
+                    return Ok(Spanned {
+                        node: Node::Poison,
+                        span: span.clone(),
+                    });
                 }
-                self.error(
-                    format!("Expected an Term"),
-                    &self.peek().unwrap().clone().span,
-                );
-                exit(100)
+                return Err(self.fail(
+                    format!("Not an expression {}", self.next()),
+                    &self.next().span,
+                ));
             }
         };
-        self.parse_postfix(base)
+        Ok(Spanned {
+            node,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Span from `start` to the end of the last consumed token.
+    fn span_from(&self, start: usize) -> Span {
+        Span {
+            start,
+            end: self.before().span.end,
+            file: self.file,
+        }
     }
 
-    fn parse_func_call(&mut self, check_semi: bool) -> Spanned<Node> {
-        let start = self.peek().unwrap().clone().span.start;
-        let callee = self.parse_logic_or();
+    /// Splits a `>>`, `>=` or `>>=` at the cursor into `>` and the rest, for when
+    /// its first `>` closes a type argument list as in `ptr<ptr<int>>`.
+    fn split_angle(&mut self) {
+        let token = self.next();
+        let TokenType::Operator(op) = &token.kind else {
+            return;
+        };
+        let Some(rest) = op.strip_prefix('>').filter(|rest| !rest.is_empty()) else {
+            return;
+        };
+        let mut rest_token = token.clone();
+        rest_token.kind = TokenType::Operator(rest.to_string());
+        rest_token.span.start += 1;
+        let angle = Token {
+            kind: TokenType::Operator(">".to_owned()),
+            span: Span {
+                end: token.span.start + 1,
+                ..token.span
+            },
+        };
+        self.tokens[self.current] = angle;
+        self.tokens.insert(self.current + 1, rest_token);
+    }
+    /// Parses the `= value` after `target`. A compound assignment such as
+    /// `target += value` is lowered to `target = target + value`.
+    fn parse_assignment(&mut self, target: Spanned<Node>) -> PResult<Spanned<Node>> {
+        let TokenType::Operator(op) = self.next().kind else {
+            return Ok(target);
+        };
+        if !matches!(
+            target.node,
+            Node::Token(..)
+                | Node::MemberAccess { .. }
+                | Node::ListAccess { .. }
+                | Node::DeRefExpr { .. }
+        ) {
+            return Err(self.fail(
+                format!("Cannot assign to this expression with '{op}'"),
+                &target.span,
+            ));
+        }
+        self.advance();
+        let mut value = self.parse_expr()?;
+        if let Some(binary) = op.strip_suffix('=').filter(|b| !b.is_empty()) {
+            value = Spanned {
+                span: Span {
+                    start: target.span.start,
+                    end: value.span.end,
+                    file: self.file,
+                },
+                node: Node::BinaryExpr {
+                    lhs: Box::new(target.clone()),
+                    opr: TokenType::Operator(binary.to_string()),
+                    rhs: Box::new(value),
+                },
+            };
+        }
+        Ok(Spanned {
+            span: target.span.clone(),
+            node: Node::ReVal {
+                name: Box::new(target),
+                value: Box::new(value),
+            },
+        })
+    }
+    fn parse_func_call(&mut self, check_semi: bool) -> PResult<Spanned<Node>> {
+        let callee = self.parse_expr()?;
         // debug
         if self.next().kind == TokenType::Separator(')'.to_string()) {
-            self.expect_separator(")");
+            self.expect_separator(")")?;
         }
         if check_semi {
-            self.expect_separator(";");
+            self.expect_separator(";")?;
         }
-        let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-        callee
+        Ok(callee)
     }
 
-    fn parse_extern(&mut self) -> Spanned<Node> {
-        let start = self.peek().unwrap().span.start;
+    fn parse_extern(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        let doc = self.take_doc();
         self.advance(); // skip the keyword
-	if self.next().kind == TokenType::Keyword("fn".to_owned()) {
-	    self.advance();
-            let name = self.expect_identifier().unwrap().clone();
-            self.expect_separator("(");
+        if self.next().kind == TokenType::Keyword("fn".to_owned()) {
+            self.advance();
+            let name = self.expect_identifier()?;
+            self.expect_separator("(")?;
             let mut args = Vec::new();
             let mut vardaic = false;
-            while self.peek().unwrap().kind.clone() != TokenType::Separator(')'.to_string()) {
-		let arg_name: String = {
-                    match self.peek().cloned().unwrap().kind {
-			TokenType::Ident(val) => {
-                            self.advance();
-                            val.clone()
-			}
-			_ => {
-                            self.error(
-				format!(
-                                    "Expected an Identifier, got {:?}",
-                                    self.peek().cloned().unwrap().kind.clone()
-				),
-				&self.peek().unwrap().span.clone(),
-                            );
-                            exit(100);
-			}
+            while self.next().kind != TokenType::Separator(')'.to_string()) {
+                let arg_name: String = match self.next().kind {
+                    TokenType::Ident(val) => {
+                        self.advance();
+                        val
                     }
-		};
-		self.expect_separator(":");
-		let mut is_ref = false;
-		if self.peek().unwrap().clone().kind == TokenType::Operator('%'.to_string()) {
+                    kind => {
+                        return Err(self.fail(
+                            format!("Expected an Identifier, got {:?}", kind),
+                            &self.next().span,
+                        ));
+                    }
+                };
+                self.expect_separator(":")?;
+                let mut is_ref = false;
+                if self.next().kind == TokenType::Operator('%'.to_string()) {
                     is_ref = true;
                     self.advance();
-		}
-		let type_hint = self.parse_type();
-		self.advance();
-		let arg = FunctionArg {
+                }
+                let type_hint = self.parse_type()?;
+                self.advance();
+                let arg = FunctionArg {
                     name: arg_name,
-                    type_hint: type_hint.unwrap(),
+                    type_hint,
                     is_ref,
-		};
-		args.push(arg);
-		if self.peek().unwrap().clone().kind == TokenType::Separator(','.to_string()) {
-                    self.expect_separator(",");
-                    if self.peek().unwrap().clone().kind == TokenType::Vardaic {
-			self.advance();
-			vardaic = true;
-			break;
+                };
+                args.push(arg);
+                if self.next().kind == TokenType::Separator(','.to_string()) {
+                    self.expect_separator(",")?;
+                    if self.next().kind == TokenType::Vardaic {
+                        self.advance();
+                        vardaic = true;
+                        break;
                     }
-		}
+                    if self.next().kind == TokenType::DOTDOT {
+                        return Err(self.variadic_typo());
+                    }
+                } else if self.next().kind != TokenType::Separator(")".to_string()) {
+                    return Err(self.fail(
+                        format!("Expected , or ) after argument, got {}", self.next()),
+                        &self.next().span,
+                    ));
+                }
             }
-            self.expect_separator(")");
-            self.expect_separator(":");
-            let type_hint = self.parse_type();
+            self.expect_separator(")")?;
+            self.expect_separator(":")?;
+            let type_hint = self.parse_type()?;
             self.advance();
-            self.expect_separator(";");
-            let end = self.tokens.get(self.current - 1).cloned().unwrap().span.end;
-            return Spanned {
-		node: Node::ExTernStmt {
+            self.expect_separator(";")?;
+            let end = self.before().span.end;
+            return Ok(Spanned {
+                node: Node::ExTernStmt {
                     name,
                     args,
-                    return_type: type_hint.unwrap(),
+                    return_type: type_hint,
                     vardaic,
-		},
-		span: Span { start, end },
-            };
-	}
-	let v = self.before().span.clone();
-	self.error("Expected on of ('fn'|'struct')".to_string(), &v);
-	exit(1);
+                    doc,
+                },
+                span: Span {
+                    start,
+                    end,
+                    file: self.file,
+                },
+            });
+        }
+        let v = self.before().span.clone();
+        Err(self.fail("Expected on of ('fn'|'struct')".to_string(), &v))
     }
 
-    fn parse_reval(&mut self) -> Spanned<Node> {
-        let start = self.peek().unwrap().clone().span.start;
-        let name = self.parse_expr();
-        self.expect_operator("=");
-        let value = self.parse_expr();
-        self.expect_separator(";");
-        let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-        Spanned {
-            node: Node::ReVal {
-                name: Box::new(name),
-                value: Box::new(value),
-            },
-            span: Span { start, end },
-        }
+    fn parse_reval(&mut self) -> PResult<Spanned<Node>> {
+        let reval = self.parse_expr()?;
+        self.expect_separator(";")?;
+        Ok(reval)
     }
 
     fn is_expr_start(&self, arg: TokenType) -> bool {
-	match arg.clone() {
-            TokenType::Number(_num) => true,
-            TokenType::Operator(sep) if "*" == sep || "&" == sep => true,
-            TokenType::Ident(val) => true,
+        match arg {
+            TokenType::Number(..) | TokenType::Float(..) => true,
+            TokenType::Keyword(k) if k == "true" || k == "false" || k == "null" => true,
+            TokenType::Keyword(k) if INTRINSICS.contains(&k.as_str()) => true,
+            TokenType::Keyword(k) if k == "match" || k == "sizeof" => true,
+            TokenType::StrLit(_) | TokenType::Char(_) => true,
+            TokenType::Separator(sep) if sep == "(" => true,
+            TokenType::Operator(op) if ["-", "!", "~", "*", "&"].contains(&op.as_str()) => true,
+            TokenType::Ident(_val) => true,
             _ => false,
         }
     }
+    /// Builds an error for the caller to propagate with `?`.
+    fn fail(&self, err_msg: String, spanned: &Span) -> CompileError {
+        CompileError::new(err_msg, None, spanned.clone(), ErrLevel::ERROR)
+    }
+    /// `..` where the arguments of a variadic function end.
+    fn variadic_typo(&self) -> CompileError {
+        CompileError::new(
+            "Unexpected '..'".into(),
+            Some("variadic parameters are written as '...'".into()),
+            self.next().span,
+            ErrLevel::ERROR,
+        )
+    }
+    /// Records an error that does not stop the current statement from parsing.
     fn error(&mut self, err_msg: String, spanned: &Span) {
-        self.has_error = true;
-        let span = spanned.clone();
-        let source = self.source_code.clone();
-        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
-        let line_end = source[span.start..]
-            .find('\n')
-            .map(|i| span.start + i)
-            .unwrap_or(source.len());
-        let line = &source[line_start..line_end];
-        let col_start = span.start - line_start;
-        let col_end = span.end - line_start;
-        let line_number = source[..span.start].chars().filter(|&c| c == '\n').count() + 1;
-        eprintln!("{}", format!("[Tixie Error]: {err_msg}").red().bold());
-        eprintln!(" ---> line : {line_number}");
-        eprintln!("   |\n{: >2} | {}", line_number, line);
-        let mut caret_line = String::new();
-        for (i, c) in line.chars().enumerate() {
-            if i >= col_start && i < col_end {
-                caret_line.push('^');
-            } else if i < col_start {
-                caret_line.push(if c == '\t' { '\t' } else { ' ' });
-            }
+        let err = self.fail(err_msg, spanned);
+        self.report(err);
+    }
+    fn report(&mut self, err: CompileError) {
+        if err.is_error() {
+            self.has_error = true;
         }
-        eprintln!("   | {}", format!("{caret_line}").as_str().yellow().bold());
+        self.diagnostics.push(err);
     }
 
     fn warn<T: std::fmt::Debug>(&mut self, err_msg: String, spanned: &Spanned<T>) {
-        let span = spanned.span.clone();
-        let source = self.source_code.clone();
-        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
-        let line_end = source[span.start..]
-            .find('\n')
-            .map(|i| span.start + i)
-            .unwrap_or(source.len());
-        let line = &source[line_start..line_end];
-        let col_start = span.start - line_start;
-        let col_end = span.end - line_start;
-        let line_number = source[..span.start].chars().filter(|&c| c == '\n').count() + 1;
-        eprintln!("{}", format!("[Tixie Warning]: {err_msg}").yellow().bold());
-        eprintln!(" ---> line : {line_number}");
-        eprintln!("   |\n{: >2} | {}", line_number, line);
-        let mut caret_line = String::new();
-        for (i, c) in line.chars().enumerate() {
-            if i >= col_start && i < col_end {
-                caret_line.push('^');
-            } else if i < col_start {
-                caret_line.push(if c == '\t' { '\t' } else { ' ' });
-            }
-        }
-        eprintln!("   | {}", format!("{caret_line}").as_str().yellow().bold());
+        self.report(CompileError::new(
+            err_msg,
+            None,
+            spanned.span.clone(),
+            ErrLevel::WARNING,
+        ));
     }
 
-    fn parse_struct(&mut self) -> Spanned<Node> {
-        let start = self.peek().unwrap().clone().span.start;
+    fn parse_struct(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        let doc = self.take_doc();
         self.advance(); // skip 'struct'
-        let name = self.expect_identifier().unwrap().clone();
+        let name = self.expect_identifier()?;
+        let (generics, bounds) = self.parse_type_params()?;
 
-        self.expect_separator("{");
+        self.expect_separator("{")?;
         let mut fields = Vec::new();
         let mut meths = Vec::new();
         let mut static_methods = vec![];
-        while self.peek().unwrap().clone().kind != TokenType::Separator('}'.to_string()) {
-            if self.next().kind == TokenType::Keyword("fn".to_owned()) {
-                let method = self.parse_func();
-                meths.push(method);
-                continue;
-            } else if self.next().kind == TokenType::Keyword("st".to_string()) {
+        while self.next().kind != TokenType::Separator('}'.to_string()) {
+            if self.next().kind == TokenType::EOF {
+                return Err(self.fail(format!("Unterminated struct '{name}'"), &self.next().span));
+            }
+            let before = self.current;
+            if let Err(e) = self.parse_struct_member(&mut fields, &mut meths, &mut static_methods) {
+                self.recover(e, before, true);
+            }
+        }
+        self.expect_separator("}")?;
+        let node: Node = if generics.is_empty() {
+            Node::StructStmt {
+                name,
+                fields,
+                meths,
+                statics: Some(static_methods),
+                doc,
+            }
+        } else {
+            Node::GenericStructStmt {
+                name,
+                generics,
+                bounds,
+                fields,
+                meths,
+                statics: Some(static_methods),
+                doc,
+            }
+        };
+        let end = self.before().span.end;
+        Ok(Spanned {
+            node,
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
+    }
+
+    fn parse_struct_member(
+        &mut self,
+        fields: &mut Vec<Spanned<Node>>,
+        meths: &mut Vec<Result<Spanned<Node>, String>>,
+        static_methods: &mut Vec<Spanned<Node>>,
+    ) -> PResult<()> {
+        if self.next().kind == TokenType::Keyword("fn".to_owned()) {
+            let method = self.parse_func()?;
+            meths.push(Ok(method));
+            return Ok(());
+        } else if self.next().kind == TokenType::Keyword("st".to_string()) {
+            /* the doc comment sits on 'st', hand it on to the 'fn' */
+            if let Some(doc) = self.take_doc() {
+                self.docs.insert(self.current + 1, doc);
+            }
+            self.advance();
+            if self.next().kind == TokenType::Keyword("fn".to_string()) {
+                static_methods.push(self.parse_func()?);
+            }
+            return Ok(());
+        }
+        let fe_start = self.next().span.start;
+        let doc = self.take_doc();
+        let feild_name = self.expect_identifier()?;
+        self.expect_separator(":")?;
+        let feild_type = self.parse_type()?;
+        self.advance();
+        if self.next().kind == TokenType::Separator(','.to_string()) {
+            self.expect_separator(",")?;
+        } else if matches!(self.next().kind, TokenType::Separator(v) if v == "}") {
+            self.error("Missing comma?".to_string(), &self.next().span);
+        } else {
+            return Err(self.fail(
+                format!("Expected , after field '{feild_name}', got {}", self.next()),
+                &self.next().span,
+            ));
+        }
+        let fe_end = self.before().span.end;
+        fields.push(Spanned {
+            node: Node::Feilds {
+                name: feild_name,
+                type_hint: feild_type,
+                doc,
+            },
+            span: Span {
+                start: fe_start,
+                end: fe_end,
+                file: self.file,
+            },
+        });
+        Ok(())
+    }
+
+    fn parse_enum(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        let doc = self.take_doc();
+        self.advance(); // skip 'enum'
+        let name = self.expect_identifier()?;
+        let (generics, bounds) = self.parse_type_params()?;
+        self.expect_separator("{")?;
+        let mut variants = Vec::new();
+        let mut meths = Vec::new();
+        while self.next().kind != TokenType::Separator("}".to_owned()) {
+            if self.next().kind == TokenType::EOF {
+                return Err(self.fail(format!("Unterminated enum '{name}'"), &self.next().span));
+            }
+            let before = self.current;
+            let member = if self.next().kind == TokenType::Keyword("fn".to_owned()) {
+                self.parse_func().map(|m| meths.push(m))
+            } else {
+                self.parse_variant().map(|v| variants.push(v))
+            };
+            if let Err(e) = member {
+                self.recover(e, before, true);
+            }
+        }
+        self.expect_separator("}")?;
+        let node = if generics.is_empty() {
+            Node::EnumStmt {
+                name,
+                variants,
+                meths,
+                doc,
+            }
+        } else {
+            Node::GenericEnumStmt {
+                name,
+                generics,
+                bounds,
+                variants,
+                meths,
+                doc,
+            }
+        };
+        Ok(Spanned {
+            node,
+            span: self.span_from(start),
+        })
+    }
+    /// `Name`, `Name(T, U)`, either optionally followed by `= value`, and the `,` after it,
+    /// which the last variant may leave out.
+    fn parse_variant(&mut self) -> PResult<Variant> {
+        let start = self.next().span.start;
+        let doc = self.take_doc();
+        let name = self.expect_identifier()?;
+        let mut payload = Vec::new();
+        if self.next().kind == TokenType::Separator("(".to_owned()) {
+            self.advance();
+            while self.next().kind != TokenType::Separator(")".to_owned()) {
+                payload.push(self.parse_type()?);
                 self.advance();
-                if self.next().kind == TokenType::Keyword("fn".to_string()) {
-                    static_methods.push(self.parse_func().unwrap());
+                if self.next().kind == TokenType::Separator(",".to_owned()) {
+                    self.advance();
+                } else if self.next().kind != TokenType::Separator(")".to_owned()) {
+                    return Err(self.fail(
+                        format!("Expected , or ) after type, got {}", self.next()),
+                        &self.next().span,
+                    ));
                 }
-                continue;
-            }
-            let fe_start = self.peek().unwrap().clone().span.start;
-            let feild_name = self.expect_identifier().unwrap();
-            self.expect_separator(":");
-            let fstart = self.peek().unwrap().clone().span.start;
-            let feild_type = self.parse_type();
-            let fend = self.tokens.get(self.current - 1).unwrap().clone().span.end;
+            }
+            self.expect_separator(")")?;
+        }
+        let mut value = None;
+        if self.next().kind == TokenType::Operator("=".to_owned()) {
+            self.advance();
+            let negative = self.next().kind == TokenType::Operator("-".to_owned());
+            if negative {
+                self.advance();
+            }
+            let TokenType::Number(digits, None) = self.next().kind else {
+                return Err(self.fail(
+                    format!("Expected an integer discriminant, got {}", self.next()),
+                    &self.next().span,
+                ));
+            };
+            let digits = if negative {
+                format!("-{digits}")
+            } else {
+                digits
+            };
+            let Ok(v) = digits.parse::<i64>() else {
+                return Err(self.fail(
+                    format!("Discriminant {digits} does not fit in i64"),
+                    &self.next().span,
+                ));
+            };
+            value = Some(v);
             self.advance();
-            if self.peek().unwrap().clone().kind == TokenType::Separator(','.to_string()) {
-                self.expect_separator(",");
-            } else if matches!(self.peek().unwrap().clone().kind, TokenType::Separator(v) if v == "}")
-            {
-                self.error(
-                    format!("Missing comma?"),
-                    &self.peek().unwrap().clone().span,
-                );
-            }
-            let fe_end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-            fields.push(Spanned {
-                node: Node::Feilds {
-                    name: feild_name,
-                    type_hint: feild_type.unwrap(),
-                },
-                span: Span {
-                    start: fe_start,
-                    end: fe_end,
-                },
-            });
         }
-        self.expect_separator("}");
-        let node: Node = Node::StructStmt {
+        let span = self.span_from(start);
+        match self.next().kind {
+            TokenType::Separator(s) if s == "," => {
+                self.advance();
+            }
+            TokenType::Separator(s) if s == "}" => {}
+            _ => {
+                return Err(self.fail(
+                    format!("Expected , after variant '{name}', got {}", self.next()),
+                    &self.next().span,
+                ));
+            }
+        }
+        Ok(Variant {
             name,
-            fields,
-            meths,
-            statics: Some(static_methods),
-        };
-        let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-        return Spanned {
-            node,
-            span: Span { start, end },
-        };
+            payload,
+            value,
+            doc,
+            span,
+        })
+    }
+
+    fn parse_trait(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        let doc = self.take_doc();
+        self.advance(); // skip 'trait'
+        let name = self.expect_identifier()?;
+        self.expect_separator("{")?;
+        let mut meths = Vec::new();
+        while self.next().kind != TokenType::Separator("}".to_owned()) {
+            if self.next().kind == TokenType::EOF {
+                return Err(self.fail(format!("Unterminated trait '{name}'"), &self.next().span));
+            }
+            let before = self.current;
+            match self.parse_trait_method() {
+                Ok(meth) => meths.push(meth),
+                Err(e) => self.recover(e, before, false),
+            }
+        }
+        self.expect_separator("}")?;
+        Ok(Spanned {
+            node: Node::TraitStmt { name, meths, doc },
+            span: self.span_from(start),
+        })
+    }
+    /// `fn name(args): ret;`, or a whole function when it has a default body.
+    fn parse_trait_method(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        let first = self.current;
+        self.expect_keyword("fn")?;
+        let name = self.expect_identifier()?;
+        let (args, _, ret_type) = self.parse_signature()?;
+        if self.next().kind == TokenType::Separator("{".to_owned()) {
+            self.current = first;
+            return self.parse_func();
+        }
+        self.expect_separator(";")?;
+        Ok(Spanned {
+            node: Node::FnSig {
+                name,
+                args,
+                ret_type,
+                doc: self.docs.remove(&first),
+            },
+            span: self.span_from(start),
+        })
+    }
+    fn parse_impl(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        self.advance(); // skip 'impl'
+        let trait_name = self.parse_type()?;
+        self.advance();
+        self.expect_keyword("for")?;
+        let target = self.parse_type()?;
+        self.advance();
+        self.expect_separator("{")?;
+        let mut meths = Vec::new();
+        while self.next().kind != TokenType::Separator("}".to_owned()) {
+            if self.next().kind == TokenType::EOF {
+                return Err(self.fail("Unterminated impl".to_string(), &self.next().span));
+            }
+            let before = self.current;
+            let meth = match self.next().kind {
+                TokenType::Keyword(k) if k == "fn" => self.parse_func(),
+                _ => Err(self.fail(
+                    format!("Expected a method, got {}", self.next()),
+                    &self.next().span,
+                )),
+            };
+            match meth {
+                Ok(meth) => meths.push(meth),
+                Err(e) => self.recover(e, before, false),
+            }
+        }
+        self.expect_separator("}")?;
+        Ok(Spanned {
+            node: Node::ImplStmt {
+                trait_name,
+                target,
+                meths,
+            },
+            span: self.span_from(start),
+        })
     }
 
-    fn parse_args(&mut self) -> Vec<Spanned<Node>> {
+    fn parse_args(&mut self) -> PResult<Vec<Spanned<Node>>> {
         let mut args = Vec::new();
-        let start = self.peek().cloned().unwrap().span.start;
         if self.next().kind == TokenType::Separator(')'.to_string()) {
-            return args;
+            return Ok(args);
         }
         loop {
-            let arg = self.parse_logic_or();
-            if self.peek().cloned().unwrap().kind == TokenType::Separator(",".to_string()) {
-                self.expect_separator(",");
-            } else if self.peek().cloned().unwrap().kind == TokenType::Separator(')'.to_string()) {
-                args.push(arg);
+            let arg = self.parse_expr()?;
+            args.push(arg);
+            if self.next().kind == TokenType::Separator(",".to_string()) {
+                self.expect_separator(",")?;
+            } else if self.next().kind == TokenType::Separator(')'.to_string()) {
                 break;
+            } else {
+                return Err(self.fail(
+                    format!("Expected , or ) in argument list, got {}", self.next()),
+                    &self.next().span,
+                ));
             }
-            args.push(arg);
         }
-        let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-        args
+        Ok(args)
     }
-    fn before(&mut self) -> Token {
-        return self.tokens.clone().get(self.current - 1).cloned().unwrap();
+    fn before(&self) -> Token {
+        self.tokens[self.current.saturating_sub(1)].clone()
     }
-    fn next(&mut self) -> Token {
-        return self.peek().cloned().unwrap();
+    fn next(&self) -> Token {
+        self.peek().cloned().unwrap()
     }
 
-    fn parse_bundle(&mut self) -> Spanned<Node> {
+    fn parse_bundle(&mut self) -> PResult<Spanned<Node>> {
         let start = self.next().span.start;
         self.advance(); // skip 'bundle'
-        let mut bpath = String::new();
-        let mut balais = String::new();
-        if let TokenType::StrLit(path) = self.peek().cloned().unwrap().kind {
-            bpath = path;
+        if let TokenType::StrLit(path) = self.next().kind {
             self.advance();
-            self.expect_keyword("as").unwrap();
-            balais = self.expect_identifier().unwrap();
+            self.expect_keyword("as")?;
+            let balais = self.expect_identifier()?;
             let end = self.before().span.end;
-            self.expect_separator(";");
-            return Spanned {
+            self.expect_separator(";")?;
+            return Ok(Spanned {
                 node: Node::BundleStmt {
-                    path: bpath,
+                    path,
                     alias: balais,
                 },
-                span: Span { start, end },
-            };
+                span: Span {
+                    start,
+                    end,
+                    file: self.file,
+                },
+            });
         }
-        balais = self.expect_identifier().unwrap();
-        self.expect_separator("{");
-        let body: Spanned<Node> = self.parse_bundle_body();
-        self.expect_separator("}");
+        let balais = self.expect_identifier()?;
+        self.expect_separator("{")?;
+        let body: Spanned<Node> = self.parse_bundle_body()?;
+        self.expect_separator("}")?;
         let end = self.before().span.end;
-        Spanned {
+        Ok(Spanned {
             node: Node::NameSpace {
                 alias: balais,
                 body: Box::new(body),
             },
-            span: Span { start, end },
-        }
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
     }
 
     fn get(&self, arg: i32) -> Option<Token> {
-        if (self.current + arg as usize) < self.tokens.len() {
-            Some(
-                self.tokens
-                    .get(self.current + arg as usize)
-                    .unwrap()
-                    .clone(),
-            )
-        } else {
-            None
-        }
+        self.tokens.get(self.current + arg as usize).cloned()
     }
 
     fn is_type_ahead(&self) -> bool {
         let mut state = self.clone();
-        if matches!(state.parse_type(), None) {
-            return false;
-        }
-        true
+        state.parse_type().is_ok()
     }
 
-    fn parse_bundle_body(&mut self) -> Spanned<Node> {
+    fn parse_bundle_body(&mut self) -> PResult<Spanned<Node>> {
         let start = self.next().span.start;
         let mut stmts = Vec::new();
         loop {
-            match self.next().kind {
+            let before = self.current;
+            let stmt = match self.next().kind {
                 TokenType::Comment(_) => {
                     self.advance();
-                }
-                TokenType::Keyword(k) if k == "let" => {
-                    let m = self.parse_let_stmt();
-                    stmts.push(m);
-                    continue;
-                }
-                TokenType::Keyword(k) if k == "fn" => {
-                    let f = self.parse_func();
-                    stmts.push(f.unwrap());
                     continue;
                 }
-                TokenType::Ident(val)
-                    if self.tokens.get(self.current + 1).unwrap().clone().kind
-                        == TokenType::Separator('('.to_string()) =>
+                TokenType::Keyword(k) if k == "let" => self.parse_let_stmt(),
+                TokenType::Keyword(k) if k == "fn" => self.parse_func(),
+                TokenType::Ident(_val)
+                    if self.get(1).map(|t| t.kind)
+                        == Some(TokenType::Separator('('.to_string())) =>
                 {
-                    println!(
-                        "Todo Err system: Cannot have a function call in a none executable scope"
-                    );
-                    exit(100);
-                }
-                TokenType::Keyword(val) if val == "struct" => {
-                    let struct_stmt = self.parse_struct();
-                    stmts.push(struct_stmt);
-                    continue;
-                }
-                TokenType::Ident(val)
-                    if self.tokens.get(self.current + 1).unwrap().clone().kind
-                        == TokenType::Operator('='.to_string()) =>
+                    Err(self.fail(
+                        "Cannot have a function call in a non executable scope".to_string(),
+                        &self.next().span,
+                    ))
+                }
+                TokenType::Keyword(val) if val == "struct" => self.parse_struct(),
+                TokenType::Keyword(val) if val == "trait" => self.parse_trait(),
+                TokenType::Keyword(val) if val == "enum" => self.parse_enum(),
+                TokenType::Keyword(val) if val == "impl" => self.parse_impl(),
+                TokenType::Ident(_val)
+                    if self.get(1).map(|t| t.kind)
+                        == Some(TokenType::Operator('='.to_string())) =>
                 {
-                    self.todo_err("Cannot have a re-assignment in a non exectable scope");
+                    Err(self.fail(
+                        "Cannot have a re-assignment in a non exectable scope".to_string(),
+                        &self.next().span,
+                    ))
                 }
                 TokenType::EOF => {
-                    self.error(
-                        format!("Not a valid statment"),
-                        &self.peek().unwrap().clone().span,
-                    );
-                    exit(100)
+                    return Err(self.fail("Not a valid statment".to_string(), &self.next().span));
                 }
                 _ => {
                     break;
                 }
+            };
+            match stmt {
+                Ok(s) => stmts.push(s),
+                Err(e) => self.recover(e, before, false),
             }
         }
-        let end = self.tokens.get(self.current - 1).unwrap().clone().span.end;
-        Spanned {
+        let end = self.before().span.end;
+        Ok(Spanned {
             node: Node::Program(stmts),
-            span: Span { start, end },
-        }
-    }
-    fn todo_err(&self, arg: &str) {
-        println!("Todo Err system: {arg}");
-        exit(100);
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
     }
 
-    fn parse_ifstmt(&mut self) -> Spanned<Node> {
+    fn parse_ifstmt(&mut self) -> PResult<Spanned<Node>> {
         let start = self.next().span.start;
         self.advance(); // skip if keyword
-        let cond = self.parse_logic_or();
-        self.expect_separator("{");
-        let body = self.parse_body(true, false);
-        self.expect_separator("}");
+        let cond = self.parse_expr()?;
+        self.expect_separator("{")?;
+        let body = self.parse_body(true, false)?;
+        self.expect_separator("}")?;
         let mut branches = vec![];
-        if self.next().kind == TokenType::Keyword("else".to_owned()) {
-            while self.next().kind == TokenType::Keyword("else".to_owned())
-                && self.get(1).unwrap().kind == TokenType::Keyword("if".to_owned())
-            {
-                let _ = self.expect_keyword("else");
-                branches.push(self.parse_ifstmt());
-            }
+        while self.next().kind == TokenType::Keyword("else".to_owned())
+            && self.get(1).map(|t| t.kind) == Some(TokenType::Keyword("if".to_owned()))
+        {
+            self.expect_keyword("else")?;
+            branches.push(self.parse_ifstmt()?);
         }
         let mut else_body = None;
         if self.next().kind == TokenType::Keyword("else".to_owned()) {
             self.advance();
-            self.expect_separator("{");
-            else_body = Some(Box::new(self.parse_body(false, false)));
-            self.expect_separator("}");
+            self.expect_separator("{")?;
+            else_body = Some(Box::new(self.parse_body(false, false)?));
+            self.expect_separator("}")?;
         }
         let end = self.before().span.end;
-        Spanned {
+        Ok(Spanned {
             node: Node::IfStmt {
                 cond: Box::new(cond),
                 body: Box::new(body),
                 elseifs: Some(branches),
                 elsestmt: else_body,
             },
-            span: Span { start, end },
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
+    }
+
+    /// `match value { pattern => arm, ... }`. An arm is an expression followed by `,`,
+    /// which the last may leave out, or a `{ ... }` block.
+    fn parse_match(&mut self) -> PResult<Spanned<Node>> {
+        let start = self.next().span.start;
+        self.advance(); // skip 'match'
+        let scrutinee = self.parse_expr()?;
+        self.expect_separator("{")?;
+        let mut arms = Vec::new();
+        while self.next().kind != TokenType::Separator("}".to_owned()) {
+            if self.next().kind == TokenType::EOF {
+                return Err(self.fail("Unterminated match".to_string(), &self.next().span));
+            }
+            let before = self.current;
+            match self.parse_arm() {
+                Ok(arm) => arms.push(arm),
+                Err(e) => self.recover(e, before, true),
+            }
+        }
+        self.expect_separator("}")?;
+        Ok(Spanned {
+            node: Node::MatchExpr {
+                scrutinee: Box::new(scrutinee),
+                arms,
+            },
+            span: self.span_from(start),
+        })
+    }
+    /// A `match` on its own, where the `;` after it may be left out.
+    fn parse_match_stmt(&mut self) -> PResult<Spanned<Node>> {
+        let stmt = self.parse_match()?;
+        if self.next().kind == TokenType::Separator(";".to_owned()) {
+            self.advance();
         }
+        Ok(stmt)
+    }
+    fn parse_arm(&mut self) -> PResult<MatchArm> {
+        let pattern = self.parse_pattern()?;
+        let mut guard = None;
+        if self.next().kind == TokenType::Keyword("if".to_owned()) {
+            self.advance();
+            guard = Some(self.parse_expr()?);
+        }
+        self.expect_operator("=>")?;
+        if self.next().kind == TokenType::Separator("{".to_owned()) {
+            self.advance();
+            let body = self.parse_body(false, false)?;
+            self.expect_separator("}")?;
+            if self.next().kind == TokenType::Separator(",".to_owned()) {
+                self.advance();
+            }
+            return Ok(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+        }
+        let body = self.parse_expr()?;
+        match self.next().kind {
+            TokenType::Separator(s) if s == "," => {
+                self.advance();
+            }
+            TokenType::Separator(s) if s == "}" => {}
+            _ => {
+                return Err(self.fail(
+                    format!("Expected , or }} after match arm, got {}", self.next()),
+                    &self.next().span,
+                ));
+            }
+        }
+        Ok(MatchArm {
+            pattern,
+            guard,
+            body,
+        })
+    }
+    /// `_`, a name, a literal, a range `lo..hi` or `lo..=hi`, or a variant `Enum::Name`
+    /// or `Enum::Name(a, _)`, whose path may start with a bundle alias.
+    fn parse_pattern(&mut self) -> PResult<Spanned<Pattern>> {
+        let start = self.next().span.start;
+        let pattern = match self.next().kind {
+            TokenType::Ident(name) if self.get(1).map(|t| t.kind) == Some(TokenType::DCOLON) => {
+                self.advance();
+                let mut path = vec![name];
+                while self.next().kind == TokenType::DCOLON {
+                    self.advance();
+                    path.push(self.expect_identifier()?);
+                }
+                let mut bindings = Vec::new();
+                if self.next().kind == TokenType::Separator("(".to_owned()) {
+                    self.advance();
+                    while self.next().kind != TokenType::Separator(")".to_owned()) {
+                        bindings.push(self.expect_identifier()?);
+                        if self.next().kind == TokenType::Separator(",".to_owned()) {
+                            self.advance();
+                        } else if self.next().kind != TokenType::Separator(")".to_owned()) {
+                            return Err(self.fail(
+                                format!("Expected , or ) after binding, got {}", self.next()),
+                                &self.next().span,
+                            ));
+                        }
+                    }
+                    self.expect_separator(")")?;
+                }
+                Pattern::Variant { path, bindings }
+            }
+            TokenType::Ident(name) => {
+                self.advance();
+                if name == "_" {
+                    Pattern::Wildcard
+                } else {
+                    Pattern::Binding(name)
+                }
+            }
+            _ => {
+                let lo = self.parse_pattern_literal()?;
+                let inclusive = match self.next().kind {
+                    TokenType::DOTDOT => false,
+                    TokenType::DOTDOTEQ => true,
+                    _ => {
+                        return Ok(Spanned {
+                            node: Pattern::Literal(Box::new(lo)),
+                            span: self.span_from(start),
+                        });
+                    }
+                };
+                self.advance();
+                let hi = self.parse_pattern_literal()?;
+                Pattern::Range {
+                    lo: Box::new(lo),
+                    hi: Box::new(hi),
+                    inclusive,
+                }
+            }
+        };
+        Ok(Spanned {
+            node: pattern,
+            span: self.span_from(start),
+        })
+    }
+    /// A literal in a pattern: a number, which may be negative, a char, a string or a bool.
+    fn parse_pattern_literal(&mut self) -> PResult<Node> {
+        let negative = self.next().kind == TokenType::Operator("-".to_owned());
+        if negative {
+            self.advance();
+        }
+        let node = match self.next().kind {
+            TokenType::Number(digits, suffix) => {
+                let digits = if negative {
+                    format!("-{digits}")
+                } else {
+                    digits
+                };
+                Node::LiteralInt(digits, suffix.map(|s| int_suffix(&s)))
+            }
+            _ if negative => {
+                return Err(self.fail(
+                    format!("Expected a number after '-', got {}", self.next()),
+                    &self.next().span,
+                ));
+            }
+            TokenType::Char(c) => Node::LiteralCh(c),
+            TokenType::StrLit(s) => Node::LiteralStr(s),
+            TokenType::Keyword(k) if k == "true" || k == "false" => Node::LiteralBool(k == "true"),
+            _ => {
+                return Err(self.fail(
+                    format!("Expected a pattern, got {}", self.next()),
+                    &self.next().span,
+                ));
+            }
+        };
+        self.advance();
+        Ok(node)
     }
 
-    fn parse_forstmt(&mut self) -> Spanned<Node> {
-        self.is_inloop = true;
+    fn parse_forstmt(&mut self) -> PResult<Spanned<Node>> {
         let start = self.next().span.start;
         self.advance();
-        self.expect_separator("(");
-        let init = self.parse_expr();
-        self.expect_separator(";");
-        let cond = self.parse_logic_or();
-        self.expect_separator(";");
-        let inc = self.parse_logic_or();
-        self.expect_separator(")");
-        self.expect_separator("{");
+        self.expect_separator("(")?;
+        let init = self.parse_expr()?;
+        self.expect_separator(";")?;
+        let cond = self.parse_expr()?;
+        self.expect_separator(";")?;
+        let inc = self.parse_expr()?;
+        self.expect_separator(")")?;
+        self.expect_separator("{")?;
+        let was_inloop = self.is_inloop;
+        self.is_inloop = true;
         let body = self.parse_body(true, true);
-        self.expect_separator("}");
+        self.is_inloop = was_inloop;
+        let body = body?;
+        self.expect_separator("}")?;
         let end = self.before().span.end;
-        self.is_inloop = false;
-        Spanned {
+        Ok(Spanned {
             node: Node::ForStmt {
                 init: Box::new(init),
                 cond: Box::new(cond),
                 inc: Box::new(inc),
                 body: Box::new(body),
             },
-            span: Span { start, end },
-        }
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
     }
 
-    fn parse_unpack(&mut self) -> Spanned<Node> {
+    fn parse_unpack(&mut self) -> PResult<Spanned<Node>> {
         let start = self.next().span.start;
         self.advance();
         let mut symbols = vec![];
-        let alias = self.expect_identifier().unwrap();
-        self.expect_separator("{");
+        let alias = self.expect_identifier()?;
+        self.expect_separator("{")?;
         while self.next().kind != TokenType::Separator("}".to_owned()) {
-            symbols.push(self.expect_identifier().unwrap());
+            symbols.push(self.expect_identifier()?);
             if self.next().kind == TokenType::Separator(",".to_owned()) {
-                self.expect_separator(",");
+                self.expect_separator(",")?;
             }
         }
-        self.expect_separator("}");
-        self.expect_separator(";");
+        self.expect_separator("}")?;
+        self.expect_separator(";")?;
         let end = self.before().span.end;
-        Spanned {
+        Ok(Spanned {
             node: Node::UnpackStmt { alias, symbols },
-            span: Span { start, end },
-        }
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
     }
 
-    fn parse_while(&mut self) -> Spanned<Node> {
+    fn parse_while(&mut self) -> PResult<Spanned<Node>> {
         let start = self.next().span.start;
         self.advance();
-        let cond = self.parse_logic_or();
-        self.expect_separator("{");
+        let cond = self.parse_expr()?;
+        self.expect_separator("{")?;
+        let was_inloop = self.is_inloop;
         self.is_inloop = true;
         let body = self.parse_body(false, true);
-        self.is_inloop = false;
-        self.expect_separator("}");
+        self.is_inloop = was_inloop;
+        let body = body?;
+        self.expect_separator("}")?;
         let end = self.next().span.end;
-        Spanned {
+        Ok(Spanned {
             node: Node::WhileStmt {
                 cond: Box::new(cond),
                 body: Box::new(body),
             },
-            span: Span { start, end },
-        }
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
     }
 }
@@ -0,0 +1,189 @@
+//! Coverage of `match` arms: which values of the matched type the arms seen so far take,
+//! to tell an arm no value can reach and a match some values get through.
+//!
+//! Integers, chars and bools are ranges of numbers, enums a set of variants. Strings are
+//! never covered by literals alone, only by `_` or a binding.
+use super::enums::Enum;
+use super::ttype::Type;
+
+/// What the numbers of an integer space stand for, to show a missing one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Int,
+    Char,
+    Bool,
+}
+
+/// The values a single pattern takes.
+#[derive(Debug, Clone)]
+pub enum Part {
+    /// Every value, taken by `_` or a binding.
+    All,
+    /// The numbers `lo..=hi`. A literal is a range of one.
+    Range(i128, i128),
+    /// The variant at this index.
+    Variant(usize),
+    Str(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Space {
+    /// Numbers from `min` to `max`. `covered` is sorted, its ranges do not touch.
+    Ints {
+        min: i128,
+        max: i128,
+        kind: Kind,
+        covered: Vec<(i128, i128)>,
+    },
+    /// Variants by their name as written, `Shape::Circle`, and whether each is taken.
+    Variants {
+        names: Vec<String>,
+        covered: Vec<bool>,
+    },
+    Strs {
+        covered: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    pub space: Space,
+    /// A `_` or a binding took everything.
+    all: bool,
+}
+
+impl Coverage {
+    /// Nothing taken yet of the values of `ty`, None if it is not an integer, char, bool
+    /// or string type. Enums are made with [`Coverage::variants`].
+    pub fn of(ty: &Type) -> Option<Coverage> {
+        let (min, max, kind) = match ty {
+            Type::MUT(inner) => return Coverage::of(inner),
+            Type::STR => return Some(Coverage::new(Space::Strs { covered: vec![] })),
+            Type::I8 => (i8::MIN as i128, i8::MAX as i128, Kind::Int),
+            Type::I16 => (i16::MIN as i128, i16::MAX as i128, Kind::Int),
+            Type::INT | Type::I32 => (i32::MIN as i128, i32::MAX as i128, Kind::Int),
+            Type::I64 => (i64::MIN as i128, i64::MAX as i128, Kind::Int),
+            Type::U8 => (0, u8::MAX as i128, Kind::Int),
+            Type::U16 => (0, u16::MAX as i128, Kind::Int),
+            Type::U32 => (0, u32::MAX as i128, Kind::Int),
+            Type::U64 => (0, u64::MAX as i128, Kind::Int),
+            Type::CHAR => (0, u8::MAX as i128, Kind::Char),
+            Type::BOOL => (0, 1, Kind::Bool),
+            _ => return None,
+        };
+        Some(Coverage::new(Space::Ints {
+            min,
+            max,
+            kind,
+            covered: vec![],
+        }))
+    }
+    /// Nothing taken yet of the variants of `en`, which is shown as `prefix`.
+    pub fn variants(prefix: &str, en: &Enum) -> Coverage {
+        Coverage::new(Space::Variants {
+            names: en
+                .variants
+                .iter()
+                .map(|v| format!("{prefix}::{}", v.name))
+                .collect(),
+            covered: vec![false; en.variants.len()],
+        })
+    }
+    fn new(space: Space) -> Coverage {
+        Coverage { space, all: false }
+    }
+    /// Whether the values of `part` are all taken already.
+    pub fn covers(&self, part: &Part) -> bool {
+        if self.all {
+            return true;
+        }
+        match (&self.space, part) {
+            (_, Part::All) => self.missing().is_none(),
+            (Space::Ints { covered, .. }, Part::Range(lo, hi)) => {
+                covered.iter().any(|(a, b)| a <= lo && hi <= b)
+            }
+            (Space::Variants { covered, .. }, Part::Variant(i)) => covered[*i],
+            (Space::Strs { covered }, Part::Str(s)) => covered.contains(s),
+            _ => false,
+        }
+    }
+    pub fn add(&mut self, part: Part) {
+        match (&mut self.space, part) {
+            (_, Part::All) => self.all = true,
+            (Space::Ints { covered, .. }, Part::Range(lo, hi)) => {
+                covered.push((lo, hi));
+                covered.sort();
+                let mut merged: Vec<(i128, i128)> = Vec::new();
+                for (a, b) in covered.drain(..) {
+                    match merged.last_mut() {
+                        Some(last) if a <= last.1 + 1 => last.1 = last.1.max(b),
+                        _ => merged.push((a, b)),
+                    }
+                }
+                *covered = merged;
+            }
+            (Space::Variants { covered, .. }, Part::Variant(i)) => covered[i] = true,
+            (Space::Strs { covered }, Part::Str(s)) => covered.push(s),
+            _ => {}
+        }
+    }
+    /// What no arm takes, e.g. `'Shape::Empty' is not covered`. None once everything is.
+    pub fn missing(&self) -> Option<String> {
+        if self.all {
+            return None;
+        }
+        match &self.space {
+            Space::Ints {
+                min,
+                max,
+                kind,
+                covered,
+            } => {
+                let mut next = *min;
+                for (a, b) in covered {
+                    if *a > next {
+                        break;
+                    }
+                    next = next.max(b + 1);
+                }
+                if next > *max {
+                    return None;
+                }
+                let end = covered
+                    .iter()
+                    .find(|(a, _)| *a > next)
+                    .map_or(*max, |(a, _)| a - 1);
+                let gap = if end == next {
+                    show(next, *kind)
+                } else {
+                    format!("{}..={}", show(next, *kind), show(end, *kind))
+                };
+                Some(format!("{gap} is not covered"))
+            }
+            Space::Variants { names, covered } => {
+                let i = covered.iter().position(|c| !c)?;
+                Some(format!("'{}' is not covered", names[i]))
+            }
+            Space::Strs { .. } => Some("other strings are not covered".to_owned()),
+        }
+    }
+    /// The lowest and highest number and their kind, None for enums and strings.
+    pub fn bounds(&self) -> Option<(i128, i128, Kind)> {
+        match self.space {
+            Space::Ints { min, max, kind, .. } => Some((min, max, kind)),
+            _ => None,
+        }
+    }
+}
+
+/// A number of an integer space as it is written in Jaguar.
+pub fn show(value: i128, kind: Kind) -> String {
+    match kind {
+        Kind::Int => value.to_string(),
+        Kind::Bool => (value != 0).to_string(),
+        Kind::Char => match char::from_u32(value as u32) {
+            Some(c) => format!("{c:?}"),
+            None => value.to_string(),
+        },
+    }
+}
@@ -0,0 +1,107 @@
+//! Traits: named sets of methods that types promise to have.
+//!
+//! An `impl Trait for Type` block adds its methods to the type's [`StructLayout`] like
+//! any other method, and records the trait's [`Trait::id`] in the layout's `traits`.
+//! Calls stay static: `x.show()` on a `Point` is a call of `Point_show`, in generic
+//! functions too, since every instance is generated for concrete types.
+//!
+//! [`StructLayout`]: super::codegen::StructLayout
+use super::parser::{FunctionArg, Node, Spanned};
+use super::ttype::Type;
+
+#[derive(Debug, Clone)]
+pub struct Trait {
+    pub name: String,
+    /// File the trait is declared in. Tells apart traits of the same name from different bundles.
+    pub file: String,
+    /// `FnSig`s an impl must provide, and `FnStmt`s it may leave out to get the default.
+    pub meths: Vec<Spanned<Node>>,
+}
+
+impl Trait {
+    /// What the layouts of the types implementing this trait record.
+    pub fn id(&self) -> String {
+        format!("{}::{}", self.file, self.name)
+    }
+    /// Name, arguments and return type of the methods a `dyn` of this trait can call.
+    /// Those naming `Self` are left out, their signature depends on the type behind it.
+    pub fn dyn_methods(&self) -> Vec<(&String, &[FunctionArg], &Type)> {
+        self.meths
+            .iter()
+            .filter_map(|m| match &m.node {
+                Node::FnSig {
+                    name,
+                    args,
+                    ret_type,
+                    ..
+                }
+                | Node::FnStmt {
+                    name,
+                    args,
+                    ret_type,
+                    ..
+                } => Some((name, args.as_slice(), ret_type)),
+                _ => None,
+            })
+            .filter(|(_, args, ret)| {
+                !names_self(ret) && !args.iter().any(|a| names_self(&a.type_hint))
+            })
+            .collect()
+    }
+    /// Name, arguments and return type of the method `name`, if the trait has it.
+    pub fn method(&self, name: &str) -> Option<(&[FunctionArg], &Type)> {
+        self.meths.iter().find_map(|m| match &m.node {
+            Node::FnSig {
+                name: n,
+                args,
+                ret_type,
+                ..
+            }
+            | Node::FnStmt {
+                name: n,
+                args,
+                ret_type,
+                ..
+            } if n == name => Some((args.as_slice(), ret_type)),
+            _ => None,
+        })
+    }
+}
+
+fn names_self(ty: &Type) -> bool {
+    match ty {
+        Type::Custom(name) => name == "Self",
+        Type::PTR(inner) | Type::MUT(inner) | Type::List(inner, _) => names_self(inner),
+        Type::Generic { args, .. } => args.iter().any(names_self),
+        _ => false,
+    }
+}
+
+/// The vtable entry `name` for a method taking `args` and returning `ret_type`, e.g.
+/// `jaguar_str (*show)(void*)`. With an empty name it is the entry's type. `self` becomes
+/// a `void*`, the pointer a `dyn` holds.
+pub(crate) fn vtable_entry(name: &str, args: &[FunctionArg], ret_type: &Type) -> String {
+    let args: Vec<String> = args
+        .iter()
+        .map(|a| match a.name.as_str() {
+            "self" => "void*".to_string(),
+            _ => a.type_hint.to_str(),
+        })
+        .collect();
+    format!("{} (*{name})({})", ret_type.c_impl(), args.join(", "))
+}
+
+/// `fn name(self, x: int): str`, for diagnostics.
+pub(crate) fn signature(name: &str, args: &[FunctionArg], ret_type: &Type) -> String {
+    let args: Vec<String> = args
+        .iter()
+        .map(|a| match a.type_hint {
+            Type::NoType => a.name.clone(),
+            _ => format!("{}: {}", a.name, a.type_hint.debug()),
+        })
+        .collect();
+    match ret_type {
+        Type::NoType => format!("fn {name}({})", args.join(", ")),
+        _ => format!("fn {name}({}): {}", args.join(", "), ret_type.debug()),
+    }
+}
@@ -1,7 +1,12 @@
+use super::generics::instance_name;
+
 #[derive(Debug, Clone, PartialEq, Hash, std::cmp::Eq)]
 pub enum Type {
     Any,
+    BOOL,
     CHAR,
+    F32,
+    F64,
     Custom(String),
     List(Box<Type>, String), /* list<T, N> T: Type N: size */
     I16,
@@ -16,8 +21,11 @@ pub enum Type {
     U64,
     U8,
     BundledType { bundle: String, ty: Box<Type> },
+    Generic { name: String, args: Vec<Type> }, /* name<args> as written, codegen swaps in its instance */
     PTR(Box<Type>),
     MUT(Box<Type>),
+    Dyn(Box<Type>), /* dyn Trait: a pointer to a value and the vtable of its type's impl */
+    Poison, /* type of an expression that already failed to check, silences follow-up errors */
 }
 
 impl Type {
@@ -27,7 +35,7 @@ impl Type {
                 return format!("const {}", value.clone());
             }
             Type::List(t, n) => {
-                return format!("const jaguar_list_{}", t.to_str());
+                return format!("const jaguar_list_{}", t.c_impl());
             }
             Type::STR => {
                 return "const jaguar_str".into();
@@ -38,6 +46,9 @@ impl Type {
             Type::CHAR => {
                 return "const char".into();
             }
+            Type::BOOL => {
+                return "const _Bool".into();
+            }
             Type::U8 => {
                 return "const jaguar_u8".into();
             }
@@ -62,12 +73,24 @@ impl Type {
             Type::I64 => {
                 return "const jaguar_i64".into();
             }
+            Type::F32 => {
+                return "const jaguar_f32".into();
+            }
+            Type::F64 => {
+                return "const jaguar_f64".into();
+            }
             Type::BundledType { bundle, ty } => {
                 return format!("const {}", ty.to_str());
             }
+            Type::Generic { name, args } => {
+                return format!("const {}", instance_name(name, args));
+            }
             Type::PTR(v) => {
                 return format!("{}* const", v.to_str());
             }
+            Type::Dyn(t) => {
+                return format!("const dyn_{}", t.c_impl());
+            }
             Type::MUT(v) => {
                 return v.c_impl();
             }
@@ -83,7 +106,7 @@ impl Type {
                 return format!("{}", value.clone());
             }
             Type::List(t, n) => {
-                return format!("jaguar_list_{}", t.to_str());
+                return format!("jaguar_list_{}", t.c_impl());
             }
             Type::STR => {
                 return "jaguar_str".into();
@@ -94,6 +117,9 @@ impl Type {
             Type::CHAR => {
                 return "char".into();
             }
+            Type::BOOL => {
+                return "_Bool".into();
+            }
             Type::U8 => {
                 return "jaguar_u8".into();
             }
@@ -118,12 +144,24 @@ impl Type {
             Type::I64 => {
                 return "jaguar_i64".into();
             }
+            Type::F32 => {
+                return "jaguar_f32".into();
+            }
+            Type::F64 => {
+                return "jaguar_f64".into();
+            }
             Type::BundledType { bundle, ty } => {
-                return format!("{}", ty.to_str());
+                return ty.c_impl();
+            }
+            Type::Generic { name, args } => {
+                return instance_name(name, args);
             }
             Type::PTR(v) => {
                 return format!("{}*", v.to_str());
             }
+            Type::Dyn(t) => {
+                return format!("dyn_{}", t.c_impl());
+            }
             Type::MUT(v) => {
                 return v.c_impl();
             }
@@ -144,13 +182,22 @@ impl Type {
             Type::BundledType { bundle, ty } => {
                 return format!("{bundle}::{}", ty.debug());
             }
+            Type::Generic { name, args } => {
+                let args: Vec<String> = args.iter().map(|a| a.debug()).collect();
+                return format!("{name}<{}>", args.join(", "));
+            }
             Type::CHAR => return "char".into(),
+            Type::BOOL => return "bool".into(),
             Type::STR => return "str".into(),
             Type::INT => return "int".into(),
             Type::PTR(ty) => {
                 return format!("*{}", ty.debug());
             }
+            Type::Dyn(t) => {
+                return format!("dyn {}", t.debug());
+            }
             Type::NoType => return "void".into(),
+            Type::Poison => return "{unknown}".into(),
             _ => return format!("{:?}", self.clone()),
         }
     }
@@ -169,4 +216,11 @@ impl Type {
         }
         false
     }
+    pub(crate) fn is_dyn(&self) -> bool {
+        match self {
+            Type::Dyn(_) => true,
+            Type::MUT(t) => t.is_dyn(),
+            _ => false,
+        }
+    }
 }
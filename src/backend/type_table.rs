@@ -17,6 +17,7 @@ impl TTable {
                 name: Type::INT,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
                 file: "".to_owned(),
             },
         );
@@ -26,6 +27,7 @@ impl TTable {
                 name: Type::STR,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -50,6 +52,7 @@ impl TTable {
                         Node::Program(vec![]),
                     ),
                 ],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -59,6 +62,7 @@ impl TTable {
                 name: Type::U8,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -68,6 +72,7 @@ impl TTable {
                 name: Type::U16,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -77,6 +82,7 @@ impl TTable {
                 name: Type::U32,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -86,6 +92,7 @@ impl TTable {
                 name: Type::U64,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -95,6 +102,7 @@ impl TTable {
                 name: Type::U8,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -104,6 +112,7 @@ impl TTable {
                 name: Type::U16,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -113,6 +122,7 @@ impl TTable {
                 name: Type::U32,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -122,6 +132,37 @@ impl TTable {
                 name: Type::U64,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
+                file: String::new(),
+            },
+        );
+        content.insert(
+            Type::BOOL,
+            StructLayout {
+                name: Type::BOOL,
+                feilds: HashMap::new(),
+                methods: vec![],
+                traits: vec![],
+                file: String::new(),
+            },
+        );
+        content.insert(
+            Type::F32,
+            StructLayout {
+                name: Type::F32,
+                feilds: HashMap::new(),
+                methods: vec![],
+                traits: vec![],
+                file: String::new(),
+            },
+        );
+        content.insert(
+            Type::F64,
+            StructLayout {
+                name: Type::F64,
+                feilds: HashMap::new(),
+                methods: vec![],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -131,6 +172,7 @@ impl TTable {
                 name: Type::NoType,
                 feilds: HashMap::new(),
                 methods: vec![],
+                traits: vec![],
                 file: String::new(),
             },
         );
@@ -154,6 +196,11 @@ impl TTable {
         let l = self.content.get_mut(&ty);
         l.unwrap().methods.push(plugin);
     }
+    /// Records that `ty` implements the trait with the id `id`.
+    pub fn add_trait(&mut self, ty: Type, id: String) {
+        let l = self.content.get_mut(&ty);
+        l.unwrap().traits.push(id);
+    }
 
     pub(crate) fn wrap(&mut self, clone: String) {
         for mut ty in &mut self.content {
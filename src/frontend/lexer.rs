@@ -1,15 +1,22 @@
 use core::fmt;
-use std::process::exit;
 
+use super::source_map::FileId;
+use crate::backend::compile_error::{CompileError, ErrLevel};
+
+/// Byte range `start..end` inside the file `file`.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
+    pub file: FileId,
 }
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     Ident(String),
-    Number(String),
+    /// Decimal value of an integer literal and its type suffix (`u8`, `i64`, ...), if any.
+    Number(String, Option<String>),
+    /// Value of a float literal, as a valid C constant, and its suffix (`f32` or `f64`), if any.
+    Float(String, Option<String>),
     StrLit(String),
     Operator(String),
     Separator(String),
@@ -17,10 +24,18 @@ pub enum TokenType {
     Vardaic,
 
     Char(char),
+    /// Text the lexer could not make sense of. It has already been reported as a diagnostic.
+    Error,
     EOF,
     DOT,
+    /// `..`, the end of a range pattern is left out.
+    DOTDOT,
+    /// `..=`, the end of a range pattern is included.
+    DOTDOTEQ,
     DCOLON,
     Comment(String),
+    /// Text of a `///` line, without the slashes and the space after them.
+    DocComment(String),
 }
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
@@ -33,8 +48,11 @@ impl fmt::Display for Token {
             TokenType::Ident(var) => {
                 write!(f, "Identifier {var}")
             }
-            TokenType::Number(num) => {
-                write!(f, "Number {num}")
+            TokenType::Number(num, suffix) => {
+                write!(f, "Number {num}{}", suffix.unwrap_or_default())
+            }
+            TokenType::Float(num, suffix) => {
+                write!(f, "Float {num}{}", suffix.unwrap_or_default())
             }
             TokenType::StrLit(lit) => {
                 write!(f, "String Constant: '{lit}'")
@@ -54,20 +72,73 @@ impl fmt::Display for Token {
         }
     }
 }
+/// Operators longer than one char, longest first.
+const OPERATORS: [&str; 20] = [
+    "<<=", ">>=", "&&", "||", "<<", ">>", "==", "!=", "<=", ">=", "+=", "-=", "*=", "/=", "%=",
+    "&=", "|=", "^=", "->", "=>",
+];
+
 pub struct Tokenizer {
     source: Vec<char>,
+    /* byte offset of every char in `source`, plus one past the end */
+    offsets: Vec<usize>,
+    file: FileId,
     pos: usize,
     start: usize,
-    line: u64,
+    diagnostics: Vec<CompileError>,
 }
 
 impl Tokenizer {
-    pub fn new(input: &str) -> Self {
+    pub fn new(input: &str, file: FileId) -> Self {
+        let mut offsets: Vec<usize> = input.char_indices().map(|(i, _)| i).collect();
+        offsets.push(input.len());
         Tokenizer {
             source: input.chars().collect(),
+            offsets,
+            file,
             pos: 0,
             start: 0,
-            line: 1,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Errors found while scanning, such as invalid escape sequences.
+    pub fn diagnostics(&self) -> &[CompileError] {
+        &self.diagnostics
+    }
+
+    /// Scans the whole input, up to and including the EOF token. Comments are
+    /// dropped, doc comments are kept for the parser to attach.
+    pub fn tokenize(&mut self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        loop {
+            let tok = self.next_token();
+            if let TokenType::Comment(_) = tok.kind {
+                continue;
+            }
+            tokens.push(tok.clone());
+            if tok.kind == TokenType::EOF {
+                return tokens;
+            }
+        }
+    }
+
+    fn error(&mut self, msg: String, help: Option<String>, from: usize) {
+        let span = Span {
+            start: self.offsets[from],
+            end: self.offsets[self.pos],
+            file: self.file,
+        };
+        self.diagnostics
+            .push(CompileError::new(msg, help, span, ErrLevel::ERROR));
+    }
+
+    /// Span of the token being scanned, from `start` up to the current position.
+    fn span(&self) -> Span {
+        Span {
+            start: self.offsets[self.start],
+            end: self.offsets[self.pos],
+            file: self.file,
         }
     }
 
@@ -76,9 +147,6 @@ impl Tokenizer {
     }
     fn consume(&mut self) -> Option<char> {
         let ch = self.peek()?;
-        if ch == '\n' {
-            self.line += 1;
-        }
         self.pos += 1;
         Some(ch)
     }
@@ -92,166 +160,76 @@ impl Tokenizer {
         self.skip_ws();
         self.start = self.pos;
         match self.peek() {
+            Some('r') if self.is_raw_string() => self.raw_string(),
             Some(c) if c.is_alphabetic() || c == '_' => self.identifier_or_keyword(),
             Some(c) if c.is_numeric() => self.number(),
+            Some('"') if self.source[self.pos..].starts_with(&['"'; 3]) => self.multiline_string(),
             Some('"') => self.string_lit(),
-            Some('\'') => {
-                self.consume();
-                if self.peek().unwrap() != '\'' {
-                    let c = self.consume().unwrap();
-                    if self.peek().unwrap() == '\'' {
-                        self.consume();
-                        return Token {
-                            kind: TokenType::Char(c),
-                            span: Span {
-                                start: self.start,
-                                end: self.pos,
-                            },
-                        };
-                    } else {
-                        println!("Expected closing \'");
-                        exit(1)
-                    }
-                } else {
-                    println!("Cannot have empty char literal");
-                    exit(1);
-                }
-            }
-            Some(c) if "+-*/=<>!&|%?".contains(c) => {
+            Some('\'') => self.char_lit(),
+            Some(c) if "+-*/=<>!&|%?^~".contains(c) => {
                 if c == '/' {
-                    if self.source.get(self.pos + 1).unwrap() == &'/' {
-                        self.consume();
-                        self.consume();
-                        let mut comment = String::new();
-                        while self.peek().unwrap() != '\n' {
-                            comment.push(self.consume().unwrap());
-                        }
-                        return Token {
-                            kind: TokenType::Comment(comment),
-                            span: Span {
-                                start: self.start,
-                                end: self.pos,
-                            },
-                        };
-                    } else if self.source.get(self.pos + 1).unwrap() == &'*' {
-                        let mut comment = String::new();
-                        self.consume();
-                        self.consume();
-                        while !(self.peek().unwrap() == '*'
-                            && self.source.get(self.pos + 1).unwrap() == &'/')
-                        {
-                            comment.push(self.consume().unwrap());
-                        }
-                        self.consume();
-                        self.consume();
-                        return Token {
-                            kind: TokenType::Comment(comment),
-                            span: Span {
-                                start: self.start,
-                                end: self.pos,
-                            },
-                        };
-                    }
-                } else if c == '=' {
-                    if self.source.get(self.pos + 1).unwrap() == &'=' {
-                        self.consume();
-                        self.consume();
-                        return Token {
-                            kind: TokenType::Operator("==".to_owned()),
-                            span: Span {
-                                start: self.start,
-                                end: self.pos,
-                            },
-                        };
+                    match self.source.get(self.pos + 1) {
+                        Some('/') => return self.line_comment(),
+                        Some('*') => return self.block_comment(),
+                        _ => {}
                     }
-                } else if c == '!' {
-                    if self.source.get(self.pos + 1).unwrap() == &'=' {
-                        self.consume();
-                        self.consume();
-                        return Token {
-                            kind: TokenType::Operator("!=".to_owned()),
-                            span: Span {
-                                start: self.start,
-                                end: self.pos,
-                            },
-                        };
-                    }
-                } else if c == '<' {
-                    if self.source.get(self.pos + 1).unwrap() == &'=' {
-                        self.consume();
-                        self.consume();
-                        return Token {
-                            kind: TokenType::Operator("<=".to_owned()),
-                            span: Span {
-                                start: self.start,
-                                end: self.pos,
-                            },
-                        };
-                    }
-                } else if c == '>' {
-                    if self.source.get(self.pos + 1).unwrap() == &'=' {
-                        self.consume();
+                }
+                /* longest match first, so `<<=` is never read as `<<` and `=` */
+                if let Some(op) = OPERATORS.iter().find(|op| {
+                    op.chars()
+                        .enumerate()
+                        .all(|(i, c)| self.source.get(self.pos + i) == Some(&c))
+                }) {
+                    for _ in 0..op.len() {
                         self.consume();
-                        return Token {
-                            kind: TokenType::Operator(">=".to_owned()),
-                            span: Span {
-                                start: self.start,
-                                end: self.pos,
-                            },
-                        };
                     }
+                    return Token {
+                        kind: TokenType::Operator(op.to_string()),
+                        span: self.span(),
+                    };
                 }
                 self.consume();
                 Token {
                     kind: TokenType::Operator(format!("{c}")),
-                    span: Span {
-                        start: self.start,
-                        end: self.pos,
-                    },
+                    span: self.span(),
                 }
             }
-            Some(c) if c == '.' => {
+            Some('.') => {
                 self.consume();
-                match self.peek().unwrap() == '.' {
-                    true => {
-                        self.consume();
-                        match self.peek().unwrap() == '.' {
-                            true => {
-                                self.consume();
-                                Token {
-                                    kind: TokenType::Vardaic,
-                                    span: Span {
-                                        start: self.start,
-                                        end: self.pos,
-                                    },
-                                }
-                            }
-                            false => {
-                                println!("Unexpected character. missing '.' maybe?");
-                                exit(100);
-                            }
-                        }
-                    }
-                    false => Token {
+                if self.peek() != Some('.') {
+                    return Token {
                         kind: TokenType::DOT,
-                        span: Span {
-                            start: self.start,
-                            end: self.pos,
-                        },
-                    },
+                        span: self.span(),
+                    };
+                }
+                self.consume();
+                if self.peek() == Some('=') {
+                    self.consume();
+                    return Token {
+                        kind: TokenType::DOTDOTEQ,
+                        span: self.span(),
+                    };
+                }
+                if self.peek() != Some('.') {
+                    return Token {
+                        kind: TokenType::DOTDOT,
+                        span: self.span(),
+                    };
+                }
+                self.consume();
+                Token {
+                    kind: TokenType::Vardaic,
+                    span: self.span(),
                 }
             }
             Some(c) if "(){}[],;:".contains(c) => {
                 if c == ':' {
                     self.consume();
-                    if self.peek().unwrap() == ':' {
+                    if self.peek() == Some(':') {
                         self.consume();
                         return Token {
                             kind: TokenType::DCOLON,
-                            span: Span {
-                                start: self.start,
-                                end: self.pos,
-                            },
+                            span: self.span(),
                         };
                     }
                 } else {
@@ -259,28 +237,20 @@ impl Tokenizer {
                 }
                 Token {
                     kind: TokenType::Separator(format!("{c}")),
-                    span: Span {
-                        start: self.start,
-                        end: self.pos,
-                    },
+                    span: self.span(),
                 }
             }
-            Some(_) => {
+            Some(c) => {
                 self.consume();
+                self.error(format!("Unknown character {c:?}"), None, self.start);
                 Token {
-                    kind: TokenType::EOF,
-                    span: Span {
-                        start: self.start,
-                        end: self.pos,
-                    },
+                    kind: TokenType::Error,
+                    span: self.span(),
                 }
             }
             None => Token {
                 kind: TokenType::EOF,
-                span: Span {
-                    start: self.start,
-                    end: self.pos,
-                },
+                span: self.span(),
             },
         }
     }
@@ -298,49 +268,302 @@ impl Tokenizer {
 
         match ident.as_str() {
             "let" | "fn" | "if" | "else" | "while" | "ret" | "int" | "str" | "bool" | "buf"
-            | "extern" | "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "char"
-            | "struct" | "for" | "bundle" | "as" | "list" | "void" | "ptr" | "break" | "pack"
-            | "null" | "st" | "unpack" | "with" | "continue" | "until" | "mut" => Token {
+            | "extern" | "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "f32"
+            | "f64" | "char" | "struct" | "for" | "bundle" | "as" | "list" | "void" | "ptr"
+            | "break" | "pack" | "null" | "st" | "unpack" | "with" | "continue" | "until"
+            | "mut" | "true" | "false" | "JLINE" | "JCOLUMN" | "JFILE" | "JFUNC" | "JBUNDLE"
+            | "trait" | "impl" | "dyn" | "enum" | "match" | "sizeof" => Token {
                 kind: TokenType::Keyword(ident),
-                span: Span {
-                    start: self.start,
-                    end: self.pos,
-                },
-            },
-            "JLINE" => Token {
-                kind: TokenType::Number(format!("{}", self.line)),
-                span: Span {
-                    start: self.start,
-                    end: self.pos,
-                },
+                span: self.span(),
             },
             _ => Token {
                 kind: TokenType::Ident(ident),
-                span: Span {
-                    start: self.start,
-                    end: self.pos,
-                },
+                span: self.span(),
             },
         }
     }
     fn number(&mut self) -> Token {
         self.start = self.pos;
-        let mut number = String::new();
+        let (radix, base) = match (self.peek(), self.source.get(self.pos + 1)) {
+            (Some('0'), Some('x')) => (16, "hex"),
+            (Some('0'), Some('o')) => (8, "octal"),
+            (Some('0'), Some('b')) => (2, "binary"),
+            _ => (10, "decimal"),
+        };
+        if radix != 10 {
+            self.consume();
+            self.consume();
+        }
+        /* digits, separators and the suffix all run together, e.g. 0xFF_u8 */
+        let mut digits = String::new();
+        let mut suffix = String::new();
+        let (mut is_float, mut has_exponent) = (false, false);
         while let Some(c) = self.peek() {
-            if c.is_numeric() {
-                number.push(c);
-                self.consume();
-            } else {
+            /* only decimal literals can be floats, 'e' and 'f' are hex digits */
+            if radix == 10 && suffix.is_empty() {
+                let next = self.source.get(self.pos + 1).copied();
+                if c == '.' && !is_float && next.is_some_and(|n| n.is_ascii_digit()) {
+                    self.consume();
+                    digits.push('.');
+                    is_float = true;
+                    continue;
+                }
+                if (c == 'e' || c == 'E') && !has_exponent {
+                    self.consume();
+                    digits.push('e');
+                    if let Some(sign @ ('+' | '-')) = self.peek() {
+                        self.consume();
+                        digits.push(sign);
+                    }
+                    is_float = true;
+                    has_exponent = true;
+                    continue;
+                }
+            }
+            if !(c.is_alphanumeric() || c == '_') {
                 break;
+            }
+            self.consume();
+            if !suffix.is_empty() || c == 'u' || c == 'i' || (radix == 10 && c == 'f') {
+                suffix.push(c);
+            } else if c != '_' {
+                digits.push(c);
+            }
+        }
+        if is_float || suffix == "f32" || suffix == "f64" {
+            return self.float(digits, suffix);
+        }
+
+        let mut number = String::from("0");
+        if let Some(bad) = digits.chars().find(|c| !c.is_digit(radix)) {
+            self.error(
+                format!("Invalid digit '{bad}' in {base} literal"),
+                None,
+                self.start,
+            );
+        } else if digits.is_empty() {
+            self.error(
+                format!("Missing digits in {base} literal"),
+                None,
+                self.start,
+            );
+        } else {
+            match u64::from_str_radix(&digits, radix) {
+                Ok(value) => number = value.to_string(),
+                Err(_) => self.error(
+                    "Integer literal is too large".into(),
+                    Some(format!("the largest integer literal is {}", u64::MAX)),
+                    self.start,
+                ),
+            }
+        }
+        let suffix = match suffix.as_str() {
+            "" => None,
+            "u8" | "u16" | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" => Some(suffix),
+            _ => {
+                self.error(
+                    format!("Invalid suffix '{suffix}' for an integer literal"),
+                    Some(
+                        "valid suffixes are u8, u16, u32, u64, i8, i16, i32, i64, f32 and f64"
+                            .into(),
+                    ),
+                    self.start,
+                );
+                None
+            }
+        };
+
+        Token {
+            kind: TokenType::Number(number, suffix),
+            span: self.span(),
+        }
+    }
+
+    /// Finishes a decimal literal with a fraction, an exponent or a float suffix.
+    fn float(&mut self, digits: String, suffix: String) -> Token {
+        let mut number = String::from("0.0");
+        if let Some(bad) = digits
+            .chars()
+            .find(|c| !(c.is_ascii_digit() || "e.+-".contains(*c)))
+        {
+            self.error(
+                format!("Invalid digit '{bad}' in float literal"),
+                None,
+                self.start,
+            );
+        } else if digits.ends_with(['e', '+', '-']) {
+            self.error(
+                "Missing digits in float exponent".into(),
+                Some("write exponents as 1e10 or 2.5e-3".into()),
+                self.start,
+            );
+        } else {
+            match digits.parse::<f64>() {
+                /* Debug always keeps a '.' or an exponent, so C reads it as a double */
+                Ok(value) if value.is_finite() => number = format!("{value:?}"),
+                _ => self.error(
+                    "Float literal is too large".into(),
+                    Some(format!("the largest float literal is {:e}", f64::MAX)),
+                    self.start,
+                ),
+            }
+        }
+        let suffix = match suffix.as_str() {
+            "" => None,
+            "f32" | "f64" => Some(suffix),
+            _ => {
+                self.error(
+                    format!("Invalid suffix '{suffix}' for a float literal"),
+                    Some("valid suffixes are f32 and f64".into()),
+                    self.start,
+                );
+                None
+            }
+        };
+
+        Token {
+            kind: TokenType::Float(number, suffix),
+            span: self.span(),
+        }
+    }
+
+    fn char_lit(&mut self) -> Token {
+        self.consume(); // opening '
+        let c = match self.peek() {
+            None | Some('\n') => {
+                self.error(
+                    "Unterminated char literal".into(),
+                    Some("add the closing '".into()),
+                    self.start,
+                );
+                return Token {
+                    kind: TokenType::Char('\0'),
+                    span: self.span(),
+                };
+            }
+            Some('\'') => {
+                self.consume();
+                self.error(
+                    "Empty char literal".into(),
+                    Some("use '\\0' for the null character".into()),
+                    self.start,
+                );
+                return Token {
+                    kind: TokenType::Char('\0'),
+                    span: self.span(),
+                };
+            }
+            Some('\\') => self.escape().unwrap_or('\0'),
+            Some(c) => {
+                self.consume();
+                c
+            }
+        };
+        if !c.is_ascii() {
+            self.error(
+                format!("Character literal {c:?} does not fit in a char"),
+                Some("char holds a single ASCII character, use a str".into()),
+                self.start,
+            );
+        }
+        if self.peek() == Some('\'') {
+            self.consume();
+            return Token {
+                kind: TokenType::Char(c),
+                span: self.span(),
             };
         }
+        /* skip the rest of a literal like 'ab' so it is reported once */
+        let mut rest = self.source[self.pos..].iter().take_while(|&&c| c != '\n');
+        if let Some(len) = rest.position(|&c| c == '\'') {
+            for _ in 0..=len {
+                self.consume();
+            }
+            self.error(
+                "Char literal holds more than one character".into(),
+                Some("use a str for more than one character".into()),
+                self.start,
+            );
+        } else {
+            self.error(
+                "Expected a closing ' after the char literal".into(),
+                None,
+                self.start,
+            );
+        }
+        Token {
+            kind: TokenType::Char(c),
+            span: self.span(),
+        }
+    }
 
+    /// `// ...` up to, but not including, the end of the line. `///` starts a
+    /// doc comment, four or more slashes are a plain comment again.
+    fn line_comment(&mut self) -> Token {
+        self.consume();
+        self.consume();
+        let is_doc = self.peek() == Some('/') && self.source.get(self.pos + 1) != Some(&'/');
+        if is_doc {
+            self.consume();
+        }
+        let mut comment = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            comment.push(c);
+            self.consume();
+        }
+        let kind = match is_doc {
+            true => TokenType::DocComment(comment.strip_prefix(' ').unwrap_or(&comment).into()),
+            false => TokenType::Comment(comment),
+        };
         Token {
-            kind: TokenType::Number(number),
-            span: Span {
-                start: self.start,
-                end: self.pos,
-            },
+            kind,
+            span: self.span(),
+        }
+    }
+
+    /// `/* ... */`. Block comments nest, so a commented out region may contain them.
+    fn block_comment(&mut self) -> Token {
+        self.consume();
+        self.consume();
+        let mut comment = String::new();
+        let mut depth = 1;
+        loop {
+            match (self.peek(), self.source.get(self.pos + 1)) {
+                (Some('/'), Some('*')) => {
+                    depth += 1;
+                    comment.push_str("/*");
+                    self.consume();
+                    self.consume();
+                }
+                (Some('*'), Some('/')) => {
+                    self.consume();
+                    self.consume();
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    comment.push_str("*/");
+                }
+                (Some(c), _) => {
+                    comment.push(c);
+                    self.consume();
+                }
+                (None, _) => {
+                    self.error(
+                        "Unterminated block comment".into(),
+                        Some("close it with '*/'".into()),
+                        self.start,
+                    );
+                    break;
+                }
+            }
+        }
+        Token {
+            kind: TokenType::Comment(comment),
+            span: self.span(),
         }
     }
 
@@ -348,11 +571,25 @@ impl Tokenizer {
         self.start = self.pos;
         self.consume(); // opening "
         let mut content = String::new();
-        while let Some(c) = self.peek() {
+        loop {
+            let Some(c) = self.peek() else {
+                self.error(
+                    "Unterminated string literal".into(),
+                    Some("add the closing '\"'".into()),
+                    self.start,
+                );
+                break;
+            };
             if c == '"' {
                 self.consume();
                 break;
             }
+            if c == '\\' {
+                if let Some(c) = self.escape() {
+                    content.push(c);
+                }
+                continue;
+            }
 
             content.push(c);
             self.consume();
@@ -360,10 +597,227 @@ impl Tokenizer {
 
         Token {
             kind: TokenType::StrLit(content),
-            span: Span {
-                start: self.start,
-                end: self.pos,
-            },
+            span: self.span(),
         }
     }
+
+    /// `r"` or `r#"`, with any number of `#`.
+    fn is_raw_string(&self) -> bool {
+        let rest = &self.source[self.pos + 1..];
+        let hashes = rest.iter().take_while(|&&c| c == '#').count();
+        rest.get(hashes) == Some(&'"')
+    }
+
+    /// `r"..."`, taken verbatim. `r#"..."#` may contain `"`, and more `#`s allow `"#`.
+    fn raw_string(&mut self) -> Token {
+        self.start = self.pos;
+        self.consume(); // r
+        let mut hashes = 0;
+        while self.peek() == Some('#') {
+            self.consume();
+            hashes += 1;
+        }
+        self.consume(); // opening "
+        let mut content = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                self.error(
+                    "Unterminated raw string literal".into(),
+                    Some(format!("close it with '\"{}'", "#".repeat(hashes))),
+                    self.start,
+                );
+                break;
+            };
+            self.consume();
+            if c == '"' && (0..hashes).all(|i| self.source.get(self.pos + i) == Some(&'#')) {
+                for _ in 0..hashes {
+                    self.consume();
+                }
+                break;
+            }
+            content.push(c);
+        }
+        Token {
+            kind: TokenType::StrLit(content),
+            span: self.span(),
+        }
+    }
+
+    /// `"""..."""`, which may span lines. Escapes work as in `"..."`, see
+    /// [`strip_indent`] for how the layout is removed.
+    fn multiline_string(&mut self) -> Token {
+        self.start = self.pos;
+        for _ in 0..3 {
+            self.consume();
+        }
+        /* escaped chars are marked, so a '\t' or '\n' is never taken for layout */
+        let mut chars: Vec<(char, bool)> = Vec::new();
+        loop {
+            match self.peek() {
+                None => {
+                    self.error(
+                        "Unterminated multi-line string literal".into(),
+                        Some("close it with '\"\"\"'".into()),
+                        self.start,
+                    );
+                    break;
+                }
+                Some('"') if self.source[self.pos..].starts_with(&['"'; 3]) => {
+                    for _ in 0..3 {
+                        self.consume();
+                    }
+                    break;
+                }
+                Some('\\') => {
+                    if let Some(c) = self.escape() {
+                        chars.push((c, true));
+                    }
+                }
+                Some(c) => {
+                    chars.push((c, false));
+                    self.consume();
+                }
+            }
+        }
+        Token {
+            kind: TokenType::StrLit(strip_indent(&chars)),
+            span: self.span(),
+        }
+    }
+
+    /// Decodes the escape sequence at the current `\\`. Reports invalid ones and
+    /// returns None for them, leaving the scanner after the offending characters.
+    fn escape(&mut self) -> Option<char> {
+        let from = self.pos;
+        self.consume(); // the backslash
+        let Some(c) = self.peek() else {
+            self.error("Unterminated escape sequence".into(), None, from);
+            return None;
+        };
+        self.consume();
+        match c {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '\\' => Some('\\'),
+            '"' => Some('"'),
+            '\'' => Some('\''),
+            'x' => {
+                let digits: String = self.take_while_n(2, |c| c.is_ascii_hexdigit());
+                if digits.len() != 2 {
+                    self.error(
+                        "Invalid escape '\\x', expected two hex digits".into(),
+                        Some("write bytes as \\x00 to \\x7F".into()),
+                        from,
+                    );
+                    return None;
+                }
+                let value = u8::from_str_radix(&digits, 16).unwrap();
+                if value > 0x7F {
+                    self.error(
+                        format!("Hex escape '\\x{digits}' is out of range"),
+                        Some("\\x only goes up to \\x7F, use \\u{...} for other characters".into()),
+                        from,
+                    );
+                    return None;
+                }
+                Some(value as char)
+            }
+            'u' => {
+                if self.peek() != Some('{') {
+                    self.error(
+                        "Invalid unicode escape, expected '{'".into(),
+                        Some("write unicode escapes as \\u{1F600}".into()),
+                        from,
+                    );
+                    return None;
+                }
+                self.consume();
+                let digits: String = self.take_while_n(6, |c| c.is_ascii_hexdigit());
+                if self.peek() != Some('}') || digits.is_empty() {
+                    self.error(
+                        "Invalid unicode escape, expected 1 to 6 hex digits and '}'".into(),
+                        Some("write unicode escapes as \\u{1F600}".into()),
+                        from,
+                    );
+                    return None;
+                }
+                self.consume();
+                let value = u32::from_str_radix(&digits, 16).unwrap();
+                let decoded = char::from_u32(value);
+                if decoded.is_none() {
+                    self.error(
+                        format!("'\\u{{{digits}}}' is not a valid unicode character"),
+                        None,
+                        from,
+                    );
+                }
+                decoded
+            }
+            other => {
+                self.error(
+                    format!("Unknown escape sequence '\\{other}'"),
+                    Some(
+                        "valid escapes are \\n \\t \\r \\0 \\\\ \\\" \\' \\xNN and \\u{...}".into(),
+                    ),
+                    from,
+                );
+                None
+            }
+        }
+    }
+
+    fn take_while_n(&mut self, max: usize, accept: fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while taken.len() < max {
+            match self.peek() {
+                Some(c) if accept(c) => {
+                    taken.push(c);
+                    self.consume();
+                }
+                _ => break,
+            }
+        }
+        taken
+    }
+}
+
+/// Removes the layout of a `"""` string: a blank first line (the break right
+/// after the opening quotes), a blank last line (the one holding the closing
+/// quotes) and the indentation shared by every other non-blank line.
+fn strip_indent(chars: &[(char, bool)]) -> String {
+    let is_layout = |&(c, escaped): &(char, bool)| !escaped && (c == ' ' || c == '\t');
+    let blank = |line: &[(char, bool)]| line.iter().all(is_layout);
+    let mut lines: Vec<&[(char, bool)]> =
+        chars.split(|&(c, escaped)| c == '\n' && !escaped).collect();
+    if lines.len() > 1 && blank(lines[0]) {
+        lines.remove(0);
+    }
+    if lines.len() > 1 && blank(lines[lines.len() - 1]) {
+        lines.pop();
+    }
+    /* the longest run of spaces and tabs every non-blank line starts with */
+    let mut shared: Option<&[(char, bool)]> = None;
+    for line in lines.iter().filter(|line| !blank(line)) {
+        let lead = line.iter().take_while(|c| is_layout(c)).count();
+        let prefix = &line[..lead];
+        shared = Some(match shared {
+            None => prefix,
+            Some(other) => {
+                let same = other
+                    .iter()
+                    .zip(prefix)
+                    .take_while(|(a, b)| a.0 == b.0)
+                    .count();
+                &other[..same]
+            }
+        });
+    }
+    let indent = shared.map_or(0, <[(char, bool)]>::len);
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| line.iter().skip(indent).map(|&(c, _)| c).collect())
+        .collect();
+    lines.join("\n")
 }
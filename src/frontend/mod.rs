@@ -1 +1,2 @@
-pub(crate) mod lexer;
+pub mod lexer;
+pub mod source_map;
@@ -0,0 +1,49 @@
+use crate::lexer::Span;
+
+/// Index of a file in the [`SourceMap`], carried by every [`Span`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FileId(pub u32);
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// Path as shown in diagnostics.
+    pub name: String,
+    pub source: String,
+}
+
+impl SourceFile {
+    /// 1-based line and column of the byte `offset`.
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let source = self.source.as_str();
+        let offset = offset.min(source.len());
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line = source[..offset].chars().filter(|&c| c == '\n').count() + 1;
+        (line, source[line_start..offset].chars().count() + 1)
+    }
+}
+
+/// Every file loaded during a compilation: the main source and all of its bundles.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: String, source: String) -> FileId {
+        self.files.push(SourceFile { name, source });
+        FileId(self.files.len() as u32 - 1)
+    }
+
+    pub fn get(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    /// The file `span` points into.
+    pub fn file(&self, span: &Span) -> &SourceFile {
+        self.get(span.file)
+    }
+}
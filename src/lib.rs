@@ -0,0 +1,8 @@
+pub mod backend;
+pub mod frontend;
+pub mod runtime;
+pub mod session;
+
+use backend::parser;
+use frontend::lexer;
+use frontend::source_map;
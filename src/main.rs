@@ -1,12 +1,33 @@
-pub(crate) mod backend;
-pub(crate) mod frontend;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, exit};
 
-use backend::codegen::Generator;
-use backend::parser;
 use clap::Parser as OtherParser;
-use frontend::lexer;
-use lexer::{TokenType, Tokenizer};
+use colored::Colorize;
+use jagc::backend::ast_dump;
+use jagc::backend::codegen::GeneratedFile;
+use jagc::backend::compile_error::ErrorFormat;
+use jagc::runtime::Runtime;
+use jagc::session::{Options, Session};
+/// What jagc stops at and prints, picked with `--emit`.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Emit {
+    /// The token stream, one token per line.
+    Tokens,
+    /// The parsed syntax tree.
+    Ast,
+    /// The generated C, without invoking the C compiler.
+    C,
+    /// A linked executable.
+    Exe,
+}
+
+/// Whether generated code keeps its runtime checks, picked with `--checks`.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Checks {
+    On,
+    Off,
+}
+
 #[derive(OtherParser)]
 #[command(
     name = "Jagc",
@@ -16,9 +37,21 @@ use lexer::{TokenType, Tokenizer};
 )]
 #[derive(Debug, Clone)]
 pub struct Cli {
-    #[arg(short, long)]
+    #[arg(
+        short,
+        long,
+        help = "Optimize the C and drop runtime checks (bounds checks) unless --checks=on"
+    )]
     pub release: bool,
 
+    #[arg(
+        long,
+        value_enum,
+        value_name = "on|off",
+        help = "Keep or drop runtime checks regardless of --release"
+    )]
+    pub checks: Option<Checks>,
+
     #[arg(value_name = "SOURCE")]
     pub source: String,
 
@@ -27,75 +60,304 @@ pub struct Cli {
 
     #[arg(long, help = "Keep the C artifacts")]
     pub keepc: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory holding the Jaguar runtime (claw.h, claw.c, trn0/stdio.asm)"
+    )]
+    pub runtime_dir: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value = "human",
+        help = "How to print errors and warnings"
+    )]
+    pub error_format: ErrorFormat,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "KIND",
+        default_value = "exe",
+        help = "What to produce. tokens, ast and c print to stdout, or to the -o file"
+    )]
+    pub emit: Emit,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        default_value = "cc",
+        help = "C compiler used to compile the generated C and link it"
+    )]
+    pub cc: String,
+
+    #[arg(
+        long,
+        value_name = "FLAGS",
+        allow_hyphen_values = true,
+        help = "Extra flags for the C compiler, split on whitespace. May be repeated"
+    )]
+    pub cflags: Vec<String>,
+
+    #[arg(
+        short = 'l',
+        value_name = "LIB",
+        help = "Link against the C library LIB"
+    )]
+    pub libs: Vec<String>,
+
+    #[arg(
+        short = 'L',
+        value_name = "DIR",
+        help = "Add DIR to the library search path"
+    )]
+    pub lib_dirs: Vec<String>,
+
+    #[arg(
+        short = 'I',
+        value_name = "DIR",
+        help = "Add DIR to the C header search path"
+    )]
+    pub include_dirs: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory for the generated C. Defaults to a temporary directory, or ./build with --keepc"
+    )]
+    pub build_dir: Option<String>,
+
+    #[arg(
+        long,
+        help = "Accept integer conditions in if/while/for with a warning instead of an error"
+    )]
+    pub int_conditions: bool,
+}
+
+/// Where the generated C goes, and whether jagc made the directory itself.
+struct BuildDir {
+    path: PathBuf,
+    created: bool,
 }
 
-fn initbuilddir() -> String {
-    let cwd = std::env::current_dir()
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .to_string();
-    let builddir = format!("{cwd}/build");
-    // println!("Current Directory: {cwd}");
-    // println!("Current Build Directory: {builddir}");
-    if !std::path::Path::exists(std::path::Path::new(&builddir)) {
-        let _ = std::fs::create_dir(builddir.clone());
-    }
-    builddir
+impl BuildDir {
+    fn new(path: PathBuf) -> Self {
+        /* bundle headers are #included by this path, so it has to work from anywhere */
+        let path = std::path::absolute(&path).unwrap_or(path);
+        let created = !path.exists();
+        BuildDir { path, created }
+    }
+
+    fn for_cli(cli: &Cli) -> Self {
+        if let Some(dir) = &cli.build_dir {
+            return Self::new(PathBuf::from(dir));
+        }
+        match (cli.emit, cli.output.as_deref()) {
+            /* emitted C includes the bundle headers, so they go next to it. C printed to
+            stdout has nowhere to put them but the temporary directory */
+            (Emit::C, Some(output)) => Self::new(
+                Path::new(output)
+                    .parent()
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .map(Path::to_path_buf)
+                    .unwrap_or(std::env::current_dir().unwrap_or_default()),
+            ),
+            _ if cli.keepc => Self::new(PathBuf::from("build")),
+            _ => Self::new(std::env::temp_dir().join(format!("jagc-{}", std::process::id()))),
+        }
+    }
+
+    /// Removes `files` and, if jagc created the directory, the directory once it is empty.
+    fn clean(&self, files: &[GeneratedFile]) {
+        for file in files {
+            let _ = std::fs::remove_file(&file.path);
+        }
+        if self.created {
+            let _ = std::fs::remove_dir(&self.path);
+        }
+    }
+}
+
+/// Writes an `--emit` result to the -o file, or stdout without one.
+fn write_output(output: Option<&str>, contents: &str) {
+    let Some(path) = output else {
+        print!("{contents}");
+        return;
+    };
+    if let Err(e) = std::fs::write(path, contents) {
+        eprintln!(
+            "{} Could not write to {path}: {e}",
+            "[Tixie Error] :".red().bold()
+        );
+        exit(1);
+    }
 }
 
 fn main() {
     let cli = Cli::parse();
+    if cli.emit == Emit::Exe && cli.output.is_none() {
+        eprintln!(
+            "{} No output file given, pass -o <OUTPUT>",
+            "[Tixie Error] :".red().bold()
+        );
+        exit(1);
+    }
+    /* the C file is named after the executable, or after the source when there is none */
+    let output = match (&cli.output, cli.emit) {
+        (Some(output), Emit::Exe) => Path::new(output).file_name(),
+        _ => Path::new(&cli.source).file_stem(),
+    }
+    .map(|s| s.to_string_lossy().to_string())
+    .unwrap_or("out".into());
 
-    let input = std::fs::read_to_string(cli.source.clone()).expect("Unable to open File");
-    let mut tokenizer = Tokenizer::new(&input);
-    let mut tokens = Vec::new();
-    loop {
-        let tok = tokenizer.next_token();
-        if let TokenType::Comment(_) = tok.kind {
-            continue;
+    let build = BuildDir::for_cli(&cli);
+    let b = build.path.display().to_string();
+    let mut session = Session::new(Options {
+        build_dir: build.path.clone(),
+        output,
+        int_conditions: cli.int_conditions,
+    });
+    /* tokens and the tree only need the parser, no C is generated for them */
+    let compiled = match cli.emit {
+        Emit::Tokens | Emit::Ast => session.parse_file(Path::new(&cli.source)),
+        Emit::C | Emit::Exe => session.compile_file(Path::new(&cli.source)),
+    };
+    let mut compilation = match compiled {
+        Ok(compilation) => compilation,
+        Err(e) => {
+            eprintln!(
+                "{} Unable to open {}: {e}",
+                "[Tixie Error] :".red().bold(),
+                cli.source
+            );
+            exit(1);
         }
-        tokens.push(tok.clone());
-        if tok.kind == TokenType::EOF {
-            break;
+    };
+    /* tokens and the tree are printed even when parsing fails, that is when they are needed */
+    match cli.emit {
+        Emit::Tokens => {
+            let sources = session.sources();
+            let mut dump = String::new();
+            for token in &compilation.tokens {
+                let (line, column) = sources.file(&token.span).line_column(token.span.start);
+                dump += &format!("{line}:{column}\t{token}\n");
+            }
+            drop(sources);
+            write_output(cli.output.as_deref(), &dump);
         }
+        Emit::Ast => write_output(cli.output.as_deref(), &ast_dump::dump(&compilation.ast)),
+        Emit::C | Emit::Exe => {}
+    }
+    if session.report(&mut compilation.diagnostics, cli.error_format) {
+        exit(1);
     }
-    let b = initbuilddir();
-    let mut parser = parser::Parser::new(tokens, input.clone());
-    match parser.parse_program() {
-        program => {
-            let mut cgen = Generator::new(
-                program,
-                &format!("{b}/{}", cli.output.clone().unwrap()),
-                input,
-                false,
-                std::path::Path::new(cli.source.clone().as_str())
-                    .to_str()
-                    .unwrap()
-                    .to_string(),
-                std::fs::canonicalize(cli.source)
-                    .ok()
-                    .map(|p| p.to_string_lossy().to_string())
-                    .unwrap()
-                    .to_string(),
-                b,
+    match cli.emit {
+        Emit::Tokens | Emit::Ast => return,
+        Emit::C => {
+            /* bundle headers are #included from the build directory, so they are kept there */
+            for header in compilation.files.iter().skip(1) {
+                if let Err(e) = header.write() {
+                    eprintln!(
+                        "{} Could not write to {b}: {e}",
+                        "[Tixie Error] :".red().bold()
+                    );
+                    exit(1);
+                }
+            }
+            write_output(
+                cli.output.as_deref(),
+                compilation.c_source().unwrap_or_default(),
+            );
+            return;
+        }
+        Emit::Exe => {}
+    }
+
+    let runtime = match Runtime::discover(cli.runtime_dir.as_deref(), &cli.cc) {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("{} {e}", "[Tixie Error] :".red().bold());
+            exit(1);
+        }
+    };
+    if let Err(e) = compilation.write() {
+        eprintln!(
+            "{} Could not write to {b}: {e}",
+            "[Tixie Error] :".red().bold()
+        );
+        build.clean(&compilation.files);
+        exit(1);
+    }
+    let mut cc = Command::new(&cli.cc);
+    cc.arg(&compilation.files[0].path)
+        .arg("-o")
+        .arg(cli.output.as_deref().unwrap())
+        .arg("-I")
+        .arg(&runtime.dir);
+    for dir in &cli.include_dirs {
+        cc.arg("-I").arg(dir);
+    }
+    /* debug builds keep every check, release builds only when asked to */
+    if cli.release {
+        cc.arg("-O2");
+    } else {
+        cc.arg("-g");
+    }
+    if cli
+        .checks
+        .unwrap_or(if cli.release { Checks::Off } else { Checks::On })
+        == Checks::Off
+    {
+        cc.arg("-DJAGUAR_NO_CHECKS");
+    }
+    cc.arg("-no-pie")
+        .arg("-w")
+        .args(cli.cflags.iter().flat_map(|f| f.split_whitespace()))
+        .args(&runtime.objects);
+    /* libraries go after the objects that use them */
+    for dir in &cli.lib_dirs {
+        cc.arg("-L").arg(dir);
+    }
+    for lib in &cli.libs {
+        cc.arg("-l").arg(lib);
+    }
+    let status = cc.output();
+    if !cli.keepc {
+        build.clean(&compilation.files);
+    }
+    match status {
+        Ok(out) if out.status.success() => {}
+        Ok(out) => {
+            eprintln!(
+                "{} The C compiler ({}) failed ({}):\n{}",
+                "[Tixie Error] :".red().bold(),
+                cli.cc,
+                out.status,
+                String::from_utf8_lossy(&out.stderr).trim_end()
             );
-            cgen.init();
-            cgen.generate(cgen.source.clone());
-            // exit(1);
-            cgen.rest();
-            let mut gcc = Command::new("cc")
-                .arg(cgen.outfilename)
-                .arg("-o")
-                .arg(cli.output.unwrap().to_string().clone())
-                .arg("/home/dry/Documents/Eggo/jaguar/std/claw.o")
-                .arg("/home/dry/Documents/Eggo/jaguar/std/stdjr.o")
-                .arg("-no-pie")
-                .arg("-w")
-                .status();
             if !cli.keepc {
-                std::fs::remove_dir_all(cgen.buildpath.to_str().unwrap()).unwrap();
+                /* the C goes elsewhere once it is kept */
+                let kept = Cli {
+                    keepc: true,
+                    ..cli.clone()
+                };
+                eprintln!(
+                    " help : pass --keepc to keep the generated C in {}",
+                    BuildDir::for_cli(&kept).path.display()
+                );
             }
+            exit(1);
         }
-    };
+        Err(e) => {
+            eprintln!(
+                "{} Could not run the C compiler '{}': {e}",
+                "[Tixie Error] :".red().bold(),
+                cli.cc
+            );
+            exit(1);
+        }
+    }
 }
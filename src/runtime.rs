@@ -0,0 +1,178 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/* files the runtime directory is expected to provide */
+const RUNTIME_HEADER: &str = "claw.h";
+const RUNTIME_SOURCE: &str = "claw.c";
+const RUNTIME_ASM: &str = "trn0/stdio.asm";
+/* object names produced by the makefile, reused when present */
+const CLAW_OBJECT: &str = "claw.o";
+const STDJR_OBJECT: &str = "stdjr.o";
+
+/// The Jaguar runtime (claw) a program is compiled and linked against.
+#[derive(Debug, Clone)]
+pub struct Runtime {
+    /// Directory holding `claw.h`, passed to the C compiler as an include path.
+    pub dir: PathBuf,
+    /// Runtime objects to hand to the linker.
+    pub objects: Vec<PathBuf>,
+}
+
+impl Runtime {
+    /// Finds the runtime directory and makes sure its objects are built.
+    ///
+    /// Lookup order: the `--runtime-dir` flag, `$JAGUAR_HOME/std`, then a
+    /// `std/` directory next to (or above) the jagc executable. Missing C
+    /// objects are built with the C compiler `cc`.
+    pub fn discover(flag: Option<&str>, cc: &str) -> Result<Runtime, String> {
+        let dir = locate(flag)?;
+        let objects = vec![
+            ensure_object(
+                &dir,
+                cc,
+                &[RUNTIME_SOURCE, RUNTIME_HEADER],
+                CLAW_OBJECT,
+                build_claw,
+            )?,
+            ensure_object(&dir, cc, &[RUNTIME_ASM], STDJR_OBJECT, build_stdjr)?,
+        ];
+        Ok(Runtime { dir, objects })
+    }
+}
+
+fn is_runtime_dir(dir: &Path) -> bool {
+    dir.join(RUNTIME_HEADER).is_file()
+}
+
+fn locate(flag: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(dir) = flag {
+        let dir = PathBuf::from(dir);
+        if !is_runtime_dir(&dir) {
+            return Err(format!(
+                "'{}' is not a Jaguar runtime directory (missing {RUNTIME_HEADER})",
+                dir.display()
+            ));
+        }
+        return Ok(dir);
+    }
+    if let Ok(home) = std::env::var("JAGUAR_HOME") {
+        let dir = Path::new(&home).join("std");
+        if !is_runtime_dir(&dir) {
+            return Err(format!(
+                "JAGUAR_HOME is set but '{}' does not contain {RUNTIME_HEADER}",
+                dir.display()
+            ));
+        }
+        return Ok(dir);
+    }
+    /* installed next to the binary, or running out of a cargo target directory */
+    if let Ok(exe) = std::env::current_exe() {
+        let exe = std::fs::canonicalize(&exe).unwrap_or(exe);
+        for ancestor in exe.ancestors().skip(1) {
+            let dir = ancestor.join("std");
+            if is_runtime_dir(&dir) {
+                return Ok(dir);
+            }
+        }
+    }
+    Err(format!(
+        "Could not find the Jaguar runtime ({RUNTIME_HEADER}). Pass --runtime-dir or set JAGUAR_HOME"
+    ))
+}
+
+/// Returns `object` from the runtime directory when it is up to date with `sources`,
+/// otherwise builds it from the first of them into the runtime cache. The rest are
+/// headers that one includes.
+fn ensure_object(
+    dir: &Path,
+    cc: &str,
+    sources: &[&str],
+    object: &str,
+    build: fn(&str, &Path, &Path, &Path) -> Result<(), String>,
+) -> Result<PathBuf, String> {
+    let sources: Vec<PathBuf> = sources.iter().map(|s| dir.join(s)).collect();
+    let source = &sources[0];
+    let prebuilt = dir.join(object);
+    if is_fresh(&prebuilt, &sources) {
+        return Ok(prebuilt);
+    }
+    let cached = cache_dir(dir)?.join(object);
+    if is_fresh(&cached, &sources) {
+        return Ok(cached);
+    }
+    if !source.is_file() {
+        return Err(format!("Runtime source '{}' is missing", source.display()));
+    }
+    /* build under a private name first so concurrent jagc runs never link a half-written object */
+    let partial = cached.with_extension(format!("o.{}", std::process::id()));
+    build(cc, dir, source, &partial)?;
+    std::fs::rename(&partial, &cached)
+        .map_err(|e| format!("Could not store '{}': {e}", cached.display()))?;
+    Ok(cached)
+}
+
+/// Whether `object` exists and is no older than any of `sources`.
+fn is_fresh(object: &Path, sources: &[PathBuf]) -> bool {
+    let Ok(obj) = std::fs::metadata(object).and_then(|m| m.modified()) else {
+        return false;
+    };
+    sources.iter().all(
+        |source| match std::fs::metadata(source).and_then(|m| m.modified()) {
+            Ok(src) => obj >= src,
+            /* no source to compare against, trust the object */
+            Err(_) => true,
+        },
+    )
+}
+
+fn cache_dir(runtime: &Path) -> Result<PathBuf, String> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+    let mut hasher = DefaultHasher::new();
+    std::fs::canonicalize(runtime)
+        .unwrap_or(runtime.to_path_buf())
+        .hash(&mut hasher);
+    let dir = base
+        .join("jaguar")
+        .join(format!("runtime-{:016x}", hasher.finish()));
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Could not create runtime cache '{}': {e}", dir.display()))?;
+    Ok(dir)
+}
+
+fn build_claw(compiler: &str, dir: &Path, source: &Path, output: &Path) -> Result<(), String> {
+    let mut cc = Command::new(compiler);
+    cc.arg("-c")
+        .arg(source)
+        .arg("-I")
+        .arg(dir)
+        .arg("-o")
+        .arg(output)
+        .arg("-w");
+    run_tool(cc, compiler)
+}
+
+fn build_stdjr(_cc: &str, _dir: &Path, source: &Path, output: &Path) -> Result<(), String> {
+    let mut nasm = Command::new("nasm");
+    nasm.arg("-f")
+        .arg("elf64")
+        .arg(source)
+        .arg("-o")
+        .arg(output);
+    run_tool(nasm, "nasm")
+}
+
+fn run_tool(mut cmd: Command, tool: &str) -> Result<(), String> {
+    match cmd.output() {
+        Ok(out) if out.status.success() => Ok(()),
+        Ok(out) => Err(format!(
+            "Building the runtime with {tool} failed:\n{}",
+            String::from_utf8_lossy(&out.stderr)
+        )),
+        Err(e) => Err(format!("Could not run {tool} to build the runtime: {e}")),
+    }
+}
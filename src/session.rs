@@ -0,0 +1,154 @@
+use std::cell::{Ref, RefCell};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::backend::codegen::{GeneratedFile, Generator};
+use crate::backend::compile_error::{CompileError, ErrorFormat, report};
+use crate::backend::parser::{Node, Parser, Spanned};
+use crate::lexer::{Token, Tokenizer};
+use crate::source_map::SourceMap;
+
+/// Where a [`Session`] places the C it generates, and how strictly it checks the source.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Directory the generated files are meant to be written to. Bundle headers
+    /// are `#include`d from here, so the C only compiles once written there.
+    pub build_dir: PathBuf,
+    /// File name (without `.c`) of the main C file.
+    pub output: String,
+    /// Accept integer conditions in if/while/for with a warning instead of an error.
+    pub int_conditions: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            build_dir: PathBuf::from("build"),
+            output: "out".into(),
+            int_conditions: false,
+        }
+    }
+}
+
+/// Everything one call to [`Session::compile`] or [`Session::parse`] produced.
+#[derive(Debug, Clone)]
+pub struct Compilation {
+    pub tokens: Vec<Token>,
+    pub ast: Vec<Spanned<Node>>,
+    /// Main C file first, then one header per bundle. Empty if parsing failed, or after
+    /// [`Session::parse`].
+    pub files: Vec<GeneratedFile>,
+    pub diagnostics: Vec<CompileError>,
+}
+
+impl Compilation {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.is_error())
+    }
+
+    /// The generated C for the main file.
+    pub fn c_source(&self) -> Option<&str> {
+        self.files.first().map(|f| f.contents.as_str())
+    }
+
+    /// Writes every generated file to its path.
+    pub fn write(&self) -> std::io::Result<()> {
+        for file in &self.files {
+            file.write()?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the lexer, parser and code generator without touching the disk
+/// (bundles are still read from it) or exiting the process.
+pub struct Session {
+    sources: Rc<RefCell<SourceMap>>,
+    options: Options,
+}
+
+impl Session {
+    pub fn new(options: Options) -> Self {
+        Self {
+            sources: Rc::new(RefCell::new(SourceMap::new())),
+            options,
+        }
+    }
+
+    /// Lexes and parses `source` and stops there, without generating any C. `path` names
+    /// it in diagnostics.
+    pub fn parse(&mut self, path: &str, source: &str) -> Compilation {
+        let file = self
+            .sources
+            .borrow_mut()
+            .add(path.to_string(), source.to_string());
+        let mut tokenizer = Tokenizer::new(source, file);
+        let tokens = tokenizer.tokenize();
+        let mut parser = Parser::new(tokens.clone(), source.to_string());
+        let ast = parser.parse_program();
+        let mut compilation = Compilation {
+            tokens,
+            ast,
+            files: vec![],
+            diagnostics: tokenizer.diagnostics().to_vec(),
+        };
+        compilation
+            .diagnostics
+            .extend_from_slice(parser.diagnostics());
+        compilation
+    }
+
+    /// Compiles `source`. `path` names it in diagnostics and is where `bundle`
+    /// statements are resolved from.
+    pub fn compile(&mut self, path: &str, source: &str) -> Compilation {
+        let mut compilation = self.parse(path, source);
+        if compilation.has_errors() {
+            return compilation;
+        }
+
+        let build_dir = self.options.build_dir.to_string_lossy().to_string();
+        let canonical = std::fs::canonicalize(path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or(path.to_string());
+        let mut cgen = Generator::new(
+            compilation.ast.clone(),
+            &format!("{build_dir}/{}", self.options.output),
+            self.sources.clone(),
+            false,
+            path.to_string(),
+            canonical,
+            build_dir,
+        );
+        cgen.int_conditions = self.options.int_conditions;
+        cgen.init();
+        cgen.generate(cgen.source.clone());
+        cgen.rest();
+        compilation
+            .diagnostics
+            .extend_from_slice(cgen.diagnostics());
+        compilation.files = cgen.artifacts().to_vec();
+        compilation
+    }
+
+    /// Reads `path` and compiles it.
+    pub fn compile_file(&mut self, path: &Path) -> std::io::Result<Compilation> {
+        let source = std::fs::read_to_string(path)?;
+        Ok(self.compile(&path.to_string_lossy(), &source))
+    }
+
+    /// Reads `path` and parses it.
+    pub fn parse_file(&mut self, path: &Path) -> std::io::Result<Compilation> {
+        let source = std::fs::read_to_string(path)?;
+        Ok(self.parse(&path.to_string_lossy(), &source))
+    }
+
+    /// Every file loaded so far, for rendering diagnostics.
+    pub fn sources(&self) -> Ref<'_, SourceMap> {
+        self.sources.borrow()
+    }
+
+    /// Prints `diagnostics` in source order. Returns true if any of them is an error.
+    pub fn report(&self, diagnostics: &mut [CompileError], format: ErrorFormat) -> bool {
+        report(diagnostics, &self.sources.borrow(), format)
+    }
+}
@@ -70,9 +70,18 @@ void __panic(jaguar_str errmsg) {
  * asser_eq(foo, "Hello World");
  */
 extern jaguar_str jformat(jaguar_str fmt, ...) {
-  char buf[1024];
   va_list args;
   va_start(args, fmt);
+  jaguar_str formatted = vjformat(fmt, args);
+  va_end(args);
+  return formatted;
+}
+/*
+ * vjformat:
+ *    jformat for callers that already hold a va_list, such as jprintln
+ */
+extern jaguar_str vjformat(jaguar_str fmt, va_list args) {
+  char buf[1024];
   int len = 0;
   for (int i = 0; fmt[i]; i++) {
     if (fmt[i] == '{') {
@@ -90,6 +99,11 @@ extern jaguar_str jformat(jaguar_str fmt, ...) {
         jaguar_int arg = va_arg(args, jaguar_int);
         len += snprintf(buf + len, sizeof(buf) - len, "%ld", arg);
         i += 1;
+      } else if (fmt[i] == 'f' && fmt[i + 1] == '}') {
+        /* f32 arguments are promoted to double when passed through ... */
+        double arg = va_arg(args, double);
+        len += snprintf(buf + len, sizeof(buf) - len, "%g", arg);
+        i += 1;
       } else if (fmt[i] == 'p' && fmt[i + 1] == '}') {
         void *arg = va_arg(args, void *);
         len += snprintf(buf + len, sizeof(buf) - len, "%p", arg);
@@ -106,7 +120,6 @@ extern jaguar_str jformat(jaguar_str fmt, ...) {
     }
   }
   buf[len] = '\0';
-  va_end(args);
   return strdup(buf);
 }
 /*
@@ -115,7 +128,10 @@ extern jaguar_str jformat(jaguar_str fmt, ...) {
  * newline
  */
 void jprintln(jaguar_str fmt, ...) {
-  jaguar_str p = jformat(fmt);
+  va_list args;
+  va_start(args, fmt);
+  jaguar_str p = vjformat(fmt, args);
+  va_end(args);
   println(p);
 }
 extern jaguar_str jinput(jaguar_str prompt) {
@@ -129,6 +145,25 @@ void panic(jaguar_str errmsg, jaguar_int LINE) {
   println(errmsg);
   jaguar_rest(100);
 }
+/*
+ * panic with the full location, meant to be called with the intrinsics:
+ * panic_at("msg", JFILE, JLINE, JCOLUMN, JFUNC, JBUNDLE);
+ * func and bundle are left out when empty.
+ */
+void panic_at(jaguar_str errmsg, jaguar_str file, jaguar_i32 line,
+              jaguar_i32 column, jaguar_str func, jaguar_str bundle) {
+  print(jformat("[Tixie Panicked][{s}:{d}:{d}", file, (jaguar_int)line,
+                (jaguar_int)column));
+  if (func[0] != '\0') {
+    print(jformat(" in {s}", func));
+  }
+  if (bundle[0] != '\0') {
+    print(jformat(" of bundle {s}", bundle));
+  }
+  print("]: ");
+  println(errmsg);
+  jaguar_rest(100);
+}
 void write_ch(char c) {
   jaguar_str d = &c;
   println(d);
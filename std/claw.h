@@ -3,6 +3,7 @@
 #pragma once
 #include "stdlib.h"
 // compiler handled type
+#include <stdarg.h>
 #include <stdint.h>
 typedef signed long jaguar_int;
 typedef int jaguar_i32;
@@ -16,9 +17,13 @@ typedef int32_t jaguar_i32;
 typedef int64_t jaguar_i64;
 typedef char *jaguar_str;
 typedef float jaguar_float;
+typedef float jaguar_f32;
+typedef double jaguar_f64;
 extern void print(jaguar_str msg);
 extern void println(jaguar_str msg);
 void panic(jaguar_str errmsg, jaguar_i64 LINE);
+void panic_at(jaguar_str errmsg, jaguar_str file, jaguar_i32 line,
+              jaguar_i32 column, jaguar_str func, jaguar_str bundle);
 extern void jaguar_rest(jaguar_int err_code);
 extern void write_int(jaguar_i32 i);
 extern void *mem_get(jaguar_int bytes);
@@ -26,6 +31,7 @@ extern jaguar_int str_len(const char *);
 extern char char_to_upper(char *self);
 extern char char_to_lower(char *self);
 extern jaguar_str jformat(jaguar_str fmt, ...);
+extern jaguar_str vjformat(jaguar_str fmt, va_list args);
 extern int str_eq(jaguar_str s1, jaguar_str s2);
 extern jaguar_str jinput(jaguar_str prompt);
 void claw_itoa(jaguar_int i, char *buf);
@@ -41,6 +47,9 @@ void write_ch(char c);
     T data[N];                                                                 \
     jaguar_int len;                                                            \
   } jaguar_list_##T;
+// runtime checks of generated code, jagc defines JAGUAR_NO_CHECKS to drop them
+// (--release, or --checks=off)
+#ifndef JAGUAR_NO_CHECKS
 #define jaguar_bounds_check(list, N)                                           \
   if (N >= list.len) {                                                         \
     panic("Tixie runtime: Out of bounds error");                               \
@@ -56,6 +65,10 @@ void write_ch(char c);
     }                                                                          \
     _lst.data[_idx];                                                           \
   })
+#else
+#define jaguar_bounds_check(list, N)
+#define jaguar_list_at(list, N) ((list).data[(N)])
+#endif
 #define jaguar_deftype(T) static const char *jaguar_type_##T = #T;
 #define jaguar_str_at(str, N)                                                  \
   ({                                                                           \
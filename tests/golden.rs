@@ -0,0 +1,215 @@
+//! Golden-file tests for the programs under `tests/`.
+//!
+//! Every `tests/<name>.jr` that has a `tests/out/<name>.stdout` is compiled with jagc,
+//! linked against the runtime and run. Its stdout must match `<name>.stdout` and its exit
+//! code must match `tests/out/<name>.status` (0 when absent). When `tests/out/<name>.stdin`
+//! exists it is fed to the program. A runtime that cannot be built fails the test, unless
+//! `JAGUAR_SKIP_GOLDEN` is set.
+//!
+//! Every `tests/fail/<name>.jr` must fail to compile. A `// error: <text>` comment expects an
+//! error on that line whose message contains `<text>`; any other error fails the test.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use jagc::runtime::Runtime;
+use jagc::session::{Options, Session};
+
+const ERROR_MARKER: &str = "// error: ";
+/// Set to skip the golden programs, for machines that cannot build the runtime.
+const SKIP_PROGRAMS: &str = "JAGUAR_SKIP_GOLDEN";
+
+fn tests_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+/// The `.jr` files directly inside `dir`, in name order.
+fn jaguar_sources(dir: &Path) -> Vec<PathBuf> {
+    let mut sources: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("Could not read {}: {e}", dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jr"))
+        .collect();
+    sources.sort();
+    sources
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem().unwrap().to_string_lossy().into_owned()
+}
+
+#[test]
+fn programs_match_golden_output() {
+    /* a missing runtime fails the test, skipping it has to be asked for */
+    if std::env::var_os(SKIP_PROGRAMS).is_some_and(|v| !v.is_empty()) {
+        eprintln!("skipping golden programs, {SKIP_PROGRAMS} is set");
+        return;
+    }
+    let runtime = Runtime::discover(None, "cc").unwrap_or_else(|e| {
+        panic!("The runtime is unavailable: {e}\nSet {SKIP_PROGRAMS}=1 to skip the golden programs")
+    });
+    let out_dir = tests_dir().join("out");
+    let work = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let _ = fs::remove_dir_all(&work);
+
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    for source in jaguar_sources(&tests_dir()) {
+        let name = stem(&source);
+        let Ok(stdout) = fs::read_to_string(out_dir.join(format!("{name}.stdout"))) else {
+            continue;
+        };
+        checked += 1;
+        if let Err(e) = run_golden(&source, &name, &stdout, &out_dir, &runtime, &work) {
+            failures.push(format!("{name}: {e}"));
+        }
+    }
+    assert!(checked > 0, "no golden programs found");
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+fn run_golden(
+    source: &Path,
+    name: &str,
+    expected_stdout: &str,
+    out_dir: &Path,
+    runtime: &Runtime,
+    work: &Path,
+) -> Result<(), String> {
+    /* every program gets its own directory to be built and run in */
+    let dir = work.join(name);
+    fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {e}", dir.display()))?;
+    let jagc = Command::new(env!("CARGO_BIN_EXE_jagc"))
+        .arg(source)
+        .arg("-o")
+        .arg(name)
+        .arg("--runtime-dir")
+        .arg(&runtime.dir)
+        .current_dir(&dir)
+        .output()
+        .map_err(|e| format!("Could not run jagc: {e}"))?;
+    let binary = dir.join(name);
+    if !jagc.status.success() || !binary.is_file() {
+        return Err(format!(
+            "compilation failed\n{}",
+            String::from_utf8_lossy(&jagc.stderr)
+        ));
+    }
+
+    let stdin = fs::read(out_dir.join(format!("{name}.stdin"))).ok();
+    let mut child = Command::new(&binary)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run {}: {e}", binary.display()))?;
+    if let Some(input) = stdin {
+        use std::io::Write;
+        /* the program may exit without reading everything */
+        let _ = child.stdin.take().unwrap().write_all(&input);
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Could not wait for {}: {e}", binary.display()))?;
+
+    let expected_status: i32 = match fs::read_to_string(out_dir.join(format!("{name}.status"))) {
+        Ok(status) => status
+            .trim()
+            .parse()
+            .map_err(|e| format!("Invalid {name}.status: {e}"))?,
+        Err(_) => 0,
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut problems = Vec::new();
+    if stdout != expected_stdout {
+        problems.push(format!(
+            "stdout differs\n--- expected\n{expected_stdout}\n--- actual\n{stdout}"
+        ));
+    }
+    if output.status.code() != Some(expected_status) {
+        problems.push(format!(
+            "exit status differs: expected {expected_status}, got {}",
+            output.status
+        ));
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("\n"))
+    }
+}
+
+#[test]
+fn failing_programs_report_expected_errors() {
+    let sources = jaguar_sources(&tests_dir().join("fail"));
+    assert!(!sources.is_empty(), "no compile-failure tests found");
+
+    let mut failures = Vec::new();
+    for source in sources {
+        if let Err(e) = check_errors(&source) {
+            failures.push(format!("{}: {e}", stem(&source)));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+fn check_errors(source: &Path) -> Result<(), String> {
+    let text = fs::read_to_string(source).map_err(|e| e.to_string())?;
+    let mut expected: Vec<(usize, String)> = text
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let (_, message) = line.split_once(ERROR_MARKER)?;
+            Some((i + 1, message.trim().to_string()))
+        })
+        .collect();
+    if expected.is_empty() {
+        return Err(format!("no '{ERROR_MARKER}' annotations"));
+    }
+
+    let mut session = Session::new(Options::default());
+    let compilation = session
+        .compile_file(source)
+        .map_err(|e| format!("Could not read source: {e}"))?;
+    let sources = session.sources();
+    let mut unexpected = Vec::new();
+    for diagnostic in compilation.diagnostics.iter().filter(|d| d.is_error()) {
+        let file = sources.file(&diagnostic.span);
+        let line = file.source[..diagnostic.span.start.min(file.source.len())]
+            .matches('\n')
+            .count()
+            + 1;
+        let matched = (file.name == source.to_string_lossy())
+            .then(|| {
+                expected
+                    .iter()
+                    .position(|(l, m)| *l == line && diagnostic.errmsg.contains(m.as_str()))
+            })
+            .flatten();
+        match matched {
+            Some(i) => {
+                expected.remove(i);
+            }
+            None => unexpected.push(format!("{}:{line}: {}", file.name, diagnostic.errmsg)),
+        }
+    }
+
+    let mut problems: Vec<String> = expected
+        .iter()
+        .map(|(line, message)| format!("missing error on line {line}: {message}"))
+        .collect();
+    problems.extend(
+        unexpected
+            .into_iter()
+            .map(|e| format!("unexpected error {e}")),
+    );
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("\n"))
+    }
+}